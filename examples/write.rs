@@ -1,4 +1,4 @@
-use clap::{Parser, ValueEnum};
+use clap::Parser;
 use log::LevelFilter;
 
 /// Example of writing a nitf file
@@ -0,0 +1,243 @@
+//! Attribute state and primitive geometry shared by the CGM renderers
+use std::f64::consts::TAU;
+
+use super::{ArcClosure, Color, Element, InteriorStyle, LineType, Point};
+
+/// Number of line segments used for a full ellipse
+const ELLIPSE_SEGMENTS: usize = 72;
+
+/// Current attributes while walking an element list
+#[derive(Debug, Clone)]
+pub(super) struct Attributes {
+    pub line_type: LineType,
+    pub line_width: f64,
+    pub line_color: Color,
+    pub text_color: Color,
+    pub character_height: i32,
+    pub interior_style: InteriorStyle,
+    pub fill_color: Color,
+    pub edge_type: LineType,
+    pub edge_width: f64,
+    pub edge_color: Color,
+    pub edge_visible: bool,
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        let black = Color::Rgb(0, 0, 0);
+        Self {
+            line_type: LineType::Solid,
+            line_width: 1.0,
+            line_color: black,
+            text_color: black,
+            character_height: 10,
+            interior_style: InteriorStyle::Hollow,
+            fill_color: black,
+            edge_type: LineType::Solid,
+            edge_width: 1.0,
+            edge_color: black,
+            edge_visible: false,
+        }
+    }
+}
+
+/// Stroke color, width, and type
+pub(super) type Stroke = (Color, f64, LineType);
+
+impl Attributes {
+    /// Apply an attribute element. Other elements are ignored.
+    pub fn apply(&mut self, element: &Element) {
+        use Element::*;
+        match element {
+            LineType(line_type) => self.line_type = *line_type,
+            LineWidth(width) => self.line_width = *width,
+            LineColor(color) => self.line_color = *color,
+            TextColor(color) => self.text_color = *color,
+            CharacterHeight(height) => self.character_height = *height,
+            InteriorStyle(style) => self.interior_style = *style,
+            FillColor(color) => self.fill_color = *color,
+            EdgeType(line_type) => self.edge_type = *line_type,
+            EdgeWidth(width) => self.edge_width = *width,
+            EdgeColor(color) => self.edge_color = *color,
+            EdgeVisibility(visible) => self.edge_visible = *visible,
+            _ => {}
+        }
+    }
+
+    /// Stroke of open primitives
+    pub fn line(&self) -> Stroke {
+        (self.line_color, self.line_width, self.line_type)
+    }
+
+    /// Interior color of closed primitives
+    pub fn fill(&self) -> Option<Color> {
+        match self.interior_style {
+            InteriorStyle::Hollow | InteriorStyle::Empty => None,
+            _ => Some(self.fill_color),
+        }
+    }
+
+    /// Boundary stroke of closed primitives. A hollow interior is drawn as its
+    /// boundary in the fill color.
+    pub fn edge(&self) -> Option<Stroke> {
+        if self.edge_visible {
+            Some((self.edge_color, self.edge_width, self.edge_type))
+        } else if self.interior_style == InteriorStyle::Hollow {
+            Some((self.fill_color, 1.0, LineType::Solid))
+        } else {
+            None
+        }
+    }
+}
+
+/// Red, green, and blue of a color. Without a color table, index 0 is white
+/// and other indices are black. Monochrome graphics are drawn in gray levels.
+pub(super) fn rgb(color: Color, monochrome: bool) -> [u8; 3] {
+    let [r, g, b] = match color {
+        Color::Rgb(r, g, b) => [r, g, b],
+        Color::Indexed(0) => [255; 3],
+        Color::Indexed(_) => [0; 3],
+    };
+    if monochrome {
+        let gray = (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64).round() as u8;
+        [gray; 3]
+    } else {
+        [r, g, b]
+    }
+}
+
+/// Alternating dash and gap lengths of a line type, or `None` for solid
+pub(super) fn dash_pattern(line_type: LineType, width: f64) -> Option<Vec<f64>> {
+    let w = width.max(1.0);
+    match line_type {
+        LineType::Dash => Some(vec![4.0 * w, 2.0 * w]),
+        LineType::Dot => Some(vec![w, 2.0 * w]),
+        LineType::DashDot => Some(vec![4.0 * w, 2.0 * w, w, 2.0 * w]),
+        LineType::DashDotDot => Some(vec![4.0 * w, 2.0 * w, w, 2.0 * w, w, 2.0 * w]),
+        LineType::Solid | LineType::Other(_) => None,
+    }
+}
+
+/// Points along an elliptical arc with conjugate diameters `u` and `v`, from
+/// direction `start` counterclockwise to direction `end`
+pub(super) fn arc_points(
+    center: Point,
+    u: (f64, f64),
+    v: (f64, f64),
+    start: Point,
+    end: Point,
+) -> Vec<(f64, f64)> {
+    let det = u.0 * v.1 - u.1 * v.0;
+    if det == 0.0 {
+        return vec![];
+    }
+    // Parametric angle of a direction
+    let angle = |d: Point| {
+        let (dx, dy) = (d.x as f64, d.y as f64);
+        let a = (v.1 * dx - v.0 * dy) / det;
+        let b = (u.0 * dy - u.1 * dx) / det;
+        b.atan2(a)
+    };
+    let t0 = angle(start);
+    let mut t1 = angle(end);
+    // Counterclockwise in VDC is increasing t when u to v is counterclockwise
+    if det > 0.0 {
+        while t1 <= t0 {
+            t1 += TAU;
+        }
+    } else {
+        while t1 >= t0 {
+            t1 -= TAU;
+        }
+    }
+    let n = (((t1 - t0).abs() / TAU * ELLIPSE_SEGMENTS as f64).ceil() as usize).max(1);
+    (0..=n)
+        .map(|i| {
+            let t = t0 + (t1 - t0) * i as f64 / n as f64;
+            (
+                center.x as f64 + u.0 * t.cos() + v.0 * t.sin(),
+                center.y as f64 + u.1 * t.cos() + v.1 * t.sin(),
+            )
+        })
+        .collect()
+}
+
+/// Outline of a graphical primitive in VDC, and whether it is closed. `None`
+/// for text and non-primitive elements.
+pub(super) fn outline(element: &Element) -> Option<(Vec<(f64, f64)>, bool)> {
+    use Element::*;
+    let vdc = |p: &Point| (p.x as f64, p.y as f64);
+    let conjugates = |center: &Point, cdp1: &Point, cdp2: &Point| {
+        (vdc(&(*cdp1 - *center)), vdc(&(*cdp2 - *center)))
+    };
+    let close = |center: &Point, mut points: Vec<(f64, f64)>, closure: &ArcClosure| {
+        if *closure == ArcClosure::Pie {
+            points.push(vdc(center));
+        }
+        (points, true)
+    };
+    Some(match element {
+        Polyline(points) => (points.iter().map(vdc).collect(), false),
+        Polygon(points) => (points.iter().map(vdc).collect(), true),
+        Rectangle(p1, p2) => {
+            let (x1, y1, x2, y2) = (p1.x as f64, p1.y as f64, p2.x as f64, p2.y as f64);
+            (vec![(x1, y1), (x2, y1), (x2, y2), (x1, y2)], true)
+        }
+        Circle { center, radius } => {
+            let r = *radius as f64;
+            let start = Point::new(1, 0);
+            let mut points = arc_points(*center, (r, 0.0), (0.0, r), start, start);
+            points.pop();
+            (points, true)
+        }
+        CircularArcCenter {
+            center,
+            start,
+            end,
+            radius,
+        } => {
+            let r = *radius as f64;
+            (arc_points(*center, (r, 0.0), (0.0, r), *start, *end), false)
+        }
+        CircularArcCenterClose {
+            center,
+            start,
+            end,
+            radius,
+            closure,
+        } => {
+            let r = *radius as f64;
+            let points = arc_points(*center, (r, 0.0), (0.0, r), *start, *end);
+            close(center, points, closure)
+        }
+        Ellipse { center, cdp1, cdp2 } => {
+            let (u, v) = conjugates(center, cdp1, cdp2);
+            let start = *cdp1 - *center;
+            let mut points = arc_points(*center, u, v, start, start);
+            points.pop();
+            (points, true)
+        }
+        EllipticalArc {
+            center,
+            cdp1,
+            cdp2,
+            start,
+            end,
+        } => {
+            let (u, v) = conjugates(center, cdp1, cdp2);
+            (arc_points(*center, u, v, *start, *end), false)
+        }
+        EllipticalArcClose {
+            center,
+            cdp1,
+            cdp2,
+            start,
+            end,
+            closure,
+        } => {
+            let (u, v) = conjugates(center, cdp1, cdp2);
+            close(center, arc_points(*center, u, v, *start, *end), closure)
+        }
+        _ => return None,
+    })
+}
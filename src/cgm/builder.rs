@@ -0,0 +1,292 @@
+//! Builder for annotation graphics
+use super::attributes::Attributes;
+use super::{encode, Color, Element, InteriorStyle, LineType, Point};
+use crate::headers::graphic_hdr;
+use crate::types::RowColumn;
+use crate::{GraphicSegment, NitfResult};
+
+/// METAFILE ELEMENT LIST parameters naming the drawing-plus set
+const DRAWING_PLUS: [u8; 6] = [0x00, 0x01, 0xFF, 0xFF, 0x00, 0x01];
+
+/// Assembles annotations into a NITF profile CGM and graphic segment.
+///
+/// Points are CGM virtual device coordinates relative to the graphic
+/// location, see [Point::from_pixel()]. Attributes apply to the primitives
+/// added after them.
+/// ```
+/// use nitf_rs::cgm::{CgmBuilder, Color, Point};
+/// use nitf_rs::types::RowColumn;
+///
+/// let mut builder = CgmBuilder::new("annotation");
+/// let red = Color::Rgb(255, 0, 0);
+/// builder
+///     .fill(None)
+///     .edge(Some((red, 2.0)))
+///     .rectangle(Point::from_pixel(0, 0), Point::from_pixel(20, 40))
+///     .line_color(red)
+///     .arrow(Point::from_pixel(40, 60), Point::from_pixel(20, 40), 6)
+///     .text(Point::from_pixel(50, 0), "Target");
+/// let location = RowColumn::new(100, 200);
+/// let (segment, data) = builder.build(location, 2, 1).unwrap();
+/// assert_eq!(segment.data_size, data.len() as u64);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CgmBuilder {
+    name: String,
+    body: Vec<Element>,
+    /// Lower left and upper right of everything drawn
+    bounds: Option<(Point, Point)>,
+    /// Whether anything is drawn in a color other than gray
+    color: bool,
+    attributes: Attributes,
+}
+
+impl CgmBuilder {
+    /// Empty metafile and picture named `name`
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            body: vec![],
+            bounds: None,
+            color: false,
+            attributes: Attributes::default(),
+        }
+    }
+
+    /// Add an element to the picture body, tracking attributes and bounds
+    pub fn element(&mut self, element: Element) -> &mut Self {
+        use Element::*;
+        self.attributes.apply(&element);
+        let Attributes {
+            line_width,
+            line_color,
+            text_color,
+            character_height,
+            edge_width,
+            ..
+        } = self.attributes;
+        match &element {
+            Polyline(points) => {
+                self.use_color(line_color);
+                self.extend(points, line_width);
+            }
+            Polygon(points) => {
+                self.use_fill_colors();
+                self.extend(points, edge_width);
+            }
+            Rectangle(p1, p2) => {
+                self.use_fill_colors();
+                self.extend(&[*p1, *p2], edge_width);
+            }
+            Circle { center, radius }
+            | CircularArcCenter { center, radius, .. }
+            | CircularArcCenterClose { center, radius, .. } => {
+                if matches!(element, CircularArcCenter { .. }) {
+                    self.use_color(line_color);
+                } else {
+                    self.use_fill_colors();
+                }
+                let r = Point::new(*radius, *radius);
+                self.extend(&[*center - r, *center + r], edge_width);
+            }
+            Ellipse { center, cdp1, cdp2 }
+            | EllipticalArc {
+                center, cdp1, cdp2, ..
+            }
+            | EllipticalArcClose {
+                center, cdp1, cdp2, ..
+            } => {
+                if matches!(element, EllipticalArc { .. }) {
+                    self.use_color(line_color);
+                } else {
+                    self.use_fill_colors();
+                }
+                // Half extents of the ellipse through the conjugate diameters
+                let (u, v) = (*cdp1 - *center, *cdp2 - *center);
+                let half = |a: i32, b: i32| (a as f64).hypot(b as f64).ceil() as i32;
+                let r = Point::new(half(u.x, v.x), half(u.y, v.y));
+                self.extend(&[*center - r, *center + r], edge_width);
+            }
+            Text { position, text, .. } => {
+                self.use_color(text_color);
+                // Characters are assumed to be no wider than they are tall
+                let width = character_height * text.chars().count() as i32;
+                let corner = *position + Point::new(width, character_height);
+                self.extend(&[*position, corner], 0.0);
+            }
+            _ => {}
+        }
+        self.body.push(element);
+        self
+    }
+
+    fn use_color(&mut self, color: Color) {
+        self.color |= match color {
+            Color::Rgb(r, g, b) => r != g || g != b,
+            Color::Indexed(_) => false,
+        };
+    }
+
+    fn use_fill_colors(&mut self) {
+        if self.attributes.interior_style != InteriorStyle::Empty {
+            self.use_color(self.attributes.fill_color);
+        }
+        if self.attributes.edge_visible {
+            self.use_color(self.attributes.edge_color);
+        }
+    }
+
+    /// Grow the bounds to include `points`, drawn with `width`
+    fn extend(&mut self, points: &[Point], width: f64) {
+        let margin = (width / 2.0).ceil() as i32;
+        for point in points {
+            let (low, high) = self.bounds.get_or_insert((*point, *point));
+            low.x = low.x.min(point.x - margin);
+            low.y = low.y.min(point.y - margin);
+            high.x = high.x.max(point.x + margin);
+            high.y = high.y.max(point.y + margin);
+        }
+    }
+
+    pub fn line_type(&mut self, line_type: LineType) -> &mut Self {
+        self.element(Element::LineType(line_type))
+    }
+    pub fn line_width(&mut self, width: f64) -> &mut Self {
+        self.element(Element::LineWidth(width))
+    }
+    pub fn line_color(&mut self, color: Color) -> &mut Self {
+        self.element(Element::LineColor(color))
+    }
+    /// Fill closed primitives with `color`, or leave them unfilled
+    pub fn fill(&mut self, color: Option<Color>) -> &mut Self {
+        match color {
+            Some(color) => self
+                .element(Element::InteriorStyle(InteriorStyle::Solid))
+                .element(Element::FillColor(color)),
+            None => self.element(Element::InteriorStyle(InteriorStyle::Empty)),
+        }
+    }
+    /// Outline closed primitives with `color` and `width`, or not at all
+    pub fn edge(&mut self, edge: Option<(Color, f64)>) -> &mut Self {
+        match edge {
+            Some((color, width)) => self
+                .element(Element::EdgeVisibility(true))
+                .element(Element::EdgeColor(color))
+                .element(Element::EdgeWidth(width)),
+            None => self.element(Element::EdgeVisibility(false)),
+        }
+    }
+    pub fn text_color(&mut self, color: Color) -> &mut Self {
+        self.element(Element::TextColor(color))
+    }
+    pub fn character_height(&mut self, height: i32) -> &mut Self {
+        self.element(Element::CharacterHeight(height))
+    }
+
+    pub fn polyline(&mut self, points: &[Point]) -> &mut Self {
+        self.element(Element::Polyline(points.to_vec()))
+    }
+    pub fn polygon(&mut self, points: &[Point]) -> &mut Self {
+        self.element(Element::Polygon(points.to_vec()))
+    }
+    /// Rectangle with opposite corners `p1` and `p2`
+    pub fn rectangle(&mut self, p1: Point, p2: Point) -> &mut Self {
+        self.element(Element::Rectangle(p1, p2))
+    }
+    pub fn circle(&mut self, center: Point, radius: i32) -> &mut Self {
+        self.element(Element::Circle { center, radius })
+    }
+    /// Ellipse with conjugate diameter end points `cdp1` and `cdp2`
+    pub fn ellipse(&mut self, center: Point, cdp1: Point, cdp2: Point) -> &mut Self {
+        self.element(Element::Ellipse { center, cdp1, cdp2 })
+    }
+    /// Line from `tail` to `head`, with barbs of `head_length` at the head
+    pub fn arrow(&mut self, tail: Point, head: Point, head_length: i32) -> &mut Self {
+        let (dx, dy) = ((tail.x - head.x) as f64, (tail.y - head.y) as f64);
+        let length = dx.hypot(dy);
+        if length == 0.0 {
+            return self;
+        }
+        let barb = |angle: f64| {
+            let (sin, cos) = angle.sin_cos();
+            let scale = head_length as f64 / length;
+            Point::new(
+                head.x + ((dx * cos - dy * sin) * scale).round() as i32,
+                head.y + ((dx * sin + dy * cos) * scale).round() as i32,
+            )
+        };
+        let barbs = [
+            barb(-std::f64::consts::FRAC_PI_6),
+            head,
+            barb(std::f64::consts::FRAC_PI_6),
+        ];
+        self.polyline(&[tail, head]).polyline(&barbs)
+    }
+    /// Text with its lower left corner at `position`
+    pub fn text(&mut self, position: Point, text: &str) -> &mut Self {
+        self.element(Element::Text {
+            position,
+            last: true,
+            text: text.to_string(),
+        })
+    }
+
+    /// Complete element list of the metafile
+    pub fn to_elements(&self) -> Vec<Element> {
+        use Element::*;
+        let (low, high) = self.bounds.unwrap_or_default();
+        let mut elements = vec![
+            BeginMetafile(self.name.clone()),
+            MetafileVersion(1),
+            Other {
+                class: 1,
+                id: 11,
+                params: DRAWING_PLUS.to_vec(),
+            },
+            BeginPicture(self.name.clone()),
+            ColorSelectionMode(true),
+            LineWidthSpecificationMode(true),
+            EdgeWidthSpecificationMode(true),
+            VdcExtent(low, high),
+            BeginPictureBody,
+        ];
+        elements.extend(self.body.iter().cloned());
+        elements.extend([EndPicture, EndMetafile]);
+        elements
+    }
+
+    /// Encoded metafile
+    pub fn to_bytes(&self) -> NitfResult<Vec<u8>> {
+        encode(&self.to_elements())
+    }
+
+    /// Create a graphic segment holding the metafile, returning the segment
+    /// and the data to write with
+    /// [NitfSegment::write_data()](crate::types::NitfSegment::write_data).
+    ///
+    /// The graphic is placed at `location` relative to the segment at
+    /// `attachment_level`, or the common coordinate system origin for 0. The
+    /// bounds and color fields are set from the primitives drawn.
+    pub fn build(
+        &self,
+        location: RowColumn,
+        display_level: u16,
+        attachment_level: u16,
+    ) -> NitfResult<(GraphicSegment, Vec<u8>)> {
+        let data = self.to_bytes()?;
+        let (low, high) = self.bounds.unwrap_or_default();
+        let mut seg = GraphicSegment::default();
+        let header = &mut seg.header;
+        header.sdlvl.val = display_level;
+        header.salvl.val = attachment_level;
+        header.sloc.val = location;
+        header.sbnd1.val = location + RowColumn::new(-high.y, low.x);
+        header.sbnd2.val = location + RowColumn::new(-low.y, high.x);
+        header.scolor.val = match self.color {
+            true => graphic_hdr::Color::C,
+            false => graphic_hdr::Color::M,
+        };
+        seg.data_size = data.len() as u64;
+        Ok((seg, data))
+    }
+}
@@ -0,0 +1,483 @@
+//! Binary CGM encoding
+use super::{decode, valid_precision, ArcClosure, Color, Element, RealPrecision, State};
+use crate::{NitfError, NitfResult};
+
+/// Longest parameter partition, kept even so only the last needs padding
+const MAX_PARTITION: usize = 0x7FFE;
+
+/// Writer of the parameters of one element
+struct ParamWriter {
+    bytes: Vec<u8>,
+    state: State,
+}
+
+impl ParamWriter {
+    fn precision(bits: u32) -> NitfResult<()> {
+        match valid_precision(bits) {
+            true => Ok(()),
+            false => Err(NitfError::Value(format!("CGM precision {bits}"))),
+        }
+    }
+
+    fn unsigned(&mut self, value: u32, bits: u32) -> NitfResult<()> {
+        Self::precision(bits)?;
+        if bits < 32 && value >> bits != 0 {
+            Err(NitfError::Value(format!("CGM value {value}")))?
+        }
+        let bytes = value.to_be_bytes();
+        self.bytes
+            .extend_from_slice(&bytes[4 - bits as usize / 8..]);
+        Ok(())
+    }
+
+    fn signed(&mut self, value: i32, bits: u32) -> NitfResult<()> {
+        Self::precision(bits)?;
+        let limit = 1i64 << (bits - 1);
+        if !(-limit..limit).contains(&(value as i64)) {
+            Err(NitfError::Value(format!("CGM value {value}")))?
+        }
+        let mask = u32::MAX >> (32 - bits);
+        self.unsigned(value as u32 & mask, bits)
+    }
+
+    fn int(&mut self, value: i32) -> NitfResult<()> {
+        self.signed(value, self.state.integer_bits)
+    }
+
+    fn index(&mut self, value: i32) -> NitfResult<()> {
+        self.signed(value, self.state.index_bits)
+    }
+
+    fn enumerated(&mut self, value: i16) -> NitfResult<()> {
+        self.signed(value.into(), 16)
+    }
+
+    fn flag(&mut self, value: bool) -> NitfResult<()> {
+        self.enumerated(value.into())
+    }
+
+    fn vdc(&mut self, value: i32) -> NitfResult<()> {
+        self.signed(value, self.state.vdc_bits)
+    }
+
+    fn point(&mut self, point: &super::Point) -> NitfResult<()> {
+        self.vdc(point.x)?;
+        self.vdc(point.y)
+    }
+
+    fn real(&mut self, value: f64) -> NitfResult<()> {
+        match self.state.real {
+            RealPrecision::Fixed32 => {
+                let whole = value.floor();
+                self.signed(whole as i32, 16)?;
+                self.unsigned(((value - whole) * 65536.0) as u32, 16)
+            }
+            RealPrecision::Fixed64 => {
+                let whole = value.floor();
+                self.signed(whole as i32, 32)?;
+                self.unsigned(((value - whole) * 4294967296.0) as u32, 32)
+            }
+            RealPrecision::Float32 => self.unsigned((value as f32).to_bits(), 32),
+            RealPrecision::Float64 => {
+                let bits = value.to_bits();
+                self.unsigned((bits >> 32) as u32, 32)?;
+                self.unsigned(bits as u32, 32)
+            }
+        }
+    }
+
+    fn width(&mut self, value: f64, absolute: bool) -> NitfResult<()> {
+        match absolute {
+            true => self.vdc(value.round() as i32),
+            false => self.real(value),
+        }
+    }
+
+    fn color(&mut self, color: &Color) -> NitfResult<()> {
+        match (color, self.state.direct_color) {
+            (Color::Rgb(r, g, b), true) => self.rgb(*r, *g, *b),
+            (Color::Indexed(index), false) => self.unsigned(*index, self.state.color_index_bits),
+            _ => Err(NitfError::Value(
+                "CGM color does not match COLOR SELECTION MODE".to_string(),
+            )),
+        }
+    }
+
+    /// Direct color, whatever the color selection mode
+    fn rgb(&mut self, r: u8, g: u8, b: u8) -> NitfResult<()> {
+        let bits = self.state.color_bits;
+        Self::precision(bits)?;
+        let max = u32::MAX >> (32 - bits);
+        for component in [r, g, b] {
+            self.unsigned((component as u64 * max as u64 / 255) as u32, bits)?;
+        }
+        Ok(())
+    }
+
+    fn string(&mut self, text: &str) -> NitfResult<()> {
+        let bytes = text.as_bytes();
+        if bytes.len() < 255 {
+            self.bytes.push(bytes.len() as u8);
+            self.bytes.extend_from_slice(bytes);
+            return Ok(());
+        }
+        // Long string, in partitions with a 15-bit length
+        self.bytes.push(255);
+        let mut chunks = bytes.chunks(0x7FFF).peekable();
+        while let Some(chunk) = chunks.next() {
+            let more = (chunks.peek().is_some() as u32) << 15;
+            self.unsigned(more | chunk.len() as u32, 16)?;
+            self.bytes.extend_from_slice(chunk);
+        }
+        Ok(())
+    }
+
+    fn closure(&mut self, closure: ArcClosure) -> NitfResult<()> {
+        self.enumerated(match closure {
+            ArcClosure::Pie => 0,
+            ArcClosure::Chord => 1,
+        })
+    }
+}
+
+/// Class, id, and parameters of an element
+fn encode_params(element: &Element, state: State) -> NitfResult<(u8, u8, Vec<u8>)> {
+    use Element::*;
+    let p = &mut ParamWriter {
+        bytes: vec![],
+        state,
+    };
+    let (class, id) = match element {
+        BeginMetafile(name) => {
+            p.string(name)?;
+            (0, 1)
+        }
+        EndMetafile => (0, 2),
+        BeginPicture(name) => {
+            p.string(name)?;
+            (0, 3)
+        }
+        BeginPictureBody => (0, 4),
+        EndPicture => (0, 5),
+
+        MetafileVersion(version) => {
+            p.int(*version)?;
+            (1, 1)
+        }
+        MetafileDescription(description) => {
+            p.string(description)?;
+            (1, 2)
+        }
+        IntegerPrecision(bits) => {
+            p.int(*bits)?;
+            (1, 4)
+        }
+        IndexPrecision(bits) => {
+            p.int(*bits)?;
+            (1, 6)
+        }
+        ColorPrecision(bits) => {
+            p.int(*bits)?;
+            (1, 7)
+        }
+        ColorIndexPrecision(bits) => {
+            p.int(*bits)?;
+            (1, 8)
+        }
+        FontList(fonts) => {
+            for font in fonts {
+                p.string(font)?;
+            }
+            (1, 13)
+        }
+
+        ColorSelectionMode(direct) => {
+            p.flag(*direct)?;
+            (2, 2)
+        }
+        LineWidthSpecificationMode(absolute) => {
+            p.flag(!absolute)?;
+            (2, 3)
+        }
+        EdgeWidthSpecificationMode(absolute) => {
+            p.flag(!absolute)?;
+            (2, 5)
+        }
+        VdcExtent(p1, p2) => {
+            p.point(p1)?;
+            p.point(p2)?;
+            (2, 6)
+        }
+        BackgroundColor(Color::Rgb(r, g, b)) => {
+            p.rgb(*r, *g, *b)?;
+            (2, 7)
+        }
+        BackgroundColor(Color::Indexed(_)) => Err(NitfError::Value(
+            "CGM background color must be direct".to_string(),
+        ))?,
+
+        VdcIntegerPrecision(bits) => {
+            p.int(*bits)?;
+            (3, 1)
+        }
+        ClipRectangle(p1, p2) => {
+            p.point(p1)?;
+            p.point(p2)?;
+            (3, 5)
+        }
+        ClipIndicator(on) => {
+            p.flag(*on)?;
+            (3, 6)
+        }
+
+        Polyline(points) | Polygon(points) => {
+            for point in points {
+                p.point(point)?;
+            }
+            match element {
+                Polyline(_) => (4, 1),
+                _ => (4, 7),
+            }
+        }
+        Text {
+            position,
+            last,
+            text,
+        } => {
+            p.point(position)?;
+            p.flag(*last)?;
+            p.string(text)?;
+            (4, 4)
+        }
+        Rectangle(p1, p2) => {
+            p.point(p1)?;
+            p.point(p2)?;
+            (4, 11)
+        }
+        Circle { center, radius } => {
+            p.point(center)?;
+            p.vdc(*radius)?;
+            (4, 12)
+        }
+        CircularArcCenter {
+            center,
+            start,
+            end,
+            radius,
+        } => {
+            p.point(center)?;
+            p.point(start)?;
+            p.point(end)?;
+            p.vdc(*radius)?;
+            (4, 15)
+        }
+        CircularArcCenterClose {
+            center,
+            start,
+            end,
+            radius,
+            closure,
+        } => {
+            p.point(center)?;
+            p.point(start)?;
+            p.point(end)?;
+            p.vdc(*radius)?;
+            p.closure(*closure)?;
+            (4, 16)
+        }
+        Ellipse { center, cdp1, cdp2 } => {
+            p.point(center)?;
+            p.point(cdp1)?;
+            p.point(cdp2)?;
+            (4, 17)
+        }
+        EllipticalArc {
+            center,
+            cdp1,
+            cdp2,
+            start,
+            end,
+        } => {
+            for point in [center, cdp1, cdp2, start, end] {
+                p.point(point)?;
+            }
+            (4, 18)
+        }
+        EllipticalArcClose {
+            center,
+            cdp1,
+            cdp2,
+            start,
+            end,
+            closure,
+        } => {
+            for point in [center, cdp1, cdp2, start, end] {
+                p.point(point)?;
+            }
+            p.closure(*closure)?;
+            (4, 19)
+        }
+
+        LineType(line_type) => {
+            p.index(line_type.index())?;
+            (5, 2)
+        }
+        LineWidth(width) => {
+            p.width(*width, state.absolute_line_width)?;
+            (5, 3)
+        }
+        LineColor(color) => {
+            p.color(color)?;
+            (5, 4)
+        }
+        TextFontIndex(index) => {
+            p.index(*index)?;
+            (5, 10)
+        }
+        TextColor(color) => {
+            p.color(color)?;
+            (5, 14)
+        }
+        CharacterHeight(height) => {
+            p.vdc(*height)?;
+            (5, 15)
+        }
+        CharacterOrientation { up, base } => {
+            p.point(up)?;
+            p.point(base)?;
+            (5, 16)
+        }
+        InteriorStyle(style) => {
+            p.enumerated(style.value())?;
+            (5, 22)
+        }
+        FillColor(color) => {
+            p.color(color)?;
+            (5, 23)
+        }
+        HatchIndex(index) => {
+            p.index(*index)?;
+            (5, 24)
+        }
+        EdgeType(line_type) => {
+            p.index(line_type.index())?;
+            (5, 27)
+        }
+        EdgeWidth(width) => {
+            p.width(*width, state.absolute_edge_width)?;
+            (5, 28)
+        }
+        EdgeColor(color) => {
+            p.color(color)?;
+            (5, 29)
+        }
+        EdgeVisibility(visible) => {
+            p.flag(*visible)?;
+            (5, 30)
+        }
+
+        Other { class, id, params } => {
+            p.bytes.extend_from_slice(params);
+            (*class, *id)
+        }
+    };
+    Ok((class, id, std::mem::take(&mut p.bytes)))
+}
+
+/// Encode elements as binary CGM, the inverse of [parse()](super::parse)
+pub fn encode(elements: &[Element]) -> NitfResult<Vec<u8>> {
+    let mut state = State::default();
+    let mut bytes = vec![];
+    for element in elements {
+        let (class, id, params) = encode_params(element, state)?;
+        let header = ((class as u16) << 12) | ((id as u16) << 5);
+        if params.len() < 31 {
+            bytes.extend_from_slice(&(header | params.len() as u16).to_be_bytes());
+            bytes.extend_from_slice(&params);
+        } else {
+            bytes.extend_from_slice(&(header | 31).to_be_bytes());
+            let mut chunks = params.chunks(MAX_PARTITION).peekable();
+            while let Some(chunk) = chunks.next() {
+                let more = (chunks.peek().is_some() as u16) << 15;
+                bytes.extend_from_slice(&(more | chunk.len() as u16).to_be_bytes());
+                bytes.extend_from_slice(chunk);
+            }
+        }
+        if params.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        // Precision and mode elements change how later elements are encoded
+        decode(class, id, &params, &mut state)?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cgm::{parse, CgmBuilder, LineType, Point};
+
+    #[test]
+    fn builder_round_trip() {
+        let mut builder = CgmBuilder::new("annotation");
+        let red = Color::Rgb(255, 0, 0);
+        builder
+            .fill(Some(Color::Rgb(0, 128, 255)))
+            .edge(Some((red, 2.0)))
+            .rectangle(Point::from_pixel(0, 0), Point::from_pixel(20, 40))
+            .line_type(LineType::Dash)
+            .line_width(3.0)
+            .line_color(red)
+            .arrow(Point::from_pixel(40, 60), Point::from_pixel(20, 40), 6)
+            .ellipse(Point::new(0, 0), Point::new(10, 0), Point::new(0, 5))
+            .text(Point::from_pixel(50, 0), "Target");
+        let elements = builder.to_elements();
+        assert_eq!(parse(&builder.to_bytes().unwrap()).unwrap(), elements);
+    }
+
+    #[test]
+    fn precision_round_trip() {
+        let elements = [
+            Element::BeginMetafile("precision".to_string()),
+            Element::ColorPrecision(16),
+            Element::VdcIntegerPrecision(32),
+            Element::Circle {
+                center: Point::new(100_000, -100_000),
+                radius: 70_000,
+            },
+            Element::LineColor(Color::Rgb(255, 0, 127)),
+            Element::EndMetafile,
+        ];
+        assert_eq!(parse(&encode(&elements).unwrap()).unwrap(), elements);
+    }
+
+    #[test]
+    fn indexed_mode_background() {
+        let elements = [
+            Element::BeginMetafile("indexed".to_string()),
+            Element::ColorSelectionMode(false),
+            Element::BackgroundColor(Color::Rgb(0, 64, 255)),
+            Element::LineColor(Color::Indexed(3)),
+            Element::EndMetafile,
+        ];
+        assert_eq!(parse(&encode(&elements).unwrap()).unwrap(), elements);
+        let indexed = [Element::BackgroundColor(Color::Indexed(1))];
+        assert!(encode(&indexed).is_err());
+    }
+
+    #[test]
+    fn unsupported_precision() {
+        for bits in [-1, 0, 12, 33, 64] {
+            for element in [
+                Element::IntegerPrecision(bits),
+                Element::IndexPrecision(bits),
+                Element::ColorPrecision(bits),
+                Element::ColorIndexPrecision(bits),
+                Element::VdcIntegerPrecision(bits),
+            ] {
+                let elements = [element, Element::MetafileVersion(1)];
+                assert!(encode(&elements).is_err(), "{elements:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,758 @@
+//! Computer Graphics Metafile (CGM) graphic segment data
+//!
+//! Graphic segments hold binary-encoded CGM, restricted by the NITF profile
+//! of MIL-STD-2301A. [parse()] decodes the metafile into a list of
+//! [Element]s. Elements outside of the profile are kept as [Element::Other].
+//! [encode()] is the inverse, and [CgmBuilder] assembles annotations into a
+//! complete metafile and graphic segment. [to_svg_shapes()] and
+//! [rasterize()] draw the elements.
+//!
+//! Precision elements are honored, but the profile defaults are assumed
+//! otherwise: integer VDCs, direct color with components in `0..=255`, and
+//! absolute line and edge widths.
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::{GraphicSegment, NitfError, NitfResult};
+
+mod attributes;
+mod builder;
+mod encode;
+mod raster;
+mod svg;
+
+pub use builder::CgmBuilder;
+pub use encode::encode;
+pub use raster::rasterize;
+pub use svg::to_svg_shapes;
+
+/// Point in virtual device coordinates (VDC)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+    /// Point at a (row, column) pixel offset from the graphic location
+    pub fn from_pixel(row: i32, col: i32) -> Self {
+        Self::new(col, -row)
+    }
+}
+
+impl std::ops::Add for Point {
+    type Output = Point;
+    fn add(self, rhs: Self) -> Self::Output {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl std::ops::Sub for Point {
+    type Output = Point;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+/// Color specifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// Direct red, green, and blue components
+    Rgb(u8, u8, u8),
+    /// Index into the color table
+    Indexed(u32),
+}
+
+/// Line and edge types
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineType {
+    #[default]
+    Solid,
+    Dash,
+    Dot,
+    DashDot,
+    DashDotDot,
+    /// Private or registered line type
+    Other(i32),
+}
+
+impl LineType {
+    fn from_index(index: i32) -> Self {
+        match index {
+            1 => Self::Solid,
+            2 => Self::Dash,
+            3 => Self::Dot,
+            4 => Self::DashDot,
+            5 => Self::DashDotDot,
+            index => Self::Other(index),
+        }
+    }
+    fn index(&self) -> i32 {
+        match self {
+            Self::Solid => 1,
+            Self::Dash => 2,
+            Self::Dot => 3,
+            Self::DashDot => 4,
+            Self::DashDotDot => 5,
+            Self::Other(index) => *index,
+        }
+    }
+}
+
+/// Fill style of closed primitives
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InteriorStyle {
+    #[default]
+    Hollow,
+    Solid,
+    Pattern,
+    Hatch,
+    Empty,
+    Other(i16),
+}
+
+impl InteriorStyle {
+    fn from_enum(value: i16) -> Self {
+        match value {
+            0 => Self::Hollow,
+            1 => Self::Solid,
+            2 => Self::Pattern,
+            3 => Self::Hatch,
+            4 => Self::Empty,
+            value => Self::Other(value),
+        }
+    }
+    fn value(&self) -> i16 {
+        match self {
+            Self::Hollow => 0,
+            Self::Solid => 1,
+            Self::Pattern => 2,
+            Self::Hatch => 3,
+            Self::Empty => 4,
+            Self::Other(value) => *value,
+        }
+    }
+}
+
+/// How a closed arc is closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArcClosure {
+    /// Lines to the center
+    Pie,
+    /// Line between the arc end points
+    Chord,
+}
+
+/// Decoded CGM element
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    // Delimiter elements
+    BeginMetafile(String),
+    EndMetafile,
+    BeginPicture(String),
+    BeginPictureBody,
+    EndPicture,
+
+    // Metafile descriptor elements
+    MetafileVersion(i32),
+    MetafileDescription(String),
+    /// Bits of integer precision
+    IntegerPrecision(i32),
+    /// Bits of index precision
+    IndexPrecision(i32),
+    /// Bits of direct color component precision
+    ColorPrecision(i32),
+    /// Bits of color index precision
+    ColorIndexPrecision(i32),
+    FontList(Vec<String>),
+
+    // Picture descriptor elements
+    /// Direct, rather than indexed, color selection
+    ColorSelectionMode(bool),
+    /// Absolute, rather than scaled, line widths
+    LineWidthSpecificationMode(bool),
+    /// Absolute, rather than scaled, edge widths
+    EdgeWidthSpecificationMode(bool),
+    /// Lower left and upper right corners of the picture
+    VdcExtent(Point, Point),
+    /// Background color, which is always direct
+    BackgroundColor(Color),
+
+    // Control elements
+    /// Bits of VDC integer precision
+    VdcIntegerPrecision(i32),
+    ClipRectangle(Point, Point),
+    ClipIndicator(bool),
+
+    // Graphical primitive elements
+    Polyline(Vec<Point>),
+    Text {
+        position: Point,
+        /// Whether this completes the text string
+        last: bool,
+        text: String,
+    },
+    Polygon(Vec<Point>),
+    Rectangle(Point, Point),
+    Circle {
+        center: Point,
+        radius: i32,
+    },
+    CircularArcCenter {
+        center: Point,
+        /// Start direction, relative to the center
+        start: Point,
+        /// End direction, relative to the center
+        end: Point,
+        radius: i32,
+    },
+    CircularArcCenterClose {
+        center: Point,
+        start: Point,
+        end: Point,
+        radius: i32,
+        closure: ArcClosure,
+    },
+    Ellipse {
+        center: Point,
+        /// Ends of the conjugate diameters
+        cdp1: Point,
+        cdp2: Point,
+    },
+    EllipticalArc {
+        center: Point,
+        cdp1: Point,
+        cdp2: Point,
+        /// Start direction, relative to the center
+        start: Point,
+        /// End direction, relative to the center
+        end: Point,
+    },
+    EllipticalArcClose {
+        center: Point,
+        cdp1: Point,
+        cdp2: Point,
+        start: Point,
+        end: Point,
+        closure: ArcClosure,
+    },
+
+    // Attribute elements
+    LineType(LineType),
+    LineWidth(f64),
+    LineColor(Color),
+    TextFontIndex(i32),
+    TextColor(Color),
+    CharacterHeight(i32),
+    CharacterOrientation {
+        up: Point,
+        base: Point,
+    },
+    InteriorStyle(InteriorStyle),
+    FillColor(Color),
+    HatchIndex(i32),
+    EdgeType(LineType),
+    EdgeWidth(f64),
+    EdgeColor(Color),
+    EdgeVisibility(bool),
+
+    /// Element outside of the NITF profile, with its raw parameters
+    Other {
+        class: u8,
+        id: u8,
+        params: Vec<u8>,
+    },
+}
+
+/// Encoding parameters which are set by earlier elements
+#[derive(Debug, Clone, Copy)]
+struct State {
+    integer_bits: u32,
+    index_bits: u32,
+    vdc_bits: u32,
+    color_bits: u32,
+    color_index_bits: u32,
+    real: RealPrecision,
+    direct_color: bool,
+    absolute_line_width: bool,
+    absolute_edge_width: bool,
+}
+
+impl Default for State {
+    /// Defaults of the NITF profile
+    fn default() -> Self {
+        Self {
+            integer_bits: 16,
+            index_bits: 16,
+            vdc_bits: 16,
+            color_bits: 8,
+            color_index_bits: 8,
+            real: RealPrecision::Fixed32,
+            direct_color: true,
+            absolute_line_width: true,
+            absolute_edge_width: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RealPrecision {
+    Fixed32,
+    Fixed64,
+    Float32,
+    Float64,
+}
+
+/// Whether integers and colors can be encoded with `bits` of precision
+fn valid_precision(bits: u32) -> bool {
+    matches!(bits, 8 | 16 | 24 | 32)
+}
+
+/// Reader over the parameters of one element
+struct Params<'a> {
+    bytes: &'a [u8],
+    state: State,
+}
+
+impl Params<'_> {
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn take(&mut self, n: usize) -> NitfResult<&[u8]> {
+        if n > self.bytes.len() {
+            Err(NitfError::ParseError("CGM element parameters".to_string()))?
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn unsigned(&mut self, bits: u32) -> NitfResult<u32> {
+        if !valid_precision(bits) {
+            Err(NitfError::ParseError(format!("CGM precision {bits}")))?
+        }
+        let bytes = self.take(bits as usize / 8)?;
+        Ok(bytes.iter().fold(0, |value, b| (value << 8) | *b as u32))
+    }
+
+    fn signed(&mut self, bits: u32) -> NitfResult<i32> {
+        let value = self.unsigned(bits)?;
+        let shift = 32 - bits;
+        Ok(((value << shift) as i32) >> shift)
+    }
+
+    /// Integer
+    fn int(&mut self) -> NitfResult<i32> {
+        self.signed(self.state.integer_bits)
+    }
+
+    /// Precision of a precision element, in bits
+    fn precision(&mut self) -> NitfResult<u32> {
+        let bits = self.int()?;
+        match u32::try_from(bits) {
+            Ok(bits) if valid_precision(bits) => Ok(bits),
+            _ => Err(NitfError::ParseError(format!("CGM precision {bits}"))),
+        }
+    }
+
+    /// Index
+    fn index(&mut self) -> NitfResult<i32> {
+        self.signed(self.state.index_bits)
+    }
+
+    /// Enumerated value, which is always 16 bits
+    fn enumerated(&mut self) -> NitfResult<i16> {
+        Ok(self.signed(16)? as i16)
+    }
+
+    fn flag(&mut self) -> NitfResult<bool> {
+        Ok(self.enumerated()? != 0)
+    }
+
+    fn vdc(&mut self) -> NitfResult<i32> {
+        self.signed(self.state.vdc_bits)
+    }
+
+    fn point(&mut self) -> NitfResult<Point> {
+        Ok(Point::new(self.vdc()?, self.vdc()?))
+    }
+
+    fn points(&mut self) -> NitfResult<Vec<Point>> {
+        let mut points = vec![];
+        while !self.is_empty() {
+            points.push(self.point()?);
+        }
+        Ok(points)
+    }
+
+    fn real(&mut self) -> NitfResult<f64> {
+        Ok(match self.state.real {
+            RealPrecision::Fixed32 => self.signed(16)? as f64 + self.unsigned(16)? as f64 / 65536.0,
+            RealPrecision::Fixed64 => {
+                self.signed(32)? as f64 + self.unsigned(32)? as f64 / 4294967296.0
+            }
+            RealPrecision::Float32 => f32::from_bits(self.unsigned(32)?) as f64,
+            RealPrecision::Float64 => {
+                let high = self.unsigned(32)? as u64;
+                let low = self.unsigned(32)? as u64;
+                f64::from_bits((high << 32) | low)
+            }
+        })
+    }
+
+    /// Line or edge width, which is a VDC when absolute
+    fn width(&mut self, absolute: bool) -> NitfResult<f64> {
+        match absolute {
+            true => Ok(self.vdc()? as f64),
+            false => self.real(),
+        }
+    }
+
+    fn color(&mut self) -> NitfResult<Color> {
+        if !self.state.direct_color {
+            return Ok(Color::Indexed(self.unsigned(self.state.color_index_bits)?));
+        }
+        self.rgb()
+    }
+
+    /// Direct color, whatever the color selection mode
+    fn rgb(&mut self) -> NitfResult<Color> {
+        let bits = self.state.color_bits;
+        let max = u32::MAX >> (32 - bits);
+        let mut component = || -> NitfResult<u8> {
+            let value = self.unsigned(bits)?;
+            Ok((value as u64 * 255 / max as u64) as u8)
+        };
+        Ok(Color::Rgb(component()?, component()?, component()?))
+    }
+
+    fn string(&mut self) -> NitfResult<String> {
+        let length = self.unsigned(8)? as usize;
+        let mut bytes = vec![];
+        if length == 255 {
+            // Long string, in partitions with a 15-bit length
+            loop {
+                let word = self.unsigned(16)?;
+                bytes.extend_from_slice(self.take((word & 0x7FFF) as usize)?);
+                if word & 0x8000 == 0 {
+                    break;
+                }
+            }
+        } else {
+            bytes.extend_from_slice(self.take(length)?);
+        }
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+
+    fn closure(&mut self) -> NitfResult<ArcClosure> {
+        Ok(match self.enumerated()? {
+            0 => ArcClosure::Pie,
+            _ => ArcClosure::Chord,
+        })
+    }
+}
+
+/// Decode one element, updating the encoding state
+fn decode(class: u8, id: u8, bytes: &[u8], state: &mut State) -> NitfResult<Element> {
+    use Element::*;
+    let p = &mut Params {
+        bytes,
+        state: *state,
+    };
+    let element = match (class, id) {
+        (0, 1) => BeginMetafile(p.string()?),
+        (0, 2) => EndMetafile,
+        (0, 3) => BeginPicture(p.string()?),
+        (0, 4) => BeginPictureBody,
+        (0, 5) => EndPicture,
+
+        (1, 1) => MetafileVersion(p.int()?),
+        (1, 2) => MetafileDescription(p.string()?),
+        (1, 3) => {
+            if p.enumerated()? != 0 {
+                Err(NitfError::ParseError("CGM real VDC type".to_string()))?
+            }
+            Other {
+                class,
+                id,
+                params: bytes.to_vec(),
+            }
+        }
+        (1, 4) => {
+            let bits = p.precision()?;
+            state.integer_bits = bits;
+            IntegerPrecision(bits as i32)
+        }
+        (1, 5) => {
+            let fixed = p.enumerated()? == 1;
+            let exponent = p.int()?;
+            let fraction = p.int()?;
+            state.real = match (fixed, exponent, fraction) {
+                (true, 16, 16) => RealPrecision::Fixed32,
+                (true, 32, 32) => RealPrecision::Fixed64,
+                (false, 9, 23) => RealPrecision::Float32,
+                (false, 12, 52) => RealPrecision::Float64,
+                _ => Err(NitfError::ParseError("CGM REAL PRECISION".to_string()))?,
+            };
+            Other {
+                class,
+                id,
+                params: bytes.to_vec(),
+            }
+        }
+        (1, 6) => {
+            let bits = p.precision()?;
+            state.index_bits = bits;
+            IndexPrecision(bits as i32)
+        }
+        (1, 7) => {
+            let bits = p.precision()?;
+            state.color_bits = bits;
+            ColorPrecision(bits as i32)
+        }
+        (1, 8) => {
+            let bits = p.precision()?;
+            state.color_index_bits = bits;
+            ColorIndexPrecision(bits as i32)
+        }
+        (1, 13) => {
+            let mut fonts = vec![];
+            while !p.is_empty() {
+                fonts.push(p.string()?);
+            }
+            FontList(fonts)
+        }
+
+        (2, 2) => {
+            let direct = p.flag()?;
+            state.direct_color = direct;
+            ColorSelectionMode(direct)
+        }
+        (2, 3) => {
+            let absolute = p.enumerated()? == 0;
+            state.absolute_line_width = absolute;
+            LineWidthSpecificationMode(absolute)
+        }
+        (2, 5) => {
+            let absolute = p.enumerated()? == 0;
+            state.absolute_edge_width = absolute;
+            EdgeWidthSpecificationMode(absolute)
+        }
+        (2, 6) => VdcExtent(p.point()?, p.point()?),
+        (2, 7) => BackgroundColor(p.rgb()?),
+
+        (3, 1) => {
+            let bits = p.precision()?;
+            state.vdc_bits = bits;
+            VdcIntegerPrecision(bits as i32)
+        }
+        (3, 5) => ClipRectangle(p.point()?, p.point()?),
+        (3, 6) => ClipIndicator(p.flag()?),
+
+        (4, 1) => Polyline(p.points()?),
+        (4, 4) => Text {
+            position: p.point()?,
+            last: p.flag()?,
+            text: p.string()?,
+        },
+        (4, 7) => Polygon(p.points()?),
+        (4, 11) => Rectangle(p.point()?, p.point()?),
+        (4, 12) => Circle {
+            center: p.point()?,
+            radius: p.vdc()?,
+        },
+        (4, 15) => CircularArcCenter {
+            center: p.point()?,
+            start: p.point()?,
+            end: p.point()?,
+            radius: p.vdc()?,
+        },
+        (4, 16) => CircularArcCenterClose {
+            center: p.point()?,
+            start: p.point()?,
+            end: p.point()?,
+            radius: p.vdc()?,
+            closure: p.closure()?,
+        },
+        (4, 17) => Ellipse {
+            center: p.point()?,
+            cdp1: p.point()?,
+            cdp2: p.point()?,
+        },
+        (4, 18) => EllipticalArc {
+            center: p.point()?,
+            cdp1: p.point()?,
+            cdp2: p.point()?,
+            start: p.point()?,
+            end: p.point()?,
+        },
+        (4, 19) => EllipticalArcClose {
+            center: p.point()?,
+            cdp1: p.point()?,
+            cdp2: p.point()?,
+            start: p.point()?,
+            end: p.point()?,
+            closure: p.closure()?,
+        },
+
+        (5, 2) => LineType(self::LineType::from_index(p.index()?)),
+        (5, 3) => LineWidth(p.width(state.absolute_line_width)?),
+        (5, 4) => LineColor(p.color()?),
+        (5, 10) => TextFontIndex(p.index()?),
+        (5, 14) => TextColor(p.color()?),
+        (5, 15) => CharacterHeight(p.vdc()?),
+        (5, 16) => CharacterOrientation {
+            up: p.point()?,
+            base: p.point()?,
+        },
+        (5, 22) => InteriorStyle(self::InteriorStyle::from_enum(p.enumerated()?)),
+        (5, 23) => FillColor(p.color()?),
+        (5, 24) => HatchIndex(p.index()?),
+        (5, 27) => EdgeType(self::LineType::from_index(p.index()?)),
+        (5, 28) => EdgeWidth(p.width(state.absolute_edge_width)?),
+        (5, 29) => EdgeColor(p.color()?),
+        (5, 30) => EdgeVisibility(p.flag()?),
+
+        _ => Other {
+            class,
+            id,
+            params: bytes.to_vec(),
+        },
+    };
+    Ok(element)
+}
+
+/// Decode binary-encoded CGM into its elements, up to END METAFILE
+pub fn parse(bytes: &[u8]) -> NitfResult<Vec<Element>> {
+    let error = || NitfError::ParseError("CGM element".to_string());
+    let mut state = State::default();
+    let mut elements = vec![];
+    let mut pos = 0;
+    let word = |pos: &mut usize| -> NitfResult<u16> {
+        let word = bytes.get(*pos..*pos + 2).ok_or_else(error)?;
+        *pos += 2;
+        Ok(u16::from_be_bytes([word[0], word[1]]))
+    };
+    while pos + 2 <= bytes.len() {
+        let header = word(&mut pos)?;
+        let class = (header >> 12) as u8;
+        let id = ((header >> 5) & 0x7F) as u8;
+        let mut length = (header & 0x1F) as usize;
+        let mut params = vec![];
+        let mut partitioned = length == 31;
+        loop {
+            if partitioned {
+                let long = word(&mut pos)?;
+                partitioned = long & 0x8000 != 0;
+                length = (long & 0x7FFF) as usize;
+            }
+            params.extend_from_slice(bytes.get(pos..pos + length).ok_or_else(error)?);
+            // Parameters are padded to a word boundary
+            pos += length + length % 2;
+            if !partitioned {
+                break;
+            }
+        }
+        let element = decode(class, id, &params, &mut state)?;
+        let end = element == Element::EndMetafile;
+        elements.push(element);
+        if end {
+            break;
+        }
+    }
+    Ok(elements)
+}
+
+impl GraphicSegment {
+    /// Read and decode the CGM segment data
+    pub fn read_cgm(&self, reader: &mut (impl Read + Seek)) -> NitfResult<Vec<Element>> {
+        let mut bytes = vec![0; self.data_size as usize];
+        reader.seek(SeekFrom::Start(self.data_offset))?;
+        reader.read_exact(&mut bytes)?;
+        parse(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Metafile which starts with BEGIN METAFILE and ends with END METAFILE
+    /// around `body`
+    fn metafile(body: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0x00, 0x23, 2, b'M', b'F', 0];
+        bytes.extend_from_slice(body);
+        bytes.extend_from_slice(&[0x00, 0x40]);
+        bytes
+    }
+
+    #[test]
+    fn parse_metafile() {
+        let body = [
+            // METAFILE VERSION 1
+            &[0x10, 0x22, 0, 1][..],
+            // COLOUR PRECISION 16
+            &[0x10, 0xE2, 0, 16],
+            // BEGIN PICTURE "P", BEGIN PICTURE BODY
+            &[0x00, 0x62, 1, b'P', 0x00, 0x80],
+            // LINE COLOUR
+            &[0x50, 0x86, 0xFF, 0xFF, 0, 0, 0x80, 0],
+            // POLYLINE
+            &[0x40, 0x28, 0, 0, 0, 0, 0, 10, 0xFF, 0xFB],
+            // END PICTURE
+            &[0x00, 0xA0],
+        ];
+        let bytes = metafile(&body.concat());
+        let elements = parse(&bytes).unwrap();
+        assert_eq!(
+            elements,
+            [
+                Element::BeginMetafile("MF".to_string()),
+                Element::MetafileVersion(1),
+                Element::ColorPrecision(16),
+                Element::BeginPicture("P".to_string()),
+                Element::BeginPictureBody,
+                Element::LineColor(Color::Rgb(255, 0, 127)),
+                Element::Polyline(vec![Point::new(0, 0), Point::new(10, -5)]),
+                Element::EndPicture,
+                Element::EndMetafile,
+            ]
+        );
+    }
+
+    #[test]
+    fn indexed_mode_background() {
+        let body = [
+            // COLOUR SELECTION MODE indexed
+            &[0x20, 0x42, 0, 0][..],
+            // BACKGROUND COLOUR, always direct
+            &[0x20, 0xE3, 0xFF, 0x80, 0, 0],
+            // LINE COLOUR index 3
+            &[0x50, 0x81, 3, 0],
+        ];
+        let elements = parse(&metafile(&body.concat())).unwrap();
+        assert_eq!(
+            elements[2],
+            Element::BackgroundColor(Color::Rgb(255, 128, 0))
+        );
+        assert_eq!(elements[3], Element::LineColor(Color::Indexed(3)));
+    }
+
+    #[test]
+    fn hostile_precision() {
+        // COLOUR PRECISION 0, then LINE COLOUR
+        let bytes = metafile(&[0x10, 0xE2, 0, 0, 0x50, 0x83, 0, 0, 0, 0]);
+        assert!(parse(&bytes).is_err());
+        // Each precision element with unsupported values
+        for header in [0x1082u16, 0x10C2, 0x10E2, 0x1102, 0x3022] {
+            for bits in [-8i16, 0, 12, 33, 64] {
+                let mut body = header.to_be_bytes().to_vec();
+                body.extend_from_slice(&bits.to_be_bytes());
+                assert!(parse(&metafile(&body)).is_err(), "{header:x} {bits}");
+            }
+        }
+    }
+}
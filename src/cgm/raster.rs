@@ -0,0 +1,196 @@
+//! Rasterization of CGM graphics
+//!
+//! Closed primitives are filled by even-odd scanline and outlines are
+//! stroked by stamping a disk of the line width along them. Text is not
+//! rasterized, since there are no fonts to draw it with.
+use log::debug;
+
+use super::attributes::{dash_pattern, outline, rgb, Attributes, Stroke};
+use super::Element;
+use crate::headers::graphic_hdr;
+use crate::render::RgbRaster;
+use crate::types::RowColumn;
+
+/// Distance between stamps along a stroke, in pixels
+const STEP: f64 = 0.5;
+
+/// Drawing of one graphic into a raster
+struct Rasterizer<'a> {
+    monochrome: bool,
+    /// CCS location of the VDC origin
+    location: RowColumn,
+    raster: &'a mut RgbRaster,
+    attributes: Attributes,
+}
+
+impl Rasterizer<'_> {
+    /// CCS (row, column) of a VDC point
+    fn pixel(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (self.location.row as f64 - y, self.location.col as f64 + x)
+    }
+
+    /// First and last CCS rows, and first and last columns, of the raster
+    fn bounds(&self) -> ((f64, f64), (f64, f64)) {
+        let RowColumn { row, col } = self.raster.upper_left;
+        (
+            (row as f64, row as f64 + self.raster.n_rows as f64 - 1.0),
+            (col as f64, col as f64 + self.raster.n_cols as f64 - 1.0),
+        )
+    }
+
+    /// Range of the line parameter, from 0 at `p0` to 1 at `p1`, within
+    /// `margin` of the raster, or `None` if the line is outside of it
+    fn visible(&self, p0: (f64, f64), p1: (f64, f64), margin: f64) -> Option<(f64, f64)> {
+        let (rows, cols) = self.bounds();
+        let mut range = (0.0f64, 1.0f64);
+        for (v0, v1, (low, high)) in [(p0.0, p1.0, rows), (p0.1, p1.1, cols)] {
+            let (low, high) = (low - margin, high + margin);
+            let delta = v1 - v0;
+            if delta == 0.0 {
+                if v0 < low || v0 > high {
+                    return None;
+                }
+                continue;
+            }
+            let (a, b) = ((low - v0) / delta, (high - v0) / delta);
+            range = (range.0.max(a.min(b)), range.1.min(a.max(b)));
+        }
+        (range.0 <= range.1).then_some(range)
+    }
+
+    fn set(&mut self, row: i32, col: i32, color: [u8; 3]) {
+        self.raster.set(RowColumn::new(row, col), color);
+    }
+
+    /// Fill the interior of a polygon of CCS (row, column) points
+    fn fill(&mut self, points: &[(f64, f64)], color: [u8; 3]) {
+        let Some(top) = points.iter().map(|p| p.0).reduce(f64::min) else {
+            return;
+        };
+        let bottom = points.iter().map(|p| p.0).fold(top, f64::max);
+        // Only rows and spans inside the raster are scanned
+        let (rows, cols) = self.bounds();
+        for row in top.ceil().max(rows.0) as i32..=bottom.floor().min(rows.1) as i32 {
+            let y = row as f64;
+            let mut crossings = vec![];
+            for (i, (r0, c0)) in points.iter().enumerate() {
+                let (r1, c1) = points[(i + 1) % points.len()];
+                if (*r0 <= y) != (r1 <= y) {
+                    crossings.push(c0 + (y - r0) / (r1 - r0) * (c1 - c0));
+                }
+            }
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                let first = span[0].ceil().max(cols.0) as i32;
+                let end = span[1].ceil().min(cols.1 + 1.0) as i32;
+                for col in first..end {
+                    self.set(row, col, color);
+                }
+            }
+        }
+    }
+
+    /// Draw a disk of `radius` centered at a CCS point. Thin lines set only
+    /// the nearest pixel.
+    fn stamp(&mut self, (row, col): (f64, f64), radius: f64, color: [u8; 3]) {
+        if radius < 1.0 {
+            self.set(row.round() as i32, col.round() as i32, color);
+            return;
+        }
+        for r in (row - radius).ceil() as i32..=(row + radius).floor() as i32 {
+            for c in (col - radius).ceil() as i32..=(col + radius).floor() as i32 {
+                if (r as f64 - row).hypot(c as f64 - col) <= radius {
+                    self.set(r, c, color);
+                }
+            }
+        }
+    }
+
+    /// Stroke a line through CCS points
+    fn stroke(&mut self, points: &[(f64, f64)], closed: bool, stroke: Stroke) {
+        let (color, width, line_type) = stroke;
+        let color = rgb(color, self.monochrome);
+        let radius = width / 2.0;
+        let dashes = dash_pattern(line_type, width);
+        let period = dashes.as_ref().map_or(0.0, |d| d.iter().sum::<f64>());
+        // Whether the pattern is drawn at a distance along the line
+        let on = |distance: f64| match &dashes {
+            Some(dashes) if period > 0.0 => {
+                let mut phase = distance % period;
+                for (i, dash) in dashes.iter().enumerate() {
+                    if phase < *dash {
+                        return i % 2 == 0;
+                    }
+                    phase -= dash;
+                }
+                false
+            }
+            _ => true,
+        };
+        let mut segments: Vec<_> = points.windows(2).map(|w| (w[0], w[1])).collect();
+        if closed && points.len() > 2 {
+            segments.push((points[points.len() - 1], points[0]));
+        }
+        let mut distance = 0.0;
+        for ((r0, c0), (r1, c1)) in segments {
+            let length = (r1 - r0).hypot(c1 - c0);
+            // Stamps are only made along the part of the segment which can
+            // reach the raster
+            let Some((t0, t1)) = self.visible((r0, c0), (r1, c1), radius + 1.0) else {
+                distance += length;
+                continue;
+            };
+            let n = (length / STEP).ceil().max(1.0) as usize;
+            let first = (t0 * n as f64).floor() as usize;
+            let last = ((t1 * n as f64).ceil() as usize).min(n);
+            for i in first..=last {
+                let t = i as f64 / n as f64;
+                if on(distance + t * length) {
+                    self.stamp((r0 + t * (r1 - r0), c0 + t * (c1 - c0)), radius, color);
+                }
+            }
+            distance += length;
+        }
+    }
+
+    fn element(&mut self, element: &Element) {
+        self.attributes.apply(element);
+        if let Element::Text { text, .. } = element {
+            debug!("Text {text:?} is not rasterized");
+            return;
+        }
+        let Some((points, closed)) = outline(element) else {
+            return;
+        };
+        let points: Vec<_> = points.into_iter().map(|p| self.pixel(p)).collect();
+        if !closed {
+            self.stroke(&points, false, self.attributes.line());
+            return;
+        }
+        if let Some(color) = self.attributes.fill() {
+            self.fill(&points, rgb(color, self.monochrome));
+        }
+        if let Some(edge) = self.attributes.edge() {
+            self.stroke(&points, true, edge);
+        }
+    }
+}
+
+/// Draw CGM elements into `raster`, with the VDC origin at the CCS
+/// `location` of the graphic. `color` is the SCOLOR of the graphic segment.
+pub fn rasterize(
+    elements: &[Element],
+    color: graphic_hdr::Color,
+    location: RowColumn,
+    raster: &mut RgbRaster,
+) {
+    let mut rasterizer = Rasterizer {
+        monochrome: color == graphic_hdr::Color::M,
+        location,
+        raster,
+        attributes: Attributes::default(),
+    };
+    for element in elements {
+        rasterizer.element(element);
+    }
+}
@@ -0,0 +1,241 @@
+//! SVG export of CGM graphics
+//!
+//! CGM coordinates have their origin at the graphic location (SLOC), with x
+//! increasing with column and y increasing upwards, so the row offset is `-y`.
+//! SVG output uses pixel (column, row) coordinates.
+use std::fmt::Write as _;
+use std::io::{Read, Seek};
+
+use super::attributes::{dash_pattern, outline, rgb, Attributes, Stroke};
+use super::{Color, Element};
+use crate::headers::graphic_hdr;
+use crate::layout::SegmentId;
+use crate::{Nitf, NitfError, NitfResult};
+
+/// Format a coordinate, without trailing zeros
+fn num(value: f64) -> String {
+    let value = (value * 1000.0).round() / 1000.0;
+    format!("{}", value + 0.0)
+}
+
+/// SVG coordinates of a VDC point
+fn xy(x: f64, y: f64) -> String {
+    format!("{},{}", num(x), num(-y))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Attribute state while rendering
+struct Renderer {
+    monochrome: bool,
+    out: String,
+    attributes: Attributes,
+}
+
+impl Renderer {
+    fn color(&self, color: Color) -> String {
+        let [r, g, b] = rgb(color, self.monochrome);
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+
+    fn stroke(&self, (color, width, line_type): Stroke) -> String {
+        let mut style = format!(
+            r#"stroke="{}" stroke-width="{}""#,
+            self.color(color),
+            num(width)
+        );
+        if let Some(dashes) = dash_pattern(line_type, width) {
+            let dashes = dashes.into_iter().map(num).collect::<Vec<_>>().join(",");
+            let _ = write!(style, r#" stroke-dasharray="{dashes}""#);
+        }
+        style
+    }
+
+    /// Style of open primitives
+    fn line_style(&self) -> String {
+        format!(r#"fill="none" {}"#, self.stroke(self.attributes.line()))
+    }
+
+    /// Style of closed primitives, from the interior style and edge
+    /// attributes
+    fn fill_style(&self) -> String {
+        let fill = match self.attributes.fill() {
+            Some(color) => self.color(color),
+            None => "none".to_string(),
+        };
+        let stroke = match self.attributes.edge() {
+            Some(stroke) => self.stroke(stroke),
+            None => r#"stroke="none""#.to_string(),
+        };
+        format!(r#"fill="{fill}" {stroke}"#)
+    }
+
+    fn element(&mut self, element: &Element) {
+        use Element::*;
+        self.attributes.apply(element);
+        match element {
+            Rectangle(p1, p2) => {
+                let _ = writeln!(
+                    self.out,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+                    p1.x.min(p2.x),
+                    -p1.y.max(p2.y),
+                    (p1.x - p2.x).abs(),
+                    (p1.y - p2.y).abs(),
+                    self.fill_style()
+                );
+            }
+            Circle { center, radius } => {
+                let _ = writeln!(
+                    self.out,
+                    r#"<circle cx="{}" cy="{}" r="{}" {}/>"#,
+                    center.x,
+                    -center.y,
+                    radius,
+                    self.fill_style()
+                );
+            }
+            Text { position, text, .. } => {
+                let _ = writeln!(
+                    self.out,
+                    r#"<text x="{}" y="{}" font-size="{}" fill="{}">{}</text>"#,
+                    position.x,
+                    -position.y,
+                    self.attributes.character_height,
+                    self.color(self.attributes.text_color),
+                    escape(text)
+                );
+            }
+            _ => {
+                let Some((points, closed)) = outline(element) else {
+                    return;
+                };
+                let points = points
+                    .iter()
+                    .map(|(x, y)| xy(*x, *y))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let (tag, style) = match closed {
+                    true => ("polygon", self.fill_style()),
+                    false => ("polyline", self.line_style()),
+                };
+                let _ = writeln!(self.out, r#"<{tag} points="{points}" {style}/>"#);
+            }
+        }
+    }
+}
+
+/// Render CGM elements as SVG shapes in pixel coordinates relative to the
+/// graphic location. `color` is the SCOLOR of the graphic segment.
+pub fn to_svg_shapes(elements: &[Element], color: graphic_hdr::Color) -> String {
+    let mut renderer = Renderer {
+        monochrome: color == graphic_hdr::Color::M,
+        out: String::new(),
+        attributes: Attributes::default(),
+    };
+    for element in elements {
+        renderer.element(element);
+    }
+    renderer.out
+}
+
+impl Nitf {
+    /// Render graphic segment `i_seg` as an SVG document in the common
+    /// coordinate system.
+    ///
+    /// The graphic is placed at its SLOC relative to the segment it is
+    /// attached to, and the view box is its SBND1 to SBND2 bounding box.
+    pub fn graphic_svg(&self, i_seg: usize, reader: &mut (impl Read + Seek)) -> NitfResult<String> {
+        let seg = self
+            .graphic_segments
+            .get(i_seg)
+            .ok_or(NitfError::Value(format!("Graphic segment {i_seg}")))?;
+        let layout = self.ccs_layout()?;
+        let placement = layout
+            .get(SegmentId::Graphic(i_seg))
+            .ok_or(NitfError::Value(format!("Graphic segment {i_seg}")))?;
+        let (upper_left, lower_right) = (placement.upper_left, placement.lower_right);
+        let width = lower_right.col - upper_left.col + 1;
+        let height = lower_right.row - upper_left.row + 1;
+        let shapes = to_svg_shapes(&seg.read_cgm(reader)?, seg.header.scolor.val);
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="{} {} {width} {height}">"#,
+            upper_left.col, upper_left.row,
+        );
+        let _ = writeln!(
+            svg,
+            r#"<g transform="translate({} {})">"#,
+            placement.location.col, placement.location.row,
+        );
+        svg += &shapes;
+        svg += "</g>\n</svg>\n";
+        Ok(svg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cgm::{CgmBuilder, Point};
+    use crate::headers::image_hdr::Band;
+    use crate::types::RowColumn;
+    use crate::ImageSegment;
+    use std::io::Cursor;
+
+    #[test]
+    fn attached_graphic() {
+        let mut image = ImageSegment::default();
+        image.header.idlvl.val = 1;
+        image.header.iloc.val = RowColumn::new(100, 200);
+        image.header.nrows.val = 50;
+        image.header.ncols.val = 50;
+        image.header.nbands.val = 1;
+        image.header.bands = vec![Band::default()];
+        let mut builder = CgmBuilder::new("label");
+        builder
+            .fill(Some(Color::Rgb(255, 0, 0)))
+            .rectangle(Point::from_pixel(0, 0), Point::from_pixel(9, 9))
+            .text(Point::from_pixel(5, 0), "<A & \"B\">");
+        let (graphic, data) = builder.build(RowColumn::new(10, 20), 2, 1).unwrap();
+        let mut nitf = Nitf::default();
+        nitf.add_im(image);
+        nitf.add_sy(graphic);
+        let mut file = Cursor::new(vec![]);
+        nitf.write_headers(&mut file).unwrap();
+        nitf.graphic_segments[0]
+            .write_data(&mut file, &data)
+            .unwrap();
+        file.set_position(0);
+        let nitf = Nitf::from_reader(&mut file).unwrap();
+
+        // SLOC is relative to the image at ILOC, and the view box covers the
+        // square and the text
+        let svg = nitf.graphic_svg(0, &mut file).unwrap();
+        assert!(svg.contains(r#"viewBox="219 105 92 16""#));
+        assert!(svg.contains(r#"<g transform="translate(220 110)">"#));
+        assert!(svg.contains(r##"fill="#ff0000""##));
+        assert!(svg.contains(">&lt;A &amp; &quot;B&quot;&gt;</text>"));
+    }
+
+    #[test]
+    fn monochrome() {
+        let mut builder = CgmBuilder::new("square");
+        builder
+            .fill(Some(Color::Rgb(255, 0, 0)))
+            .rectangle(Point::from_pixel(0, 0), Point::from_pixel(9, 9));
+        let elements = builder.to_elements();
+        let color = to_svg_shapes(&elements, graphic_hdr::Color::C);
+        assert!(color.contains(r##"fill="#ff0000""##));
+        // Red has a luma of 76
+        let gray = to_svg_shapes(&elements, graphic_hdr::Color::M);
+        assert!(gray.contains(r##"fill="#4c4c4c""##));
+    }
+}
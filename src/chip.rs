@@ -0,0 +1,561 @@
+//! Image chip extraction
+use log::{debug, warn};
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::geo::bilinear;
+use crate::headers::image_hdr::{Compression, CoordinateRepresentation, ImageCorner, Mode};
+use crate::headers::{ImageHeader, NitfSegmentHeader};
+use crate::tre::{Blocka, Geolob, Ichipb, Maplob, Tre};
+use crate::types::RowColumn;
+use crate::{ImageSegment, Nitf, NitfError, NitfResult};
+
+/// Largest block dimension which can be written explicitly in NPPBH/NPPBV
+const MAX_BLOCK_SIZE: u32 = 8192;
+
+/// Rectangular region of an image segment, in full pixels
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChipWindow {
+    /// First row of the chip
+    pub row: u32,
+    /// First column of the chip
+    pub col: u32,
+    /// Number of rows in the chip
+    pub n_rows: u32,
+    /// Number of columns in the chip
+    pub n_cols: u32,
+}
+impl ChipWindow {
+    pub fn new(row: u32, col: u32, n_rows: u32, n_cols: u32) -> Self {
+        Self {
+            row,
+            col,
+            n_rows,
+            n_cols,
+        }
+    }
+}
+
+/// Byte layout of uncompressed image data
+pub(crate) struct Layout {
+    mode: Mode,
+    n_bands: usize,
+    pub bytes_per_pixel: usize,
+    n_blocks: usize,
+    blocks_per_row: usize,
+    block_cols: usize,
+    block_rows: usize,
+}
+impl Layout {
+    pub fn new(header: &ImageHeader) -> NitfResult<Self> {
+        if header.ic.val != Compression::NC {
+            Err(NitfError::Fatal(format!(
+                "Cannot read image with compression {}",
+                header.ic.val
+            )))?
+        }
+        let nbpp = header.nbpp.val as usize;
+        if nbpp == 0 || !nbpp.is_multiple_of(8) {
+            Err(NitfError::Fatal(format!(
+                "Cannot read image with {nbpp} bits per pixel"
+            )))?
+        }
+        let block_cols = match header.nppbh.val {
+            0 => header.ncols.val as usize,
+            n => n as usize,
+        };
+        let block_rows = match header.nppbv.val {
+            0 => header.nrows.val as usize,
+            n => n as usize,
+        };
+        let blocks_per_row = header.nbpr.val as usize;
+        Ok(Self {
+            mode: header.imode.val,
+            n_bands: header.bands.len(),
+            bytes_per_pixel: nbpp / 8,
+            n_blocks: blocks_per_row * header.nbpc.val as usize,
+            blocks_per_row,
+            block_cols,
+            block_rows,
+        })
+    }
+
+    /// Layout of `window` read out as a single block with the same band
+    /// interleaving
+    pub fn window(&self, window: &ChipWindow) -> Self {
+        Self {
+            mode: self.mode,
+            n_bands: self.n_bands,
+            bytes_per_pixel: self.bytes_per_pixel,
+            n_blocks: 1,
+            blocks_per_row: 1,
+            block_cols: window.n_cols as usize,
+            block_rows: window.n_rows as usize,
+        }
+    }
+
+    fn block_size(&self) -> usize {
+        self.block_rows * self.block_cols * self.bytes_per_pixel
+    }
+
+    /// Total size of the image data in bytes
+    pub fn data_size(&self) -> usize {
+        self.n_blocks * self.n_bands * self.block_size()
+    }
+
+    /// Byte offset of a pixel of a band from the start of the image data
+    pub fn offset(&self, row: usize, col: usize, band: usize) -> usize {
+        let block = (row / self.block_rows) * self.blocks_per_row + col / self.block_cols;
+        let (row, col) = (row % self.block_rows, col % self.block_cols);
+        let pixel = row * self.block_cols + col;
+        let bpp = self.bytes_per_pixel;
+        match self.mode {
+            Mode::B => (block * self.n_bands + band) * self.block_size() + pixel * bpp,
+            Mode::P => {
+                block * self.n_bands * self.block_size() + (pixel * self.n_bands + band) * bpp
+            }
+            Mode::R => {
+                block * self.n_bands * self.block_size()
+                    + ((row * self.n_bands + band) * self.block_cols + col) * bpp
+            }
+            Mode::S => (band * self.n_blocks + block) * self.block_size() + pixel * bpp,
+        }
+    }
+}
+
+impl Nitf {
+    /// Extract a sub-region of an image segment as a new single image [Nitf]
+    ///
+    /// The file and image segment metadata, including security markings, are
+    /// copied from this object. The chip is written as a single uncompressed
+    /// block with the same band interleaving, IGEOLO and BLOCKA corners are
+    /// recomputed by bilinear interpolation of the original corners, GEOLOB and
+    /// MAPLOB origins are moved to the chip, and an ICHIPB TRE locating the
+    /// chip in the full image is added or updated.
+    ///
+    /// Only uncompressed (`IC = NC`) images with a whole number of bytes per
+    /// pixel are supported. Pixel data is read from `reader`, which should be
+    /// the file this object was read from.
+    ///
+    /// Returns the new [Nitf] along with the chip image data, which should be
+    /// written with [NitfSegment::write_data()](crate::types::NitfSegment::write_data)
+    /// after [Nitf::write_headers()].
+    /// ```no_run
+    /// use nitf_rs::{ChipWindow, Nitf};
+    /// let mut nitf_file = std::fs::File::open("example.nitf").unwrap();
+    /// let nitf = Nitf::from_reader(&mut nitf_file).unwrap();
+    /// let window = ChipWindow::new(100, 200, 512, 512);
+    /// let (mut chip, data) = nitf.chip(&mut nitf_file, 0, window).unwrap();
+    ///
+    /// let mut out_file = std::fs::File::create("chip.nitf").unwrap();
+    /// chip.write_headers(&mut out_file).unwrap();
+    /// chip.image_segments[0].write_data(&mut out_file, &data).unwrap();
+    /// ```
+    pub fn chip(
+        &self,
+        reader: &mut (impl Read + Seek),
+        segment_index: usize,
+        window: ChipWindow,
+    ) -> NitfResult<(Nitf, Vec<u8>)> {
+        let segment = self.image_segments.get(segment_index).ok_or_else(|| {
+            NitfError::Fatal(format!("No image segment at index {segment_index}"))
+        })?;
+        let src = &segment.header;
+        let (nrows, ncols) = (src.nrows.val, src.ncols.val);
+        if window.n_rows == 0
+            || window.n_cols == 0
+            || window.row.saturating_add(window.n_rows) > nrows
+            || window.col.saturating_add(window.n_cols) > ncols
+        {
+            Err(NitfError::Fatal(format!(
+                "Chip window {window:?} is outside of the {nrows}x{ncols} image"
+            )))?
+        }
+        let src_layout = Layout::new(src)?;
+        if (src_layout.data_size() as u64) > segment.data_size {
+            Err(NitfError::Fatal(
+                "Image data is smaller than the size described by the header".to_string(),
+            ))?
+        }
+
+        let mut header = src.clone();
+        header.nrows.val = window.n_rows;
+        header.ncols.val = window.n_cols;
+        header.nbpr.val = 1;
+        header.nbpc.val = 1;
+        header.nppbh.val = match window.n_cols > MAX_BLOCK_SIZE {
+            true => 0,
+            false => window.n_cols as u16,
+        };
+        header.nppbv.val = match window.n_rows > MAX_BLOCK_SIZE {
+            true => 0,
+            false => window.n_rows as u16,
+        };
+        // The chip is the only image in the new file
+        header.idlvl.val = 1;
+        header.ialvl.val = 0;
+        header.iloc.val = RowColumn::default();
+        update_igeolo(&mut header, src, &window)?;
+        update_geo_tres(&mut header, src, &window)?;
+        update_ichipb(&mut header, src, &window)?;
+
+        let dst_layout = Layout::new(&header)?;
+        let data = read_window(reader, segment, &src_layout, &dst_layout, &window)?;
+
+        let mut nitf_header = self.nitf_header.clone();
+        nitf_header.numi.val = 0;
+        nitf_header.imheaders.clear();
+        nitf_header.nums.val = 0;
+        nitf_header.graphheaders.clear();
+        nitf_header.numx.val = 0;
+        nitf_header.numt.val = 0;
+        nitf_header.textheaders.clear();
+        nitf_header.numdes.val = 0;
+        nitf_header.dextheaders.clear();
+        nitf_header.numres.val = 0;
+        nitf_header.resheaders.clear();
+        let mut nitf = Nitf {
+            nitf_header,
+            ..Default::default()
+        };
+        nitf.add_im(ImageSegment {
+            header,
+            data_size: data.len() as u64,
+            ..Default::default()
+        });
+        nitf.update_headers()?;
+        debug!("Extracted {window:?} from image segment {segment_index}");
+        Ok((nitf, data))
+    }
+}
+
+/// Copy the pixels of `window` into a buffer with the `dst` layout
+pub(crate) fn read_window(
+    reader: &mut (impl Read + Seek),
+    segment: &ImageSegment,
+    src: &Layout,
+    dst: &Layout,
+    window: &ChipWindow,
+) -> NitfResult<Vec<u8>> {
+    let mut data = vec![0u8; dst.data_size()];
+    let (row0, col0) = (window.row as usize, window.col as usize);
+    let col_end = col0 + window.n_cols as usize;
+    // Band interleaved by pixel stores all bands of a run together
+    let (band_runs, run_bands) = match src.mode {
+        Mode::P => (1, src.n_bands),
+        _ => (src.n_bands, 1),
+    };
+    for row in row0..row0 + window.n_rows as usize {
+        let mut col = col0;
+        while col < col_end {
+            // Pixels are only contiguous within a block
+            let run_end = col_end.min((col / src.block_cols + 1) * src.block_cols);
+            let run_size = (run_end - col) * run_bands * src.bytes_per_pixel;
+            for band in 0..band_runs {
+                let src_offset = src.offset(row, col, band) as u64;
+                let dst_offset = dst.offset(row - row0, col - col0, band);
+                reader.seek(SeekFrom::Start(segment.data_offset + src_offset))?;
+                reader.read_exact(&mut data[dst_offset..dst_offset + run_size])?;
+            }
+            col = run_end;
+        }
+    }
+    Ok(data)
+}
+
+/// Chip corner pixels as fractions of the source image size, ordered as IGEOLO
+fn corner_fractions(src: &ImageHeader, window: &ChipWindow) -> [(f64, f64); 4] {
+    let scale = |n: u32| if n > 1 { 1.0 / (n - 1) as f64 } else { 0.0 };
+    let (row_scale, col_scale) = (scale(src.nrows.val), scale(src.ncols.val));
+    let first_row = window.row as f64 * row_scale;
+    let last_row = (window.row + window.n_rows - 1) as f64 * row_scale;
+    let first_col = window.col as f64 * col_scale;
+    let last_col = (window.col + window.n_cols - 1) as f64 * col_scale;
+    [
+        (first_row, first_col),
+        (first_row, last_col),
+        (last_row, last_col),
+        (last_row, first_col),
+    ]
+}
+
+/// Bilinear interpolation of the original corners at chip corners
+fn update_igeolo(
+    header: &mut ImageHeader,
+    src: &ImageHeader,
+    window: &ChipWindow,
+) -> NitfResult<()> {
+    let corners = match src.igeolo_corners() {
+        Ok(Some(corners)) => corners,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            warn!("Removing IGEOLO from chip: {e}");
+            header.icords.val = CoordinateRepresentation::DEFAULT;
+            header.igeolo.val = String::new();
+            return Ok(());
+        }
+    };
+    // Interpolate in the native coordinates of IGEOLO
+    let mut native = [(0.0, 0.0); 4];
+    for (point, corner) in native.iter_mut().zip(&corners) {
+        *point = match (corner, corners[0]) {
+            (ImageCorner::Geographic { lat, lon }, ImageCorner::Geographic { .. }) => (*lat, *lon),
+            (
+                ImageCorner::Utm {
+                    zone,
+                    easting,
+                    northing,
+                },
+                ImageCorner::Utm { zone: zone0, .. },
+            ) if *zone == zone0 => (*northing, *easting),
+            _ => Err(NitfError::Fatal(
+                "Cannot interpolate IGEOLO corners in different UTM zones".to_string(),
+            ))?,
+        };
+    }
+    let mut new_corners = corners;
+    for (corner, (row_frac, col_frac)) in new_corners.iter_mut().zip(corner_fractions(src, window))
+    {
+        *corner = match corner {
+            ImageCorner::Geographic { .. } => {
+                let (lat, lon) = bilinear(&native, row_frac, col_frac, true);
+                ImageCorner::Geographic { lat, lon }
+            }
+            ImageCorner::Utm { zone, .. } => {
+                let (northing, easting) = bilinear(&native, row_frac, col_frac, false);
+                ImageCorner::Utm {
+                    zone: *zone,
+                    easting,
+                    northing,
+                }
+            }
+        };
+    }
+    header.set_igeolo_corners(&new_corners)
+}
+
+/// Replace a TRE in whichever extended subheader it is already located
+fn replace_tre<T: Tre>(header: &mut ImageHeader, tre: &T) -> NitfResult<()> {
+    match header.ixshd.get_tre::<T>()?.is_some() {
+        true => header.ixshd.put_tre(tre),
+        false => header.udid.put_tre(tre),
+    }
+}
+
+/// Move the origins of GEOLOB and MAPLOB, and the corners of BLOCKA, to the chip
+fn update_geo_tres(
+    header: &mut ImageHeader,
+    src: &ImageHeader,
+    window: &ChipWindow,
+) -> NitfResult<()> {
+    let (row, col) = (window.row as f64, window.col as f64);
+    if let Some(mut geolob) = src.get_tre::<Geolob>()? {
+        (geolob.pso, geolob.lso) = geolob.pixel_to_ground(row, col);
+        replace_tre(header, &geolob)?;
+    }
+    if let Some(mut maplob) = src.get_tre::<Maplob>()? {
+        maplob.lso += col * maplob.lod as f64;
+        maplob.pso -= row * maplob.lad as f64;
+        replace_tre(header, &maplob)?;
+    }
+    if let Some(mut blocka) = src.get_tre::<Blocka>()? {
+        if let Some(corners) = blocka.corners() {
+            let fractions = corner_fractions(src, window);
+            let new_corners =
+                fractions.map(|(row_frac, col_frac)| bilinear(&corners, row_frac, col_frac, true));
+            blocka.set_corners(&new_corners);
+        }
+        blocka.l_lines = window.n_rows;
+        replace_tre(header, &blocka)?;
+    }
+    Ok(())
+}
+
+/// Add ICHIPB, composing with an existing ICHIPB if the source is a chip
+fn update_ichipb(
+    header: &mut ImageHeader,
+    src: &ImageHeader,
+    window: &ChipWindow,
+) -> NitfResult<()> {
+    let mut ichipb = Ichipb::from_window(
+        window.row,
+        window.col,
+        window.n_rows,
+        window.n_cols,
+        src.nrows.val,
+        src.ncols.val,
+    );
+    if let Some(src_ichipb) = src.ichipb()? {
+        let grid_points = [
+            (&mut ichipb.fi_row_11, &mut ichipb.fi_col_11),
+            (&mut ichipb.fi_row_12, &mut ichipb.fi_col_12),
+            (&mut ichipb.fi_row_21, &mut ichipb.fi_col_21),
+            (&mut ichipb.fi_row_22, &mut ichipb.fi_col_22),
+        ];
+        if src_ichipb.xfrm_flag == 0 {
+            for (row, col) in grid_points {
+                // Grid points are half a pixel from pixel centers
+                let (full_row, full_col) = src_ichipb.chip_to_full(*row - 0.5, *col - 0.5)?;
+                (*row, *col) = (full_row + 0.5, full_col + 0.5);
+            }
+        } else {
+            warn!("Source ICHIPB transform is not applicable, and is not composed");
+            ichipb.xfrm_flag = src_ichipb.xfrm_flag;
+        }
+        ichipb.fi_row = src_ichipb.fi_row;
+        ichipb.fi_col = src_ichipb.fi_col;
+        ichipb.scale_factor = src_ichipb.scale_factor;
+        ichipb.anamrph_corr = src_ichipb.anamrph_corr;
+        ichipb.scanblk_num = src_ichipb.scanblk_num;
+    }
+    // ICHIPB is located in the image extended subheader
+    header.udid.remove_tre("ICHIPB")?;
+    header.ixshd.put_tre(&ichipb)?;
+    header.update_lengths()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::image_hdr::Band;
+    use std::io::Cursor;
+
+    /// Pixel value of the synthetic image
+    fn pixel(row: usize, col: usize, band: usize) -> [u8; 2] {
+        [band as u8, (row * 16 + col) as u8]
+    }
+
+    /// 6 x 7 image with two 16-bit bands in 4 x 3 blocks, so the last block
+    /// row and column are padded, with decimal degree corners
+    fn blocked_image(mode: Mode) -> (Nitf, Cursor<Vec<u8>>) {
+        let mut seg = ImageSegment::default();
+        let header = &mut seg.header;
+        header.nrows.val = 6;
+        header.ncols.val = 7;
+        header.nbpp.val = 16;
+        header.abpp.val = 16;
+        header.nbands.val = 2;
+        header.bands = vec![Band::default(), Band::default()];
+        header.imode.val = mode;
+        header.nppbv.val = 4;
+        header.nppbh.val = 3;
+        header.nbpr.val = 3;
+        header.nbpc.val = 2;
+        header.icords.val = CoordinateRepresentation::D;
+        // Latitude decreases by one degree per row, longitude increases by
+        // one degree per column
+        let corner = |lat: f64, lon: f64| ImageCorner::Geographic { lat, lon };
+        header
+            .set_igeolo_corners(&[
+                corner(10.0, 20.0),
+                corner(10.0, 26.0),
+                corner(5.0, 26.0),
+                corner(5.0, 20.0),
+            ])
+            .unwrap();
+
+        let layout = Layout::new(header).unwrap();
+        let mut data = vec![0; layout.data_size()];
+        for row in 0..6 {
+            for col in 0..7 {
+                for band in 0..2 {
+                    let offset = layout.offset(row, col, band);
+                    data[offset..offset + 2].copy_from_slice(&pixel(row, col, band));
+                }
+            }
+        }
+        seg.data_size = data.len() as u64;
+        let mut nitf = Nitf::default();
+        nitf.add_im(seg);
+        let mut file = Cursor::new(vec![]);
+        nitf.write_headers(&mut file).unwrap();
+        nitf.image_segments[0].write_data(&mut file, &data).unwrap();
+        file.set_position(0);
+        (Nitf::from_reader(&mut file).unwrap(), file)
+    }
+
+    #[test]
+    fn chip_blocked_image() {
+        for mode in [Mode::B, Mode::P, Mode::R, Mode::S] {
+            let (nitf, mut file) = blocked_image(mode);
+            // Spans two block rows and three block columns
+            let window = ChipWindow::new(1, 2, 4, 5);
+            let (chip, data) = nitf.chip(&mut file, 0, window).unwrap();
+
+            let header = &chip.image_segments[0].header;
+            assert_eq!((header.nrows.val, header.ncols.val), (4, 5));
+            assert_eq!((header.nbpr.val, header.nbpc.val), (1, 1));
+            assert_eq!((header.nppbh.val, header.nppbv.val), (5, 4));
+            assert_eq!(header.imode.val, mode);
+            assert_eq!(chip.image_segments[0].data_size, 4 * 5 * 2 * 2);
+
+            let layout = Layout::new(header).unwrap();
+            assert_eq!(layout.data_size(), data.len());
+            for row in 0..4 {
+                for col in 0..5 {
+                    for band in 0..2 {
+                        let offset = layout.offset(row, col, band);
+                        assert_eq!(
+                            data[offset..offset + 2],
+                            pixel(row + 1, col + 2, band),
+                            "{mode:?} ({row}, {col}) band {band}"
+                        );
+                    }
+                }
+            }
+
+            let corners = header.igeolo_corners().unwrap().unwrap();
+            let expected = [(9.0, 22.0), (9.0, 26.0), (6.0, 26.0), (6.0, 22.0)];
+            for (corner, (lat0, lon0)) in corners.iter().zip(expected) {
+                let ImageCorner::Geographic { lat, lon } = corner else {
+                    panic!("{corner:?}");
+                };
+                assert!((lat - lat0).abs() < 1e-3 && (lon - lon0).abs() < 1e-3);
+            }
+
+            let ichipb = header.ichipb().unwrap().unwrap();
+            assert_eq!((ichipb.fi_row, ichipb.fi_col), (6, 7));
+            let (row, col) = ichipb.chip_to_full(0.0, 0.0).unwrap();
+            assert!((row - 1.0).abs() < 1e-9 && (col - 2.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn chip_of_chip() {
+        let (nitf, mut file) = blocked_image(Mode::B);
+        let (mut chip, data) = nitf
+            .chip(&mut file, 0, ChipWindow::new(1, 2, 4, 5))
+            .unwrap();
+        let mut chip_file = Cursor::new(vec![]);
+        chip.write_headers(&mut chip_file).unwrap();
+        chip.image_segments[0]
+            .write_data(&mut chip_file, &data)
+            .unwrap();
+        chip_file.set_position(0);
+        let chip = Nitf::from_reader(&mut chip_file).unwrap();
+
+        let window = ChipWindow::new(2, 1, 2, 3);
+        let (chip, data) = chip.chip(&mut chip_file, 0, window).unwrap();
+        let header = &chip.image_segments[0].header;
+        let layout = Layout::new(header).unwrap();
+        let offset = layout.offset(0, 0, 1);
+        assert_eq!(data[offset..offset + 2], pixel(3, 3, 1));
+        // ICHIPB locates the chip in the original image
+        let ichipb = header.ichipb().unwrap().unwrap();
+        let (row, col) = ichipb.chip_to_full(1.0, 2.0).unwrap();
+        assert!((row - 4.0).abs() < 1e-9 && (col - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn window_outside_image() {
+        let (nitf, mut file) = blocked_image(Mode::B);
+        assert!(nitf
+            .chip(&mut file, 0, ChipWindow::new(3, 0, 4, 1))
+            .is_err());
+        assert!(nitf
+            .chip(&mut file, 0, ChipWindow::new(0, 0, 0, 1))
+            .is_err());
+        assert!(nitf
+            .chip(&mut file, 1, ChipWindow::new(0, 0, 1, 1))
+            .is_err());
+    }
+}
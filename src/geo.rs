@@ -0,0 +1,568 @@
+//! Geolocation of image pixels
+//!
+//! [Geolocation] collects the georeferencing available for an image segment.
+//! The most precise source is used, in order of precedence: GEOLOB,
+//! MAPLOB with GEOPSB, BLOCKA, then IGEOLO.
+use crate::headers::image_hdr::{CoordinateRepresentation, ImageCorner};
+use crate::headers::ImageHeader;
+use crate::tre::{Blocka, Geolob, Geopsb, Maplob};
+use crate::{Nitf, NitfError, NitfResult};
+
+/// UTM central scale factor
+const UTM_SCALE: f64 = 0.9996;
+/// UTM false easting in meters
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+/// UTM false northing in the southern hemisphere, in meters
+const UTM_FALSE_NORTHING: f64 = 10_000_000.0;
+/// Largest UTM northing in the northern hemisphere, at 84 degrees north
+const UTM_MAX_NORTHING: f64 = 9_330_000.0;
+
+/// Reference ellipsoid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipsoid {
+    /// Semi-major axis in meters
+    pub semi_major: f64,
+    /// Inverse flattening
+    pub inv_flattening: f64,
+}
+impl Ellipsoid {
+    pub const WGS84: Self = Self {
+        semi_major: 6_378_137.0,
+        inv_flattening: 298.257223563,
+    };
+    pub const GRS80: Self = Self {
+        semi_major: 6_378_137.0,
+        inv_flattening: 298.257222101,
+    };
+    pub const CLARKE_1866: Self = Self {
+        semi_major: 6_378_206.4,
+        inv_flattening: 294.978698214,
+    };
+    pub const INTERNATIONAL_1924: Self = Self {
+        semi_major: 6_378_388.0,
+        inv_flattening: 297.0,
+    };
+
+    /// First eccentricity squared
+    fn ecc_sq(&self) -> f64 {
+        let f = 1.0 / self.inv_flattening;
+        f * (2.0 - f)
+    }
+}
+
+/// Convert UTM coordinates in meters to `(latitude, longitude)` in degrees
+pub fn utm_to_geodetic(
+    zone: u8,
+    south: bool,
+    easting: f64,
+    northing: f64,
+    ellipsoid: &Ellipsoid,
+) -> NitfResult<(f64, f64)> {
+    if !(1..=60).contains(&zone) {
+        Err(NitfError::Value(format!("UTM zone {zone}")))?
+    }
+    let a = ellipsoid.semi_major;
+    let e2 = ellipsoid.ecc_sq();
+    let ep2 = e2 / (1.0 - e2);
+    let x = easting - UTM_FALSE_EASTING;
+    let y = match south {
+        true => northing - UTM_FALSE_NORTHING,
+        false => northing,
+    };
+
+    // Footprint latitude
+    let m = y / UTM_SCALE;
+    let mu = m / (a * (1.0 - e2 / 4.0 - 3.0 * e2.powi(2) / 64.0 - 5.0 * e2.powi(3) / 256.0));
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+    let phi1 = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1.powi(2) / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+        + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+    let (sin1, cos1, tan1) = (phi1.sin(), phi1.cos(), phi1.tan());
+    let c1 = ep2 * cos1.powi(2);
+    let t1 = tan1.powi(2);
+    let n1 = a / (1.0 - e2 * sin1.powi(2)).sqrt();
+    let r1 = a * (1.0 - e2) / (1.0 - e2 * sin1.powi(2)).powf(1.5);
+    let d = x / (n1 * UTM_SCALE);
+
+    let lat = phi1
+        - (n1 * tan1 / r1)
+            * (d.powi(2) / 2.0
+                - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1.powi(2) - 9.0 * ep2) * d.powi(4) / 24.0
+                + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1.powi(2)
+                    - 252.0 * ep2
+                    - 3.0 * c1.powi(2))
+                    * d.powi(6)
+                    / 720.0);
+    let central_meridian = (zone as f64 - 1.0) * 6.0 - 180.0 + 3.0;
+    let lon = central_meridian
+        + (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+            + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1.powi(2) + 8.0 * ep2 + 24.0 * t1.powi(2))
+                * d.powi(5)
+                / 120.0)
+            .to_degrees()
+            / cos1;
+    Ok((lat.to_degrees(), wrap_longitude(lon)))
+}
+
+/// Wrap a longitude to [-180, 180)
+fn wrap_longitude(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Bilinear interpolation between corner points ordered as IGEOLO, at
+/// fractional positions along the rows and columns.
+///
+/// If `longitude`, the second coordinate is unwrapped across the
+/// antimeridian before interpolating.
+pub(crate) fn bilinear(
+    corners: &[(f64, f64); 4],
+    row_frac: f64,
+    col_frac: f64,
+    longitude: bool,
+) -> (f64, f64) {
+    let (v, u) = (row_frac, col_frac);
+    let weights = [(1.0 - u) * (1.0 - v), u * (1.0 - v), u * v, (1.0 - u) * v];
+    let first = corners[0].1;
+    let (mut y, mut x) = (0.0, 0.0);
+    for ((corner_y, corner_x), weight) in corners.iter().zip(weights) {
+        let corner_x = match longitude {
+            true => corner_x - 360.0 * ((corner_x - first) / 360.0).round(),
+            false => *corner_x,
+        };
+        y += weight * corner_y;
+        x += weight * corner_x;
+    }
+    match longitude {
+        true => (y, wrap_longitude(x)),
+        false => (y, x),
+    }
+}
+
+/// Parse `ddmmss[.ss]H` or `dddmmss[.ss]H` to decimal degrees
+pub(crate) fn parse_dms(s: &str, deg_width: usize) -> NitfResult<f64> {
+    let error = || NitfError::ParseError(format!("DMS value {s:?}"));
+    let (digits, hemisphere) = s
+        .split_at_checked(s.len().saturating_sub(1))
+        .ok_or_else(error)?;
+    let deg: f64 = digits
+        .get(..deg_width)
+        .and_then(|d| d.parse().ok())
+        .ok_or_else(error)?;
+    let min: f64 = digits
+        .get(deg_width..deg_width + 2)
+        .and_then(|m| m.parse().ok())
+        .ok_or_else(error)?;
+    let sec: f64 = digits
+        .get(deg_width + 2..)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(error)?;
+    let value = deg + min / 60.0 + sec / 3600.0;
+    match hemisphere {
+        "N" | "E" => Ok(value),
+        "S" | "W" => Ok(-value),
+        _ => Err(error()),
+    }
+}
+
+/// Format decimal degrees as `ddmmss[.ss]H` or `dddmmss[.ss]H`
+pub(crate) fn format_dms(
+    value: f64,
+    deg_width: usize,
+    sec_decimals: usize,
+    latitude: bool,
+) -> String {
+    let hemisphere = match (latitude, value < 0.0) {
+        (true, false) => 'N',
+        (true, true) => 'S',
+        (false, false) => 'E',
+        (false, true) => 'W',
+    };
+    // Round in units of the last decimal to carry into minutes and degrees
+    let scale = 10f64.powi(sec_decimals as i32);
+    let units = (value.abs() * 3600.0 * scale).round() as u64;
+    let sec_units = 60 * scale as u64;
+    let deg = units / (60 * sec_units);
+    let min = (units / sec_units) % 60;
+    let sec = (units % sec_units) as f64 / scale;
+    let sec_width = match sec_decimals {
+        0 => 2,
+        n => n + 3,
+    };
+    format!("{deg:0deg_width$}{min:02}{sec:0sec_width$.sec_decimals$}{hemisphere}")
+}
+
+/// Georeferencing of an image segment
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geolocation {
+    /// Regular latitude/longitude grid from GEOLOB
+    Grid(Geolob),
+    /// Regular UTM grid from MAPLOB, with projection information from GEOPSB
+    Map {
+        maplob: Maplob,
+        geopsb: Box<Geopsb>,
+        /// Grid is in the southern hemisphere
+        south: bool,
+    },
+    /// `(latitude, longitude)` corners from BLOCKA or IGEOLO, interpolated
+    /// bilinearly. Corners are ordered as in
+    /// [ImageHeader::igeolo_corners()].
+    Corners {
+        corners: [(f64, f64); 4],
+        n_rows: u32,
+        n_cols: u32,
+    },
+}
+impl Geolocation {
+    /// Ground `(latitude, longitude)` in degrees of a `(row, column)` pixel
+    pub fn pixel_to_ground(&self, row: f64, col: f64) -> NitfResult<(f64, f64)> {
+        match self {
+            Self::Grid(geolob) => Ok(geolob.pixel_to_ground(row, col)),
+            Self::Map {
+                maplob,
+                geopsb,
+                south,
+            } => {
+                let zone = match (geopsb.grd.as_str(), geopsb.zna) {
+                    ("UTM", Some(zone)) if zone <= u8::MAX as u16 => zone as u8,
+                    _ => Err(NitfError::Fatal(format!(
+                        "Unsupported GEOPSB grid {:?} zone {:?}",
+                        geopsb.grd, geopsb.zna
+                    )))?,
+                };
+                let (easting, northing) = maplob.pixel_to_map(row, col)?;
+                utm_to_geodetic(zone, *south, easting, northing, &geopsb.ellipsoid()?)
+            }
+            Self::Corners {
+                corners,
+                n_rows,
+                n_cols,
+            } => {
+                let frac = |x: f64, n: u32| if n > 1 { x / (n - 1) as f64 } else { 0.0 };
+                Ok(bilinear(
+                    corners,
+                    frac(row, *n_rows),
+                    frac(col, *n_cols),
+                    true,
+                ))
+            }
+        }
+    }
+}
+
+impl ImageHeader {
+    /// Most precise georeferencing available from this segment's TREs and
+    /// IGEOLO. `None` if the segment is not georeferenced.
+    ///
+    /// Only a GEOPSB in this segment is used with MAPLOB; see
+    /// [Nitf::geolocation()] to also search the file header.
+    pub fn geolocation(&self) -> NitfResult<Option<Geolocation>> {
+        self.geolocation_with(None)
+    }
+
+    /// Ground `(latitude, longitude)` in degrees of a `(row, column)` pixel,
+    /// using [ImageHeader::geolocation()]
+    pub fn pixel_to_ground(&self, row: f64, col: f64) -> NitfResult<Option<(f64, f64)>> {
+        match self.geolocation()? {
+            Some(geolocation) => geolocation.pixel_to_ground(row, col).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// `(latitude, longitude)` of the corner pixels, ordered as in
+    /// [ImageHeader::igeolo_corners()], using [ImageHeader::geolocation()]
+    pub fn corner_coordinates(&self) -> NitfResult<Option<[(f64, f64); 4]>> {
+        match self.geolocation()? {
+            Some(geolocation) => {
+                let last_row = self.nrows.val.saturating_sub(1) as f64;
+                let last_col = self.ncols.val.saturating_sub(1) as f64;
+                Ok(Some([
+                    geolocation.pixel_to_ground(0.0, 0.0)?,
+                    geolocation.pixel_to_ground(0.0, last_col)?,
+                    geolocation.pixel_to_ground(last_row, last_col)?,
+                    geolocation.pixel_to_ground(last_row, 0.0)?,
+                ]))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Whether UTM coordinates with `northing` are in the southern
+    /// hemisphere.
+    ///
+    /// ICORDS only describes IGEOLO, so it is used for UTM IGEOLO. Otherwise
+    /// the hemisphere comes from the latitudes of geographic IGEOLO or BLOCKA
+    /// corners, and without those, from the northing, which is only above
+    /// [UTM_MAX_NORTHING] in the southern hemisphere.
+    fn utm_south(&self, northing: f64) -> bool {
+        use CoordinateRepresentation::*;
+        match self.icords.val {
+            N => return false,
+            S => return true,
+            _ => {}
+        }
+        let igeolo = self.igeolo_corners().ok().flatten().map(|corners| {
+            corners.map(|corner| match corner {
+                ImageCorner::Geographic { lat, .. } => lat,
+                ImageCorner::Utm { .. } => 0.0,
+            })
+        });
+        let blocka = || {
+            let corners = self.get_tre::<Blocka>().ok().flatten()?.corners()?;
+            Some(corners.map(|(lat, _)| lat))
+        };
+        match igeolo.or_else(blocka) {
+            Some(latitudes) => latitudes.iter().sum::<f64>() < 0.0,
+            None => northing > UTM_MAX_NORTHING && northing <= UTM_FALSE_NORTHING,
+        }
+    }
+
+    /// Georeferencing with an optional GEOPSB from elsewhere in the file
+    pub(crate) fn geolocation_with(
+        &self,
+        file_geopsb: Option<Geopsb>,
+    ) -> NitfResult<Option<Geolocation>> {
+        if let Some(geolob) = self.get_tre::<Geolob>()? {
+            return Ok(Some(Geolocation::Grid(geolob)));
+        }
+        if let Some(maplob) = self.get_tre::<Maplob>()? {
+            match self.get_tre::<Geopsb>()?.or(file_geopsb) {
+                Some(geopsb) => {
+                    let northing = maplob.pixel_to_map(0.0, 0.0).map_or(0.0, |(_, n)| n);
+                    let south = self.utm_south(northing);
+                    return Ok(Some(Geolocation::Map {
+                        maplob,
+                        geopsb: Box::new(geopsb),
+                        south,
+                    }));
+                }
+                None => log::warn!("Ignoring MAPLOB without GEOPSB"),
+            }
+        }
+        let (n_rows, n_cols) = (self.nrows.val, self.ncols.val);
+        if let Some(corners) = self.get_tre::<Blocka>()?.and_then(|b| b.corners()) {
+            return Ok(Some(Geolocation::Corners {
+                corners,
+                n_rows,
+                n_cols,
+            }));
+        }
+        let Some(igeolo) = self.igeolo_corners()? else {
+            return Ok(None);
+        };
+        let mut corners = [(0.0, 0.0); 4];
+        for (corner, igeolo_corner) in corners.iter_mut().zip(igeolo) {
+            *corner = match igeolo_corner {
+                ImageCorner::Geographic { lat, lon } => (lat, lon),
+                ImageCorner::Utm {
+                    zone,
+                    easting,
+                    northing,
+                } => {
+                    let south = self.utm_south(northing);
+                    utm_to_geodetic(zone, south, easting, northing, &Ellipsoid::WGS84)?
+                }
+            };
+        }
+        Ok(Some(Geolocation::Corners {
+            corners,
+            n_rows,
+            n_cols,
+        }))
+    }
+}
+
+impl Nitf {
+    /// Georeferencing of an image segment, as [ImageHeader::geolocation()].
+    ///
+    /// A GEOPSB in the file header is used for MAPLOB if the image segment
+    /// does not have its own.
+    pub fn geolocation(&self, segment_index: usize) -> NitfResult<Option<Geolocation>> {
+        let segment = self.image_segments.get(segment_index).ok_or_else(|| {
+            NitfError::Fatal(format!("No image segment at index {segment_index}"))
+        })?;
+        let file_geopsb = self.nitf_header.get_tre::<Geopsb>()?;
+        segment.header.geolocation_with(file_geopsb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close((lat, lon): (f64, f64), expected: (f64, f64), tolerance: f64) {
+        assert!(
+            (lat - expected.0).abs() < tolerance && (lon - expected.1).abs() < tolerance,
+            "{:?} != {expected:?}",
+            (lat, lon)
+        );
+    }
+
+    #[test]
+    fn utm_known_points() {
+        let wgs84 = &Ellipsoid::WGS84;
+        let point = |zone, south, easting, northing| {
+            utm_to_geodetic(zone, south, easting, northing, wgs84).unwrap()
+        };
+        assert_close(point(31, false, 500_000.0, 0.0), (0.0, 3.0), 1e-9);
+        // Equator at the western edge of zone 31
+        assert_close(point(31, false, 166_021.443, 0.0), (0.0, 0.0), 1e-6);
+        // 45 degrees on the central meridian is 0.9996 of the meridian arc
+        assert_close(point(33, false, 500_000.0, 4_982_950.4), (45.0, 15.0), 1e-5);
+        assert_close(point(33, true, 500_000.0, 5_017_049.6), (-45.0, 15.0), 1e-5);
+        assert_close(point(1, false, 500_000.0, 0.0), (0.0, -177.0), 1e-9);
+        assert!(utm_to_geodetic(0, false, 500_000.0, 0.0, wgs84).is_err());
+        assert!(utm_to_geodetic(61, false, 500_000.0, 0.0, wgs84).is_err());
+    }
+
+    #[test]
+    fn dms() {
+        assert_eq!(parse_dms("123045N", 2).unwrap(), 12.5125);
+        assert_eq!(
+            parse_dms("0013030.50W", 3).unwrap(),
+            -(1.0 + 30.5 / 60.0 + 0.5 / 3600.0)
+        );
+        assert!(parse_dms("123045X", 2).is_err());
+        assert!(parse_dms("12a045N", 2).is_err());
+        assert!(parse_dms("", 2).is_err());
+
+        assert_eq!(format_dms(12.5125, 2, 0, true), "123045N");
+        assert_eq!(format_dms(-12.5125, 2, 0, true), "123045S");
+        assert_eq!(
+            format_dms(-(1.0 + 30.5 / 60.0 + 0.5 / 3600.0), 3, 2, false),
+            "0013030.50W"
+        );
+        // Rounding carries into minutes and degrees
+        assert_eq!(format_dms(10.0 - 1e-5, 2, 0, true), "100000N");
+        for value in [0.0, 45.25, -89.999, 179.5] {
+            let formatted = format_dms(value, 3, 2, false);
+            assert!((parse_dms(&formatted, 3).unwrap() - value).abs() < 0.01 / 3600.0);
+        }
+    }
+
+    #[test]
+    fn bilinear_interpolation() {
+        let corners = [(10.0, 20.0), (10.0, 30.0), (0.0, 30.0), (0.0, 20.0)];
+        assert_eq!(bilinear(&corners, 0.0, 0.0, true), (10.0, 20.0));
+        assert_eq!(bilinear(&corners, 1.0, 1.0, true), (0.0, 30.0));
+        assert_eq!(bilinear(&corners, 0.5, 0.25, true), (5.0, 22.5));
+        // Across the antimeridian
+        let corners = [(1.0, 179.0), (1.0, -179.0), (-1.0, -179.0), (-1.0, 179.0)];
+        assert_close(bilinear(&corners, 0.5, 0.75, true), (0.0, -179.5), 1e-9);
+        let (_, x) = bilinear(&corners, 0.5, 0.75, false);
+        assert_close((0.0, x), (0.0, -89.5), 1e-9);
+    }
+
+    fn header_with_igeolo(icords: CoordinateRepresentation, lat: f64) -> ImageHeader {
+        let mut header = ImageHeader::default();
+        header.nrows.val = 11;
+        header.ncols.val = 11;
+        header.icords.val = icords;
+        let corner = |lat: f64, lon: f64| ImageCorner::Geographic { lat, lon };
+        header
+            .set_igeolo_corners(&[
+                corner(lat, 20.0),
+                corner(lat, 21.0),
+                corner(lat - 1.0, 21.0),
+                corner(lat - 1.0, 20.0),
+            ])
+            .unwrap();
+        header
+    }
+
+    fn utm_tres(northing: f64) -> (Maplob, Geopsb) {
+        let maplob = Maplob {
+            uniloa: "M".to_string(),
+            lod: 10,
+            lad: 10,
+            lso: 500_000.0,
+            pso: northing,
+        };
+        let geopsb = Geopsb {
+            typ: "MAP".to_string(),
+            uni: "M".to_string(),
+            dag: "World Geodetic System 1984".to_string(),
+            dcd: "WGE".to_string(),
+            ell: "World Geodetic System 1984".to_string(),
+            elc: "WE".to_string(),
+            grd: "UTM".to_string(),
+            zna: Some(33),
+            ..Default::default()
+        };
+        (maplob, geopsb)
+    }
+
+    #[test]
+    fn precedence() {
+        let mut header = header_with_igeolo(CoordinateRepresentation::D, 10.0);
+        let igeolo = [(10.0, 20.0), (10.0, 21.0), (9.0, 21.0), (9.0, 20.0)];
+        let corners = |header: &ImageHeader| match header.geolocation().unwrap() {
+            Some(Geolocation::Corners { corners, .. }) => corners,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(corners(&header), igeolo);
+
+        let mut blocka = Blocka::default();
+        let blocka_corners = [(10.5, 20.0), (10.5, 21.0), (9.5, 21.0), (9.5, 20.0)];
+        blocka.set_corners(&blocka_corners);
+        header.ixshd.put_tre(&blocka).unwrap();
+        assert_eq!(corners(&header), blocka_corners);
+
+        // MAPLOB is only used with GEOPSB
+        let (maplob, geopsb) = utm_tres(4_982_950.4);
+        header.ixshd.put_tre(&maplob).unwrap();
+        assert_eq!(corners(&header), blocka_corners);
+        header.ixshd.put_tre(&geopsb).unwrap();
+        let geolocation = header.geolocation().unwrap().unwrap();
+        assert!(matches!(geolocation, Geolocation::Map { south: false, .. }));
+        assert_close(
+            geolocation.pixel_to_ground(0.0, 0.0).unwrap(),
+            (45.0, 15.0),
+            1e-5,
+        );
+
+        let geolob = Geolob {
+            arv: 360_000,
+            brv: 360_000,
+            lso: 20.0,
+            pso: 10.0,
+        };
+        header.ixshd.put_tre(&geolob).unwrap();
+        let geolocation = header.geolocation().unwrap().unwrap();
+        assert_eq!(geolocation, Geolocation::Grid(geolob));
+        assert_close(
+            geolocation.pixel_to_ground(100.0, 100.0).unwrap(),
+            (9.9, 20.1),
+            1e-9,
+        );
+    }
+
+    #[test]
+    fn maplob_hemisphere() {
+        use CoordinateRepresentation::*;
+        let south = |header: &mut ImageHeader, northing: f64| {
+            let (maplob, geopsb) = utm_tres(northing);
+            header.ixshd.put_tre(&maplob).unwrap();
+            header.ixshd.put_tre(&geopsb).unwrap();
+            match header.geolocation().unwrap() {
+                Some(Geolocation::Map { south, .. }) => south,
+                other => panic!("{other:?}"),
+            }
+        };
+        // From the IGEOLO latitudes, whatever the northing
+        assert!(south(&mut header_with_igeolo(D, -30.0), 5_017_049.6));
+        assert!(south(&mut header_with_igeolo(G, -30.0), 5_017_049.6));
+        assert!(!south(&mut header_with_igeolo(D, 50.0), 5_017_049.6));
+        // From BLOCKA without IGEOLO
+        let mut header = ImageHeader::default();
+        let mut blocka = Blocka::default();
+        blocka.set_corners(&[(-1.0, 20.0), (-1.0, 21.0), (-2.0, 21.0), (-2.0, 20.0)]);
+        header.ixshd.put_tre(&blocka).unwrap();
+        assert!(south(&mut header, 9_800_000.0));
+        // From the northing alone
+        assert!(!south(&mut ImageHeader::default(), 5_017_049.6));
+        assert!(south(&mut ImageHeader::default(), 9_800_000.0));
+    }
+}
@@ -0,0 +1,262 @@
+//! CSSHPA shapefile data extension segment
+//!
+//! The segment data holds the SHP, SHX, and DBF files of an ESRI shapefile,
+//! located by the offsets in the user-defined subheader.
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+
+use crate::headers::DataExtensionHeader;
+use crate::tre::{read_string, read_value, write_int, write_string};
+use crate::{DataExtensionSegment, NitfError, NitfResult};
+
+/// DESID of shapefile segments
+pub const CSSHPA: &str = "CSSHPA DES";
+
+/// SHAPE_USE value which is followed by CC_SOURCE
+const CLOUD_SHAPES: &str = "CLOUD_SHAPES";
+
+/// CSSHPA user-defined subheader
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Csshpa {
+    /// Shapefile Use, such as `IMAGE_SHAPE` or `CLOUD_SHAPES`
+    pub shape_use: String,
+    /// Shapefile Class, such as `POLYGON`
+    pub shape_class: String,
+    /// Cloud Cover Source. Only present when `shape_use` is `CLOUD_SHAPES`
+    pub cc_source: String,
+    /// Offset of the SHP file in the segment data
+    pub shp_start: u32,
+    /// Offset of the SHX file in the segment data
+    pub shx_start: u32,
+    /// Offset of the DBF file in the segment data
+    pub dbf_start: u32,
+}
+
+impl Csshpa {
+    fn has_cc_source(&self) -> bool {
+        self.shape_use == CLOUD_SHAPES
+    }
+
+    /// Parse the subheader from the DESSHF bytes
+    pub fn from_bytes(bytes: &[u8]) -> NitfResult<Self> {
+        let reader = &mut Cursor::new(bytes);
+        let mut csshpa = Self {
+            shape_use: read_string(reader, 25, "SHAPE_USE")?,
+            shape_class: read_string(reader, 10, "SHAPE_CLASS")?,
+            ..Default::default()
+        };
+        if csshpa.has_cc_source() {
+            csshpa.cc_source = read_string(reader, 18, "CC_SOURCE")?;
+        }
+        for (name, start) in [
+            ("SHAPE1_NAME", "SHAPE1_START"),
+            ("SHAPE2_NAME", "SHAPE2_START"),
+            ("SHAPE3_NAME", "SHAPE3_START"),
+        ] {
+            let shape = read_string(reader, 3, name)?;
+            let offset = read_value(reader, 6, start)?;
+            match shape.as_str() {
+                "SHP" => csshpa.shp_start = offset,
+                "SHX" => csshpa.shx_start = offset,
+                "DBF" => csshpa.dbf_start = offset,
+                _ => Err(NitfError::ParseError(format!("{name}: {shape:?}")))?,
+            }
+        }
+        Ok(csshpa)
+    }
+
+    /// Format the subheader as DESSHF bytes
+    pub fn to_bytes(&self) -> NitfResult<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(80);
+        let writer = &mut bytes;
+        write_string(writer, &self.shape_use, 25, "SHAPE_USE")?;
+        write_string(writer, &self.shape_class, 10, "SHAPE_CLASS")?;
+        if self.has_cc_source() {
+            write_string(writer, &self.cc_source, 18, "CC_SOURCE")?;
+        }
+        write_string(writer, "SHP", 3, "SHAPE1_NAME")?;
+        write_int(writer, self.shp_start.into(), 6, "SHAPE1_START")?;
+        write_string(writer, "SHX", 3, "SHAPE2_NAME")?;
+        write_int(writer, self.shx_start.into(), 6, "SHAPE2_START")?;
+        write_string(writer, "DBF", 3, "SHAPE3_NAME")?;
+        write_int(writer, self.dbf_start.into(), 6, "SHAPE3_START")?;
+        Ok(bytes)
+    }
+}
+
+/// Components of an ESRI shapefile
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Shapefile {
+    /// Main file with the shape geometry
+    pub shp: Vec<u8>,
+    /// Index of the shape geometry
+    pub shx: Vec<u8>,
+    /// Attributes of each shape
+    pub dbf: Vec<u8>,
+}
+
+impl Shapefile {
+    /// Split CSSHPA segment data into its components.
+    ///
+    /// Each file runs to the start of the next one, or the end of the data.
+    /// Files with the same offset are taken in SHAPE1 to SHAPE3 order as
+    /// written by [Csshpa::to_bytes()], so all but the last of them are empty.
+    pub fn from_data(csshpa: &Csshpa, data: &[u8]) -> NitfResult<Self> {
+        let mut starts = [csshpa.shp_start, csshpa.shx_start, csshpa.dbf_start]
+            .map(|start| start as usize)
+            .into_iter()
+            .enumerate()
+            .collect::<Vec<_>>();
+        // Stable, so equal offsets keep the SHP, SHX, DBF order
+        starts.sort_by_key(|(_, start)| *start);
+        let mut components: [Vec<u8>; 3] = Default::default();
+        for (i, (component, start)) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).map_or(data.len(), |(_, next)| *next);
+            components[*component] = data
+                .get(*start..end)
+                .ok_or(NitfError::Fatal(format!(
+                    "{CSSHPA} offset {start} is outside of {} data bytes",
+                    data.len()
+                )))?
+                .to_vec();
+        }
+        let [shp, shx, dbf] = components;
+        Ok(Self { shp, shx, dbf })
+    }
+
+    /// Concatenate the components into segment data, setting the offsets of
+    /// `csshpa`
+    pub fn to_data(&self, csshpa: &mut Csshpa) -> NitfResult<Vec<u8>> {
+        let offset = |length: usize| {
+            u32::try_from(length).or(Err(NitfError::Value("SHAPE_START".to_string())))
+        };
+        csshpa.shp_start = 0;
+        csshpa.shx_start = offset(self.shp.len())?;
+        csshpa.dbf_start = offset(self.shp.len() + self.shx.len())?;
+        Ok([self.shp.as_slice(), &self.shx, &self.dbf].concat())
+    }
+
+    /// Read `<stem>.shp`, `<stem>.shx`, and `<stem>.dbf`
+    pub fn read_files(stem: impl AsRef<Path>) -> NitfResult<Self> {
+        let stem = stem.as_ref();
+        Ok(Self {
+            shp: std::fs::read(stem.with_extension("shp"))?,
+            shx: std::fs::read(stem.with_extension("shx"))?,
+            dbf: std::fs::read(stem.with_extension("dbf"))?,
+        })
+    }
+
+    /// Write `<stem>.shp`, `<stem>.shx`, and `<stem>.dbf`
+    pub fn write_files(&self, stem: impl AsRef<Path>) -> NitfResult<()> {
+        let stem = stem.as_ref();
+        std::fs::write(stem.with_extension("shp"), &self.shp)?;
+        std::fs::write(stem.with_extension("shx"), &self.shx)?;
+        std::fs::write(stem.with_extension("dbf"), &self.dbf)?;
+        Ok(())
+    }
+}
+
+impl DataExtensionHeader {
+    /// Whether this is a CSSHPA segment
+    pub fn is_csshpa(&self) -> bool {
+        matches!(self.desid.val.trim(), CSSHPA | "CSSHPA")
+    }
+    /// Parse the CSSHPA user-defined subheader. `None` for other segment types.
+    pub fn csshpa(&self) -> NitfResult<Option<Csshpa>> {
+        if !self.is_csshpa() {
+            return Ok(None);
+        }
+        Csshpa::from_bytes(self.desshf.tre()).map(Some)
+    }
+    /// Make this a CSSHPA segment with the given user-defined subheader,
+    /// updating DESSHL
+    pub fn set_csshpa(&mut self, csshpa: &Csshpa) -> NitfResult<()> {
+        let bytes = csshpa.to_bytes()?;
+        self.desid.val = CSSHPA.to_string();
+        self.desshl.val = bytes.len() as u16;
+        self.desshf.set_tre(bytes);
+        Ok(())
+    }
+}
+
+impl DataExtensionSegment {
+    /// Read the shapefile from a CSSHPA segment. `None` for other segment
+    /// types.
+    pub fn read_shapefile(&self, reader: &mut (impl Read + Seek)) -> NitfResult<Option<Shapefile>> {
+        let Some(csshpa) = self.header.csshpa()? else {
+            return Ok(None);
+        };
+        let data = self.read_data(reader)?;
+        Shapefile::from_data(&csshpa, &data).map(Some)
+    }
+    /// Create a CSSHPA segment holding `shapefile`, returning the segment and
+    /// the data to write with
+    /// [NitfSegment::write_data()](crate::types::NitfSegment::write_data).
+    ///
+    /// The offsets of `csshpa` are set from the shapefile components.
+    pub fn from_shapefile(csshpa: &Csshpa, shapefile: &Shapefile) -> NitfResult<(Self, Vec<u8>)> {
+        let mut csshpa = csshpa.clone();
+        let data = shapefile.to_data(&mut csshpa)?;
+        let mut seg = Self::default();
+        seg.header.desver.val = 1;
+        seg.header.set_csshpa(&csshpa)?;
+        seg.data_size = data.len() as u64;
+        Ok((seg, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(shapefile: &Shapefile) -> Shapefile {
+        let mut csshpa = Csshpa {
+            shape_use: CLOUD_SHAPES.to_string(),
+            shape_class: "POLYGON".to_string(),
+            cc_source: "SOURCE".to_string(),
+            ..Default::default()
+        };
+        let data = shapefile.to_data(&mut csshpa).unwrap();
+        let parsed = Csshpa::from_bytes(&csshpa.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed, csshpa);
+        Shapefile::from_data(&parsed, &data).unwrap()
+    }
+
+    #[test]
+    fn shapefile_round_trip() {
+        let shapefile = Shapefile {
+            shp: vec![1, 2, 3, 4],
+            shx: vec![5, 6],
+            dbf: vec![7, 8, 9],
+        };
+        assert_eq!(round_trip(&shapefile), shapefile);
+    }
+
+    #[test]
+    fn empty_components() {
+        for shapefile in [
+            Shapefile {
+                shp: vec![],
+                shx: vec![1, 2, 3],
+                dbf: vec![9],
+            },
+            Shapefile {
+                shp: vec![1, 2, 3],
+                shx: vec![],
+                dbf: vec![],
+            },
+            Shapefile::default(),
+        ] {
+            assert_eq!(round_trip(&shapefile), shapefile);
+        }
+    }
+
+    #[test]
+    fn offset_outside_data() {
+        let csshpa = Csshpa {
+            dbf_start: 10,
+            ..Default::default()
+        };
+        assert!(Shapefile::from_data(&csshpa, &[0; 4]).is_err());
+    }
+}
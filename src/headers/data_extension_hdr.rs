@@ -1,11 +1,11 @@
 //! Data Extension segment subheader definition
 use std::fmt::Display;
-use std::io::{Read, Seek, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::str::FromStr;
 
 use crate::headers::NitfSegmentHeader;
 use crate::types::{ExtendedSubheader, NitfField, Security};
-use crate::{NitfError, NitfResult};
+use crate::{DataExtensionSegment, NitfError, NitfResult};
 
 /// Metadata for Data Extension Segment
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
@@ -88,6 +88,84 @@ impl NitfSegmentHeader for DataExtensionHeader {
         length += self.desshf.size();
         length
     }
+    fn update_lengths(&mut self) -> NitfResult<()> {
+        self.desshl.val = *self.desshf.size() as u16;
+        Ok(())
+    }
+    /// Fill in DESCRC from the data, if the CRC is used. A CRC which is
+    /// marked as not used is left alone.
+    fn update_for_data(&mut self, data: &[u8]) -> NitfResult<bool> {
+        match self.xml_data_content()? {
+            Some(mut xml) if xml.descrc.is_some() => {
+                let crc = Some(crc16(data));
+                if xml.descrc == crc {
+                    return Ok(false);
+                }
+                xml.descrc = crc;
+                self.set_xml_data_content(&xml)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+impl DataExtensionHeader {
+    /// CRC of the segment data recorded in the user-defined subheader.
+    ///
+    /// `None` if the segment type has no CRC field, or the CRC is not used.
+    pub fn descrc(&self) -> NitfResult<Option<u16>> {
+        Ok(self.xml_data_content()?.and_then(|xml| xml.descrc))
+    }
+    /// Check `data` against the CRC recorded in the user-defined subheader,
+    /// if there is one
+    pub fn verify_crc(&self, data: &[u8]) -> NitfResult<()> {
+        if let Some(expected) = self.descrc()? {
+            let computed = crc16(data);
+            if computed != expected {
+                Err(NitfError::CrcMismatch {
+                    name: self.desid.val.trim().to_string(),
+                    expected,
+                    computed,
+                })?
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DataExtensionSegment {
+    /// Read the segment data, checking it against the CRC in the user-defined
+    /// subheader if there is one.
+    ///
+    /// This is where CRCs are verified. [Nitf::from_reader()](crate::Nitf::from_reader)
+    /// does not read DES data, and
+    /// [NitfSegment::get_data_map()](crate::types::NitfSegment::get_data_map)
+    /// maps it without checking. Readers built on this method, such as
+    /// [DataExtensionSegment::read_xml()] and
+    /// [DataExtensionSegment::read_shapefile()], verify the CRC as well.
+    pub fn read_data(&self, reader: &mut (impl Read + Seek)) -> NitfResult<Vec<u8>> {
+        let mut bytes = vec![0; self.data_size as usize];
+        reader.seek(SeekFrom::Start(self.data_offset))?;
+        reader.read_exact(&mut bytes)?;
+        self.header.verify_crc(&bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// CRC-16/CCITT checksum (polynomial `0x1021`, initial value `0xFFFF`) used
+/// by the DESCRC field
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = match crc & 0x8000 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x1021,
+            };
+        }
+    }
+    crc
 }
 #[derive(Default, Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub enum DE {
@@ -123,6 +201,8 @@ pub enum OverflowedHeaderType {
     UDHD,
     /// Image subheader user defined image data overflow
     UDID,
+    /// Header extended header data overflow
+    XHD,
 }
 impl Display for DataExtensionHeader {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -147,6 +227,7 @@ impl FromStr for OverflowedHeaderType {
             "TXSHD" => Ok(Self::TXSHD),
             "UDHD" => Ok(Self::UDHD),
             "UDID" => Ok(Self::UDID),
+            "XHD" => Ok(Self::XHD),
             _ => Err(NitfError::ParseError("OverflowedHeaderType".to_string())),
         }
     }
@@ -159,6 +240,53 @@ impl Display for OverflowedHeaderType {
             Self::TXSHD => write!(f, "TXSHD"),
             Self::UDHD => write!(f, "UDHD"),
             Self::UDID => write!(f, "UDID"),
+            Self::XHD => write!(f, "XHD"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::xml_data_content::XmlDataContent;
+
+    fn xml_header(descrc: Option<u16>) -> DataExtensionHeader {
+        let mut header = DataExtensionHeader::default();
+        let xml = XmlDataContent {
+            desshl: 5,
+            descrc,
+            ..Default::default()
+        };
+        header.set_xml_data_content(&xml).unwrap();
+        header
+    }
+
+    #[test]
+    fn crc_filled_in_when_used() {
+        let data = b"<xml/>";
+        let mut header = xml_header(Some(0));
+        assert!(header.update_for_data(data).unwrap());
+        assert_eq!(header.descrc().unwrap(), Some(crc16(data)));
+        // Unchanged data does not need the header written again
+        assert!(!header.update_for_data(data).unwrap());
+        header.verify_crc(data).unwrap();
+        assert!(matches!(
+            header.verify_crc(b"<xml></xml>"),
+            Err(NitfError::CrcMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn crc_not_used() {
+        let mut header = xml_header(None);
+        assert!(!header.update_for_data(b"<xml/>").unwrap());
+        assert_eq!(header.descrc().unwrap(), None);
+        header.verify_crc(b"anything").unwrap();
+    }
+
+    #[test]
+    fn crc16_check_value() {
+        // CRC-16/CCITT-FALSE check value
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+    }
+}
@@ -4,6 +4,7 @@ use std::io::{Read, Seek, Write};
 use std::str::FromStr;
 
 use crate::headers::NitfSegmentHeader;
+use crate::tre::Tre;
 use crate::types::{ExtendedSubheader, NitfField, Security};
 use crate::{NitfError, NitfResult};
 /// Metadata for Nitf File Header
@@ -32,7 +33,7 @@ pub struct NitfHeader {
     /// Encryption
     pub encryp: NitfField<String>,
     /// File Background Color
-    pub fbkgc: Vec<NitfField<String>>, // TODO: Fix the parsing of this
+    pub fbkgc: [u8; 3],
     /// Originator's Name
     pub oname: NitfField<String>,
     /// Originator's Phone Number
@@ -136,6 +137,13 @@ impl Segment {
 }
 
 impl NitfHeader {
+    /// Read the first TRE matching the tag of `T` from `xhd` or `udhd`
+    pub fn get_tre<T: Tre>(&self) -> NitfResult<Option<T>> {
+        match self.xhd.get_tre()? {
+            Some(tre) => Ok(Some(tre)),
+            None => self.udhd.get_tre(),
+        }
+    }
     pub(crate) fn write_header(
         &mut self,
         writer: &mut (impl Write + Seek),
@@ -194,7 +202,7 @@ impl Default for NitfHeader {
             fscop: NitfField::init(5u8, "FSCOP"),
             fscpys: NitfField::init(5u8, "FSCPYS"),
             encryp: NitfField::init(1u8, "ENCRYP"),
-            fbkgc: vec![NitfField::init(1u8, "FBKGC"); 3],
+            fbkgc: [0; 3],
             oname: NitfField::init(24u8, "ONAME"),
             ophone: NitfField::init(18u8, "OPHONE"),
             fl: NitfField::init(12u8, "FL"),
@@ -234,7 +242,7 @@ impl Display for NitfHeader {
         out_str += format!("{}, ", self.encryp).as_ref();
         out_str += format!(
             "FBKGC: [R: {}, G: {}, B: {}], ",
-            self.fbkgc[0], self.fbkgc[1], self.fbkgc[2],
+            self.fbkgc[0], self.fbkgc[1], self.fbkgc[2]
         )
         .as_ref();
         out_str += format!("{}, ", self.oname).as_ref();
@@ -285,10 +293,8 @@ impl NitfSegmentHeader for NitfHeader {
         self.fscop.read(reader)?;
         self.fscpys.read(reader)?;
         self.encryp.read(reader)?;
-        self.fbkgc = vec![NitfField::init(1u8, "FBKGC"); 3];
-        self.fbkgc
-            .iter_mut()
-            .try_for_each(|color| color.read(reader))?;
+        // Binary red, green, and blue values
+        reader.read_exact(&mut self.fbkgc)?;
 
         self.oname.read(reader)?;
         self.ophone.read(reader)?;
@@ -350,9 +356,8 @@ impl NitfSegmentHeader for NitfHeader {
         bytes_written += self.fscop.write(writer)?;
         bytes_written += self.fscpys.write(writer)?;
         bytes_written += self.encryp.write(writer)?;
-        for color in &self.fbkgc {
-            bytes_written += color.write(writer)?;
-        }
+        writer.write_all(&self.fbkgc)?;
+        bytes_written += self.fbkgc.len();
         bytes_written += self.oname.write(writer)?;
         bytes_written += self.ophone.write(writer)?;
         bytes_written += self.fl.write(writer)?;
@@ -408,7 +413,7 @@ impl NitfSegmentHeader for NitfHeader {
         length += self.fscop.length;
         length += self.fscpys.length;
         length += self.encryp.length;
-        length += self.fbkgc.iter().map(|c| c.length).sum::<usize>();
+        length += self.fbkgc.len();
         length += self.oname.length;
         length += self.ophone.length;
         length += self.fl.length;
@@ -436,6 +441,12 @@ impl NitfSegmentHeader for NitfHeader {
         }
         length
     }
+    fn update_lengths(&mut self) -> NitfResult<()> {
+        self.udhd
+            .update_length_fields(&mut self.udhdl, &mut self.udhofl)?;
+        self.xhd
+            .update_length_fields(&mut self.xhdl, &mut self.xhdlofl)
+    }
 }
 
 /// Subheader element type
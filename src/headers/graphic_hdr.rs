@@ -4,7 +4,7 @@ use std::io::{Read, Seek, Write};
 use std::str::FromStr;
 
 use crate::headers::NitfSegmentHeader;
-use crate::types::{ExtendedSubheader, NitfField, Security};
+use crate::types::{ExtendedSubheader, NitfField, RowColumn, Security};
 use crate::{NitfError, NitfResult};
 /// Header fields for Graphic Segment
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
@@ -28,17 +28,17 @@ pub struct GraphicHeader {
     /// Graphic Attachment Level
     pub salvl: NitfField<u16>,
     /// Graphic Location
-    pub sloc: NitfField<BoundLocation>, // TODO: Same image image ILOC type thing
+    pub sloc: NitfField<RowColumn>,
     /// First Graphic Bound Location
-    pub sbnd1: NitfField<BoundLocation>,
+    pub sbnd1: NitfField<RowColumn>,
     /// Graphic Color
     pub scolor: NitfField<Color>,
     /// Second Graphic Bound Location
-    pub sbnd2: NitfField<BoundLocation>,
+    pub sbnd2: NitfField<RowColumn>,
     /// Reserved for Future Use
     pub sres2: NitfField<u8>,
     /// Graphic Extended Subheader Data Length
-    pub sxshdl: NitfField<u16>,
+    pub sxshdl: NitfField<u32>,
     /// Graphic Extended Subheader Overflow
     pub sxsofl: NitfField<u16>,
     /// Graphic Extended Subheader Data
@@ -179,6 +179,13 @@ impl NitfSegmentHeader for GraphicHeader {
         }
         length
     }
+    fn update_lengths(&mut self) -> NitfResult<()> {
+        self.sloc.val.check("SLOC")?;
+        self.sbnd1.val.check("SBND1")?;
+        self.sbnd2.val.check("SBND2")?;
+        self.sxshd
+            .update_length_fields(&mut self.sxshdl, &mut self.sxsofl)
+    }
 }
 
 /// Graphic type. Right now standard only supports C
@@ -207,35 +214,9 @@ impl Display for Format {
 }
 
 /// Graphic bound position relative to origin of coordinate system
-#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
-pub struct BoundLocation {
-    pub row: i32,
-    pub col: i32,
-}
+#[deprecated(note = "use RowColumn")]
+pub type BoundLocation = RowColumn;
 
-impl FromStr for BoundLocation {
-    type Err = NitfError;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() == 10 {
-            let bounds = Self {
-                row: s[..5]
-                    .parse()
-                    .or(Err(NitfError::ParseError("BoundLocation.row".to_string())))?,
-                col: s[5..]
-                    .parse()
-                    .or(Err(NitfError::ParseError("BoundLocation.col".to_string())))?,
-            };
-            Ok(bounds)
-        } else {
-            Err(NitfError::ParseError("BoundLocation".to_string()))
-        }
-    }
-}
-impl Display for BoundLocation {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:0<5}{:0<5}", self.row, self.col)
-    }
-}
 /// Color type of graphics
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub enum Color {
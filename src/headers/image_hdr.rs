@@ -7,8 +7,10 @@ use std::fmt::Display;
 use std::io::{Read, Seek, Write};
 use std::str::FromStr;
 
+use crate::geo::{format_dms, parse_dms};
 use crate::headers::NitfSegmentHeader;
-use crate::types::{ExtendedSubheader, NitfField, Security};
+use crate::tre::{Bandsb, Ichipb, Rpc, Rpc00a, Rpc00b, Tre};
+use crate::types::{ExtendedSubheader, NitfField, RowColumn, Security};
 use crate::{NitfError, NitfResult};
 
 /// Metadata for Image Segment subheader
@@ -81,7 +83,7 @@ pub struct ImageHeader {
     /// Image Attachment Level
     pub ialvl: NitfField<u16>,
     /// Image Location
-    pub iloc: NitfField<String>,
+    pub iloc: NitfField<RowColumn>,
     /// Image Magnification
     pub imag: NitfField<String>,
     /// User Defined Image Data Length
@@ -144,6 +146,228 @@ impl Default for ImageHeader {
         }
     }
 }
+impl ImageHeader {
+    /// Read the first TRE matching the tag of `T` from `ixshd` or `udid`
+    pub fn get_tre<T: Tre>(&self) -> NitfResult<Option<T>> {
+        match self.ixshd.get_tre()? {
+            Some(tre) => Ok(Some(tre)),
+            None => self.udid.get_tre(),
+        }
+    }
+    /// Rational polynomial camera model, from RPC00B or RPC00A
+    pub fn rpc(&self) -> NitfResult<Option<Rpc>> {
+        if let Some(rpc) = self.get_tre::<Rpc00b>()? {
+            return Ok(Some(rpc.0));
+        }
+        Ok(self.get_tre::<Rpc00a>()?.map(|rpc| rpc.0))
+    }
+    /// Chip location within the original full image, from ICHIPB
+    pub fn ichipb(&self) -> NitfResult<Option<Ichipb>> {
+        self.get_tre::<Ichipb>()
+    }
+    /// Spectral descriptor for each band, in the order of `bands`.
+    ///
+    /// Wavelengths come from BANDSB when present. Otherwise `ISUBCAT` is read
+    /// as a center wavelength in nanometers, and bands with a non-numeric
+    /// `ISUBCAT` have no wavelength.
+    pub fn spectral_bands(&self) -> NitfResult<Vec<SpectralBand>> {
+        let Some(bandsb) = self.get_tre::<Bandsb>()? else {
+            return Ok(self
+                .bands
+                .iter()
+                .map(|band| {
+                    let isubcat = band.isubcat.val.trim();
+                    SpectralBand {
+                        band_id: isubcat.to_string(),
+                        center_wavelength: isubcat.parse::<f64>().ok().map(|nm| nm / 1000.0),
+                        unit: WavelengthUnit::Micrometers,
+                        ..Default::default()
+                    }
+                })
+                .collect());
+        };
+        if bandsb.bands.len() != self.bands.len() {
+            Err(NitfError::Value("BANDSB COUNT".to_string()))?
+        }
+        let unit = match bandsb.wave_length_unit.as_str() {
+            "W" => WavelengthUnit::Wavenumbers,
+            _ => WavelengthUnit::Micrometers,
+        };
+        Ok(bandsb
+            .bands
+            .iter()
+            .zip(&self.bands)
+            .map(|(spectral, band)| SpectralBand {
+                band_id: match spectral.bandid.is_empty() {
+                    true => band.isubcat.val.trim().to_string(),
+                    false => spectral.bandid.clone(),
+                },
+                center_wavelength: spectral.cwave.or(spectral.nom_wave),
+                bandwidth: spectral.fwhm,
+                lower_bound: spectral.lbound,
+                upper_bound: spectral.ubound,
+                unit,
+            })
+            .collect())
+    }
+    /// Project a ground point to `(row, column)` image coordinates using the
+    /// RPC camera model. `None` if the segment has no RPC TRE.
+    ///
+    /// If the segment is a chip described by ICHIPB, the RPC is assumed to
+    /// refer to the full image and the result is in chip coordinates.
+    pub fn ground_to_image(
+        &self,
+        lat: f64,
+        lon: f64,
+        height: f64,
+    ) -> NitfResult<Option<(f64, f64)>> {
+        let Some(rpc) = self.rpc()? else {
+            return Ok(None);
+        };
+        let (row, col) = rpc.ground_to_image(lat, lon, height)?;
+        match self.ichipb()? {
+            Some(ichipb) => ichipb.full_to_chip(row, col).map(Some),
+            None => Ok(Some((row, col))),
+        }
+    }
+    /// Project image coordinates to `(latitude, longitude)` at a given height
+    /// using the RPC camera model. `None` if the segment has no RPC TRE.
+    ///
+    /// If the segment is a chip described by ICHIPB, `row` and `col` are chip
+    /// coordinates and are mapped to the full image before projecting.
+    pub fn image_to_ground(
+        &self,
+        row: f64,
+        col: f64,
+        height: f64,
+    ) -> NitfResult<Option<(f64, f64)>> {
+        let Some(rpc) = self.rpc()? else {
+            return Ok(None);
+        };
+        let (row, col) = match self.ichipb()? {
+            Some(ichipb) => ichipb.chip_to_full(row, col)?,
+            None => (row, col),
+        };
+        rpc.image_to_ground(row, col, height).map(Some)
+    }
+    /// Corner locations parsed from IGEOLO, in the order (first row, first
+    /// column), (first row, last column), (last row, last column), and
+    /// (last row, first column). `None` if ICORDS is blank.
+    pub fn igeolo_corners(&self) -> NitfResult<Option<[ImageCorner; 4]>> {
+        use CoordinateRepresentation::*;
+        let igeolo = &self.igeolo.val;
+        let corner_str = |i: usize| {
+            igeolo
+                .get(15 * i..15 * (i + 1))
+                .ok_or(NitfError::ParseError("IGEOLO".to_string()))
+        };
+        let parse = |i: usize| -> NitfResult<ImageCorner> {
+            let s = corner_str(i)?;
+            match self.icords.val {
+                G => Ok(ImageCorner::Geographic {
+                    lat: parse_dms(&s[..7], 2)?,
+                    lon: parse_dms(&s[7..], 3)?,
+                }),
+                D => Ok(ImageCorner::Geographic {
+                    lat: parse_igeolo_value(&s[..7])?,
+                    lon: parse_igeolo_value(&s[7..])?,
+                }),
+                N | S => Ok(ImageCorner::Utm {
+                    zone: parse_igeolo_value(&s[..2])?,
+                    easting: parse_igeolo_value(&s[2..8])?,
+                    northing: parse_igeolo_value(&s[8..])?,
+                }),
+                U | P | DEFAULT => Err(NitfError::Fatal(format!(
+                    "Unsupported ICORDS value '{}'",
+                    self.icords.val
+                ))),
+            }
+        };
+        if self.icords.val == DEFAULT {
+            return Ok(None);
+        }
+        Ok(Some([parse(0)?, parse(1)?, parse(2)?, parse(3)?]))
+    }
+    /// Set IGEOLO from corner locations, formatted according to ICORDS.
+    ///
+    /// Corners are ordered as in [ImageHeader::igeolo_corners()], and must be
+    /// [ImageCorner::Geographic] for ICORDS `G` or `D` and [ImageCorner::Utm]
+    /// for ICORDS `N` or `S`.
+    pub fn set_igeolo_corners(&mut self, corners: &[ImageCorner; 4]) -> NitfResult<()> {
+        use CoordinateRepresentation::*;
+        let mut igeolo = String::with_capacity(60);
+        for corner in corners {
+            match (self.icords.val, corner) {
+                (G, ImageCorner::Geographic { lat, lon }) => {
+                    igeolo += &format_dms(*lat, 2, 0, true);
+                    igeolo += &format_dms(*lon, 3, 0, false);
+                }
+                (D, ImageCorner::Geographic { lat, lon }) => {
+                    igeolo += &format!("{lat:+07.3}{lon:+08.3}");
+                }
+                (
+                    N | S,
+                    ImageCorner::Utm {
+                        zone,
+                        easting,
+                        northing,
+                    },
+                ) => {
+                    igeolo += &format!(
+                        "{zone:02}{:06}{:07}",
+                        easting.round() as u32,
+                        northing.round() as u32
+                    );
+                }
+                _ => Err(NitfError::Value("IGEOLO".to_string()))?,
+            }
+        }
+        if igeolo.len() != self.igeolo.length {
+            Err(NitfError::Value("IGEOLO".to_string()))?
+        }
+        self.igeolo.val = igeolo;
+        Ok(())
+    }
+}
+
+/// Spectral description of a band
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SpectralBand {
+    /// Band identifier, from BANDSB BANDID or ISUBCAT
+    pub band_id: String,
+    /// Center wavelength
+    pub center_wavelength: Option<f64>,
+    /// Full width at half maximum
+    pub bandwidth: Option<f64>,
+    /// Lower wavelength bound
+    pub lower_bound: Option<f64>,
+    /// Upper wavelength bound
+    pub upper_bound: Option<f64>,
+    /// Unit of the wavelength values
+    pub unit: WavelengthUnit,
+}
+
+/// Unit of [SpectralBand] wavelengths
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WavelengthUnit {
+    #[default]
+    Micrometers,
+    /// Inverse centimeters
+    Wavenumbers,
+}
+
+/// Image corner location from IGEOLO
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageCorner {
+    /// Latitude and longitude in decimal degrees
+    Geographic { lat: f64, lon: f64 },
+    /// UTM zone, easting, and northing in meters
+    Utm {
+        zone: u8,
+        easting: f64,
+        northing: f64,
+    },
+}
 
 #[derive(Default, Clone, Debug, Eq, PartialEq, Copy, Ord, PartialOrd)]
 pub enum IM {
@@ -367,6 +591,12 @@ pub enum Mode {
 }
 
 // FUNCTIONS
+fn parse_igeolo_value<T: FromStr>(s: &str) -> NitfResult<T> {
+    s.trim()
+        .parse()
+        .or(Err(NitfError::ParseError("IGEOLO".to_string())))
+}
+
 /// Helper function for parsing bands
 fn read_bands(reader: &mut (impl Read + Seek), n_band: u32) -> NitfResult<Vec<Band>> {
     let mut bands: Vec<Band> = vec![Band::default(); n_band as usize];
@@ -599,6 +829,13 @@ impl NitfSegmentHeader for ImageHeader {
         length += self.ixshdl.val as usize;
         length
     }
+    fn update_lengths(&mut self) -> NitfResult<()> {
+        self.iloc.val.check("ILOC")?;
+        self.udid
+            .update_length_fields(&mut self.udidl, &mut self.udofl)?;
+        self.ixshd
+            .update_length_fields(&mut self.ixshdl, &mut self.ixsofl)
+    }
 }
 impl Display for ImageHeader {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
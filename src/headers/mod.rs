@@ -2,19 +2,23 @@
 
 use std::io::{Read, Seek, Write};
 
+pub mod csshpa;
 pub mod data_extension_hdr;
 pub mod file_hdr;
 pub mod graphic_hdr;
 pub mod image_hdr;
 pub mod reserved_extension_hdr;
 pub mod text_hdr;
+pub mod xml_data_content;
 
+pub use csshpa::Csshpa;
 pub use data_extension_hdr::DataExtensionHeader;
 pub use file_hdr::NitfHeader;
 pub use graphic_hdr::GraphicHeader;
 pub use image_hdr::ImageHeader;
 pub use reserved_extension_hdr::ReservedExtensionHeader;
 pub use text_hdr::TextHeader;
+pub use xml_data_content::XmlDataContent;
 
 use crate::NitfResult;
 
@@ -45,6 +49,20 @@ where
     #[allow(unused)]
     fn length(&self) -> usize;
 
+    /// Update any length fields which describe variable length data, such as
+    /// TRE data lengths. Called automatically before headers are written.
+    fn update_lengths(&mut self) -> NitfResult<()> {
+        Ok(())
+    }
+
+    /// Update any fields which depend on the segment data, such as checksums.
+    /// Called when the data is written, and returns whether the header changed
+    /// and needs to be written again.
+    #[allow(unused)]
+    fn update_for_data(&mut self, data: &[u8]) -> NitfResult<bool> {
+        Ok(false)
+    }
+
     fn from_reader(reader: &mut (impl Read + Seek)) -> NitfResult<Self> {
         let mut hdr = Self::default();
         hdr.read(reader)?;
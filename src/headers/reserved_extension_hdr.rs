@@ -101,4 +101,8 @@ impl NitfSegmentHeader for ReservedExtensionHeader {
         }
         length
     }
+    fn update_lengths(&mut self) -> NitfResult<()> {
+        self.resshl.val = *self.resshf.size() as u16;
+        Ok(())
+    }
 }
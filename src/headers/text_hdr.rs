@@ -1,12 +1,12 @@
 //! Text segment definition
 use std::fmt::Display;
-use std::io::{Read, Seek, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 
 use std::str::FromStr;
 
 use crate::headers::NitfSegmentHeader;
 use crate::types::{ExtendedSubheader, NitfField, Security};
-use crate::{NitfError, NitfResult};
+use crate::{NitfError, NitfResult, TextSegment};
 /// Text Segment Metadata
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct TextHeader {
@@ -27,7 +27,7 @@ pub struct TextHeader {
     /// Text Format
     pub txtfmt: NitfField<TextFormat>,
     /// Text Extended Subheader Data Length
-    pub txshdl: NitfField<u16>,
+    pub txshdl: NitfField<u32>,
     /// Text Extended Subheader Overflow
     pub txsofl: NitfField<u16>,
     /// Text Extended Subheader Data
@@ -136,6 +136,10 @@ impl NitfSegmentHeader for TextHeader {
         }
         length
     }
+    fn update_lengths(&mut self) -> NitfResult<()> {
+        self.txshd
+            .update_length_fields(&mut self.txshdl, &mut self.txsofl)
+    }
 }
 impl Display for TextHeader {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -176,3 +180,131 @@ impl Display for TextFormat {
         }
     }
 }
+
+impl TextFormat {
+    /// Whether a character is allowed in text of this format. BCS (`STA`,
+    /// `MTF`) is printable ASCII, and ECS (`UT1`) and its UTF-8 encoding
+    /// (`U8S`) add printable Latin-1. Line feed, form feed, and carriage
+    /// return are allowed in all formats.
+    pub fn allows(&self, c: char) -> bool {
+        let ecs = matches!(self, Self::UT1 | Self::U8S);
+        matches!(c, '\n' | '\x0C' | '\r' | ' '..='~') || (ecs && ('\u{A0}'..='\u{FF}').contains(&c))
+    }
+
+    /// Decode text segment data, with lines ending in `\n`
+    pub fn decode(&self, bytes: &[u8]) -> DecodedText {
+        let mut chars = vec![];
+        match self {
+            Self::U8S => {
+                for chunk in bytes.utf8_chunks() {
+                    let start = chunk.valid().as_ptr() as usize - bytes.as_ptr() as usize;
+                    chars.extend(chunk.valid().char_indices().map(|(i, c)| (start + i, c)));
+                    if !chunk.invalid().is_empty() {
+                        let offset = start + chunk.valid().len();
+                        chars.push((offset, char::REPLACEMENT_CHARACTER));
+                    }
+                }
+            }
+            // Single byte formats are a subset of Latin-1
+            _ => chars.extend(bytes.iter().map(|b| *b as char).enumerate()),
+        }
+
+        let mut decoded = DecodedText::default();
+        let mut chars = chars.into_iter().peekable();
+        while let Some((offset, c)) = chars.next() {
+            if !self.allows(c) {
+                decoded.invalid.push(InvalidCharacter {
+                    offset,
+                    character: c,
+                });
+            }
+            if c == '\r' {
+                // CR/LF or a lone CR ends a line
+                chars.next_if(|(_, c)| *c == '\n');
+                decoded.text.push('\n');
+            } else {
+                decoded.text.push(c);
+            }
+        }
+        decoded
+    }
+
+    /// Encode text as segment data, with lines ending in CR/LF
+    pub fn encode(&self, text: &str) -> NitfResult<Vec<u8>> {
+        let mut bytes = vec![];
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if !self.allows(c) {
+                Err(NitfError::Value(format!(
+                    "Character {c:?} is not allowed in {self} text"
+                )))?
+            }
+            match c {
+                '\r' | '\n' => {
+                    if c == '\r' {
+                        chars.next_if_eq(&'\n');
+                    }
+                    bytes.extend_from_slice(b"\r\n");
+                }
+                _ if *self == Self::U8S => {
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes())
+                }
+                _ => bytes.push(c as u8),
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+/// Character which is not allowed by the text format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidCharacter {
+    /// Byte offset in the segment data
+    pub offset: usize,
+    /// Decoded character, or U+FFFD for invalid UTF-8
+    pub character: char,
+}
+
+/// Text decoded from a text segment
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DecodedText {
+    pub text: String,
+    /// Characters which are not allowed by TXTFMT. They are kept in `text`.
+    pub invalid: Vec<InvalidCharacter>,
+}
+
+impl TextSegment {
+    /// Read and decode the segment data according to TXTFMT.
+    ///
+    /// Line endings are normalized to `\n`. Characters outside of the format
+    /// are reported in [DecodedText::invalid] rather than treated as errors.
+    pub fn read_text(&self, reader: &mut (impl Read + Seek)) -> NitfResult<DecodedText> {
+        let mut bytes = vec![0; self.data_size as usize];
+        reader.seek(SeekFrom::Start(self.data_offset))?;
+        reader.read_exact(&mut bytes)?;
+        Ok(self.header.txtfmt.val.decode(&bytes))
+    }
+
+    /// Create a text segment holding `text` in `format`, returning the
+    /// segment and the data to write with
+    /// [NitfSegment::write_data()](crate::types::NitfSegment::write_data).
+    ///
+    /// Lines are written with CR/LF endings. Characters which `format` does
+    /// not allow are an error.
+    /// ```
+    /// use nitf_rs::headers::text_hdr::TextFormat;
+    /// use nitf_rs::TextSegment;
+    ///
+    /// let (segment, data) = TextSegment::from_text("Line 1\nLine 2", TextFormat::STA).unwrap();
+    /// assert_eq!(data, b"Line 1\r\nLine 2");
+    /// assert_eq!(segment.data_size, data.len() as u64);
+    /// assert!(TextSegment::from_text("Caf\u{e9}", TextFormat::STA).is_err());
+    /// ```
+    pub fn from_text(text: &str, format: TextFormat) -> NitfResult<(Self, Vec<u8>)> {
+        let data = format.encode(text)?;
+        let mut seg = Self::default();
+        seg.header.txtfmt.val = format;
+        seg.data_size = data.len() as u64;
+        Ok((seg, data))
+    }
+}
@@ -0,0 +1,219 @@
+//! XML_DATA_CONTENT data extension segment user-defined subheader
+//!
+//! The subheader is empty, or holds the first 5, 283, or 773 bytes of the
+//! fields below, as given by DESSHL.
+use std::io::{Cursor, Read, Seek};
+
+use crate::headers::DataExtensionHeader;
+use crate::tre::{read_optional, read_string, write_int, write_string};
+use crate::{DataExtensionSegment, NitfError, NitfResult};
+
+/// DESID of XML data content segments
+pub const XML_DATA_CONTENT: &str = "XML_DATA_CONTENT";
+
+/// XML_DATA_CONTENT user-defined subheader
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct XmlDataContent {
+    /// Length of the subheader, one of [XmlDataContent::LENGTHS]
+    pub desshl: u16,
+    /// Cyclic Redundancy Check. `None` when not used, which is written as
+    /// 99999. When used, any value is replaced by the CRC of the data in
+    /// [NitfSegment::write_data()](crate::types::NitfSegment::write_data).
+    pub descrc: Option<u16>,
+    /// XML File Type
+    pub desshft: String,
+    /// Date and Time, `CCYY-MM-DDThh:mm:ssZ`
+    pub desshdt: String,
+    /// Responsible Party
+    pub desshrp: String,
+    /// Specification Identifier
+    pub desshsi: String,
+    /// Specification Version
+    pub desshsv: String,
+    /// Specification Date
+    pub desshsd: String,
+    /// Target Namespace
+    pub desshtn: String,
+    /// Location - Polygon
+    pub desshlpg: String,
+    /// Location - Point
+    pub desshlpt: String,
+    /// Location - Identifier
+    pub desshli: String,
+    /// Location Identifier Namespace URI
+    pub desshlin: String,
+    /// Abstract
+    pub desshabs: String,
+}
+
+impl XmlDataContent {
+    /// Allowed subheader lengths
+    pub const LENGTHS: [u16; 4] = [0, 5, 283, 773];
+
+    /// Parse the subheader from the DESSHF bytes
+    pub fn from_bytes(bytes: &[u8]) -> NitfResult<Self> {
+        let desshl = match u16::try_from(bytes.len()) {
+            Ok(desshl) if Self::LENGTHS.contains(&desshl) => desshl,
+            _ => Err(NitfError::ParseError(format!(
+                "{XML_DATA_CONTENT} DESSHL {}",
+                bytes.len()
+            )))?,
+        };
+        let mut xml = Self {
+            desshl,
+            ..Default::default()
+        };
+        let reader = &mut Cursor::new(bytes);
+        if desshl >= 5 {
+            // 99999 flags an unused CRC
+            let descrc: Option<u32> = read_optional(reader, 5, "DESCRC")?;
+            xml.descrc = match descrc {
+                None | Some(99999) => None,
+                Some(crc) => Some(u16::try_from(crc).or(Err(NitfError::ParseError(format!(
+                    "{XML_DATA_CONTENT} DESCRC {crc}"
+                ))))?),
+            };
+        }
+        if desshl >= 283 {
+            xml.desshft = read_string(reader, 8, "DESSHFT")?;
+            xml.desshdt = read_string(reader, 20, "DESSHDT")?;
+            xml.desshrp = read_string(reader, 40, "DESSHRP")?;
+            xml.desshsi = read_string(reader, 60, "DESSHSI")?;
+            xml.desshsv = read_string(reader, 10, "DESSHSV")?;
+            xml.desshsd = read_string(reader, 20, "DESSHSD")?;
+            xml.desshtn = read_string(reader, 120, "DESSHTN")?;
+        }
+        if desshl >= 773 {
+            xml.desshlpg = read_string(reader, 125, "DESSHLPG")?;
+            xml.desshlpt = read_string(reader, 25, "DESSHLPT")?;
+            xml.desshli = read_string(reader, 20, "DESSHLI")?;
+            xml.desshlin = read_string(reader, 120, "DESSHLIN")?;
+            xml.desshabs = read_string(reader, 200, "DESSHABS")?;
+        }
+        Ok(xml)
+    }
+
+    /// Format the subheader as DESSHF bytes
+    pub fn to_bytes(&self) -> NitfResult<Vec<u8>> {
+        if !Self::LENGTHS.contains(&self.desshl) {
+            Err(NitfError::Value("DESSHL".to_string()))?
+        }
+        let mut bytes = Vec::with_capacity(self.desshl as usize);
+        let writer = &mut bytes;
+        if self.desshl >= 5 {
+            let descrc = self.descrc.map_or(99999, i64::from);
+            write_int(writer, descrc, 5, "DESCRC")?;
+        }
+        if self.desshl >= 283 {
+            write_string(writer, &self.desshft, 8, "DESSHFT")?;
+            write_string(writer, &self.desshdt, 20, "DESSHDT")?;
+            write_string(writer, &self.desshrp, 40, "DESSHRP")?;
+            write_string(writer, &self.desshsi, 60, "DESSHSI")?;
+            write_string(writer, &self.desshsv, 10, "DESSHSV")?;
+            write_string(writer, &self.desshsd, 20, "DESSHSD")?;
+            write_string(writer, &self.desshtn, 120, "DESSHTN")?;
+        }
+        if self.desshl >= 773 {
+            write_string(writer, &self.desshlpg, 125, "DESSHLPG")?;
+            write_string(writer, &self.desshlpt, 25, "DESSHLPT")?;
+            write_string(writer, &self.desshli, 20, "DESSHLI")?;
+            write_string(writer, &self.desshlin, 120, "DESSHLIN")?;
+            write_string(writer, &self.desshabs, 200, "DESSHABS")?;
+        }
+        Ok(bytes)
+    }
+}
+
+impl DataExtensionHeader {
+    /// Whether this is an XML_DATA_CONTENT segment
+    pub fn is_xml_data_content(&self) -> bool {
+        self.desid.val.trim() == XML_DATA_CONTENT
+    }
+    /// Parse the XML_DATA_CONTENT user-defined subheader. `None` for other
+    /// segment types.
+    pub fn xml_data_content(&self) -> NitfResult<Option<XmlDataContent>> {
+        if !self.is_xml_data_content() {
+            return Ok(None);
+        }
+        XmlDataContent::from_bytes(self.desshf.tre()).map(Some)
+    }
+    /// Make this an XML_DATA_CONTENT segment with the given user-defined
+    /// subheader, updating DESSHL
+    pub fn set_xml_data_content(&mut self, xml: &XmlDataContent) -> NitfResult<()> {
+        let bytes = xml.to_bytes()?;
+        self.desid.val = XML_DATA_CONTENT.to_string();
+        self.desshl.val = bytes.len() as u16;
+        self.desshf.set_tre(bytes);
+        Ok(())
+    }
+}
+
+impl DataExtensionSegment {
+    /// Read the segment data as a UTF-8 XML string, checking the CRC
+    pub fn read_xml(&self, reader: &mut (impl Read + Seek)) -> NitfResult<String> {
+        let bytes = self.read_data(reader)?;
+        String::from_utf8(bytes).or(Err(NitfError::ParseError(format!(
+            "{XML_DATA_CONTENT} data"
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synthetic(desshl: u16) -> XmlDataContent {
+        let mut xml = XmlDataContent {
+            desshl,
+            ..Default::default()
+        };
+        if desshl >= 5 {
+            xml.descrc = Some(12345);
+        }
+        if desshl >= 283 {
+            xml.desshft = "XSD".to_string();
+            xml.desshdt = "2020-01-01T12:00:00Z".to_string();
+            xml.desshsi = "SICD Volume 1 Design & Implementation Description Document".to_string();
+            xml.desshsv = "1.3.0".to_string();
+            xml.desshtn = "urn:SICD:1.3.0".to_string();
+        }
+        if desshl >= 773 {
+            xml.desshlpt = "+38.8895-077.0353".to_string();
+            xml.desshabs = "Synthetic".to_string();
+        }
+        xml
+    }
+
+    #[test]
+    fn xml_data_content_round_trip() {
+        for desshl in XmlDataContent::LENGTHS {
+            let xml = synthetic(desshl);
+            let bytes = xml.to_bytes().unwrap();
+            assert_eq!(bytes.len(), desshl as usize);
+            assert_eq!(XmlDataContent::from_bytes(&bytes).unwrap(), xml);
+        }
+    }
+
+    #[test]
+    fn descrc() {
+        let xml = XmlDataContent::from_bytes(b"99999").unwrap();
+        assert_eq!(xml.descrc, None);
+        assert_eq!(xml.to_bytes().unwrap(), b"99999");
+        assert_eq!(
+            XmlDataContent::from_bytes(b"65535").unwrap().descrc,
+            Some(65535)
+        );
+        assert!(XmlDataContent::from_bytes(b"65536").is_err());
+        assert!(XmlDataContent::from_bytes(b"99998").is_err());
+    }
+
+    #[test]
+    fn rejected_length() {
+        assert!(XmlDataContent::from_bytes(b"123456").is_err());
+        let xml = XmlDataContent {
+            desshl: 6,
+            ..Default::default()
+        };
+        assert!(xml.to_bytes().is_err());
+    }
+}
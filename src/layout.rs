@@ -0,0 +1,271 @@
+//! Common coordinate system (CCS) layout
+//!
+//! Image and graphic segments are placed in the CCS by their location (ILOC,
+//! SLOC), which is relative to the segment whose display level is their
+//! attachment level, or to the CCS origin for attachment level 0. Text
+//! segments are attached the same way, but have no location.
+use std::collections::HashMap;
+
+use crate::types::RowColumn;
+use crate::{Nitf, NitfError, NitfResult};
+
+/// Segment which takes part in the layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SegmentId {
+    Image(usize),
+    Graphic(usize),
+    Text(usize),
+}
+
+/// Placement of an image or graphic segment in the CCS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+    pub segment: SegmentId,
+    /// IDLVL or SDLVL
+    pub display_level: u16,
+    /// IALVL or SALVL
+    pub attachment_level: u16,
+    /// Location of the segment origin
+    pub location: RowColumn,
+    /// Upper left corner of the segment
+    pub upper_left: RowColumn,
+    /// Lower right corner of the segment, inclusive
+    pub lower_right: RowColumn,
+    /// Segments attached to this one
+    pub children: Vec<SegmentId>,
+}
+
+/// Attachment tree of a file, with the CCS placement of each image and
+/// graphic segment
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CcsLayout {
+    /// Image and graphic placements, by increasing display level
+    pub placements: Vec<Placement>,
+    /// Segments attached to the CCS origin
+    pub roots: Vec<SegmentId>,
+}
+
+impl CcsLayout {
+    /// Placement of an image or graphic segment
+    pub fn get(&self, segment: SegmentId) -> Option<&Placement> {
+        self.placements.iter().find(|p| p.segment == segment)
+    }
+
+    /// Placement of the segment at display level `level`
+    pub fn at_level(&self, level: u16) -> Option<&Placement> {
+        self.placements.iter().find(|p| p.display_level == level)
+    }
+
+    /// Upper left and lower right corners of all placed segments
+    pub fn bounds(&self) -> Option<(RowColumn, RowColumn)> {
+        let mut placements = self.placements.iter();
+        let first = placements.next()?;
+        Some(
+            placements.fold((first.upper_left, first.lower_right), |(ul, lr), p| {
+                (
+                    RowColumn::new(ul.row.min(p.upper_left.row), ul.col.min(p.upper_left.col)),
+                    RowColumn::new(lr.row.max(p.lower_right.row), lr.col.max(p.lower_right.col)),
+                )
+            }),
+        )
+    }
+}
+
+impl Nitf {
+    /// Build the attachment tree and place each image and graphic segment in
+    /// the common coordinate system.
+    ///
+    /// Display levels must be unique and non-zero, and every attachment level
+    /// must be 0 or the display level of a segment below the attached one.
+    pub fn ccs_layout(&self) -> NitfResult<CcsLayout> {
+        // Placements relative to the attachment, with bounds relative to the
+        // location for images and to the attachment for graphics
+        let mut placements = vec![];
+        for (i_seg, seg) in self.image_segments.iter().enumerate() {
+            let header = &seg.header;
+            let size = RowColumn::new(
+                header.nrows.val.max(1) as i32 - 1,
+                header.ncols.val.max(1) as i32 - 1,
+            );
+            placements.push(Placement {
+                segment: SegmentId::Image(i_seg),
+                display_level: header.idlvl.val,
+                attachment_level: header.ialvl.val,
+                location: header.iloc.val,
+                upper_left: header.iloc.val,
+                lower_right: header.iloc.val + size,
+                children: vec![],
+            });
+        }
+        for (i_seg, seg) in self.graphic_segments.iter().enumerate() {
+            let header = &seg.header;
+            placements.push(Placement {
+                segment: SegmentId::Graphic(i_seg),
+                display_level: header.sdlvl.val,
+                attachment_level: header.salvl.val,
+                location: header.sloc.val,
+                upper_left: header.sbnd1.val,
+                lower_right: header.sbnd2.val,
+                children: vec![],
+            });
+        }
+        placements.sort_by_key(|p| p.display_level);
+
+        let mut levels: HashMap<u16, usize> = HashMap::new();
+        for (index, placement) in placements.iter().enumerate() {
+            let level = placement.display_level;
+            if level == 0 {
+                Err(NitfError::Fatal(format!(
+                    "{:?} has display level 0",
+                    placement.segment
+                )))?
+            }
+            if let Some(other) = levels.insert(level, index) {
+                Err(NitfError::Fatal(format!(
+                    "Display level {level} is used by {:?} and {:?}",
+                    placements[other].segment, placement.segment
+                )))?
+            }
+        }
+
+        let mut layout = CcsLayout::default();
+        // Attachments are to lower display levels, so each origin is known
+        // by the time it is needed
+        for index in 0..placements.len() {
+            let segment = placements[index].segment;
+            let attachment = placements[index].attachment_level;
+            let origin = match attachment {
+                0 => {
+                    layout.roots.push(segment);
+                    RowColumn::default()
+                }
+                level => {
+                    let parent = match levels.get(&level) {
+                        Some(parent) if *parent < index => *parent,
+                        Some(_) => Err(NitfError::Fatal(format!(
+                            "{segment:?} is attached to display level {level}, which is not below it"
+                        )))?,
+                        None => Err(NitfError::Fatal(format!(
+                            "{segment:?} is attached to missing display level {level}"
+                        )))?,
+                    };
+                    placements[parent].children.push(segment);
+                    placements[parent].location
+                }
+            };
+            let placement = &mut placements[index];
+            placement.location = origin + placement.location;
+            placement.lower_right = origin + placement.lower_right;
+            placement.upper_left = origin + placement.upper_left;
+        }
+
+        for (i_seg, seg) in self.text_segments.iter().enumerate() {
+            let segment = SegmentId::Text(i_seg);
+            match seg.header.txtalvl.val {
+                0 => layout.roots.push(segment),
+                level => match levels.get(&level) {
+                    Some(parent) => placements[*parent].children.push(segment),
+                    None => Err(NitfError::Fatal(format!(
+                        "{segment:?} is attached to missing display level {level}"
+                    )))?,
+                },
+            }
+        }
+        layout.placements = placements;
+        Ok(layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GraphicSegment, ImageSegment, TextSegment};
+
+    fn image(display_level: u16, attachment_level: u16, iloc: RowColumn) -> ImageSegment {
+        let mut seg = ImageSegment::default();
+        seg.header.idlvl.val = display_level;
+        seg.header.ialvl.val = attachment_level;
+        seg.header.iloc.val = iloc;
+        seg.header.nrows.val = 10;
+        seg.header.ncols.val = 20;
+        seg
+    }
+
+    fn graphic(display_level: u16, attachment_level: u16, sloc: RowColumn) -> GraphicSegment {
+        let mut seg = GraphicSegment::default();
+        seg.header.sdlvl.val = display_level;
+        seg.header.salvl.val = attachment_level;
+        seg.header.sloc.val = sloc;
+        seg.header.sbnd1.val = sloc + RowColumn::new(-1, -1);
+        seg.header.sbnd2.val = sloc + RowColumn::new(3, 4);
+        seg
+    }
+
+    fn text(attachment_level: u16) -> TextSegment {
+        let mut seg = TextSegment::default();
+        seg.header.txtalvl.val = attachment_level;
+        seg
+    }
+
+    #[test]
+    fn nested_attachments() {
+        let mut nitf = Nitf::default();
+        nitf.add_im(image(1, 0, RowColumn::new(100, 200)));
+        nitf.add_sy(graphic(3, 2, RowColumn::new(1, 2)));
+        nitf.add_im(image(2, 1, RowColumn::new(-5, 10)));
+        nitf.add_te(text(3));
+        nitf.add_te(text(0));
+        let layout = nitf.ccs_layout().unwrap();
+
+        let levels: Vec<_> = layout.placements.iter().map(|p| p.display_level).collect();
+        assert_eq!(levels, [1, 2, 3]);
+        assert_eq!(layout.roots, [SegmentId::Image(0), SegmentId::Text(1)]);
+
+        let base = layout.get(SegmentId::Image(0)).unwrap();
+        assert_eq!(base.children, [SegmentId::Image(1)]);
+        // ILOC and SLOC accumulate down the attachment tree
+        let inner = layout.at_level(2).unwrap();
+        assert_eq!(inner.segment, SegmentId::Image(1));
+        assert_eq!(inner.location, RowColumn::new(95, 210));
+        assert_eq!(inner.lower_right, RowColumn::new(104, 229));
+        assert_eq!(inner.children, [SegmentId::Graphic(0)]);
+        let label = layout.get(SegmentId::Graphic(0)).unwrap();
+        assert_eq!(label.location, RowColumn::new(96, 212));
+        assert_eq!(label.upper_left, RowColumn::new(95, 211));
+        assert_eq!(label.lower_right, RowColumn::new(99, 216));
+        assert_eq!(label.children, [SegmentId::Text(0)]);
+
+        let bounds = layout.bounds().unwrap();
+        assert_eq!(bounds, (RowColumn::new(95, 200), RowColumn::new(109, 229)));
+    }
+
+    #[test]
+    fn invalid_levels() {
+        // Duplicate display levels
+        let mut nitf = Nitf::default();
+        nitf.add_im(image(1, 0, RowColumn::default()));
+        nitf.add_sy(graphic(1, 0, RowColumn::default()));
+        assert!(nitf.ccs_layout().is_err());
+
+        // Zero display level
+        let mut nitf = Nitf::default();
+        nitf.add_im(image(0, 0, RowColumn::default()));
+        assert!(nitf.ccs_layout().is_err());
+
+        // Missing attachment targets, for graphics and for text
+        let mut nitf = Nitf::default();
+        nitf.add_im(image(1, 0, RowColumn::default()));
+        nitf.add_sy(graphic(2, 5, RowColumn::default()));
+        assert!(nitf.ccs_layout().is_err());
+        let mut nitf = Nitf::default();
+        nitf.add_im(image(1, 0, RowColumn::default()));
+        nitf.add_te(text(5));
+        assert!(nitf.ccs_layout().is_err());
+
+        // Attachment to a higher display level
+        let mut nitf = Nitf::default();
+        nitf.add_im(image(1, 2, RowColumn::default()));
+        nitf.add_sy(graphic(2, 0, RowColumn::default()));
+        assert!(nitf.ccs_layout().is_err());
+    }
+}
@@ -38,13 +38,33 @@
 //! ```
 //!
 //! If there is user-defined tagged-record-extension (TRE) data within a segment,
-//! it is stored in an [ExtendedSubheader] for the user to parse accordingly.
+//! it is stored in an [ExtendedSubheader](types::ExtendedSubheader). The
+//! individual TREs can be listed with
+//! [ExtendedSubheader::tres()](types::ExtendedSubheader::tres).
+//! ```no_run
+//! let mut nitf_file = std::fs::File::open("example.nitf").unwrap();
+//! let nitf = nitf_rs::Nitf::from_reader(&mut nitf_file).unwrap();
+//! for tre in nitf.image_segments[0].header.ixshd.tres().unwrap() {
+//!     println!("{}: {} bytes", tre.cetag, tre.cel);
+//! }
+//! ```
 use thiserror::Error;
 
+pub mod cgm;
+mod chip;
+pub mod geo;
 pub mod headers;
+pub mod layout;
 mod nitf;
+mod overflow;
+pub mod product;
+pub mod render;
+mod streaming;
+pub mod tre;
 pub mod types;
+pub mod usmtf;
 
+pub use chip::ChipWindow;
 pub use nitf::*;
 
 pub type NitfResult<T> = Result<T, NitfError>;
@@ -64,6 +84,14 @@ pub enum NitfError {
     Value(String),
     #[error("Couldn't update header values")]
     Update(),
+    #[error("TRE error: {0}")]
+    TreError(String),
+    #[error("{name} CRC mismatch: expected {expected:05}, computed {computed:05}")]
+    CrcMismatch {
+        name: String,
+        expected: u16,
+        computed: u16,
+    },
     // Wrappers for built in errors
     #[error(transparent)]
     IOError(#[from] std::io::Error),
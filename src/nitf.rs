@@ -33,11 +33,22 @@ pub struct Nitf {
 
 impl Nitf {
     /// Construct a [Nitf] from a reader
+    ///
+    /// TREs stored in `TRE_OVERFLOW` data extension segments are attached to
+    /// the header they overflowed from.
+    ///
+    /// If the file header is a placeholder written by a streaming producer, the
+    /// real header is read from the `STREAMING_FILE_HEADER` data extension
+    /// segment at the end of the file, and that segment is dropped.
+    ///
+    /// Segment data is not read, so DES CRCs are not checked here. They are
+    /// verified by [DataExtensionSegment::read_data()].
     pub fn from_reader(reader: &mut (impl Read + Seek)) -> NitfResult<Self> {
         let mut nitf = Nitf::default();
 
         debug!("Reading NITF file header");
         nitf.nitf_header.read(reader)?;
+        nitf.read_streaming_header(reader)?;
 
         let mut n_seg = nitf.nitf_header.numi.val as usize;
         for i_seg in 0..n_seg {
@@ -78,14 +89,29 @@ impl Nitf {
             let seg = ReservedExtensionSegment::read(reader, data_size)?;
             nitf.reserved_extension_segments.push(seg);
         }
+        nitf.remove_streaming_header();
+        nitf.read_overflow(reader)?;
         Ok(nitf)
     }
 
     /// Write the header information for all segments to a file
+    ///
+    /// Length fields are updated with [Nitf::update_headers()] before writing.
+    /// The data of `TRE_OVERFLOW` segments is written as well, since it is held
+    /// by the header it overflowed from.
     pub fn write_headers(&mut self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
-        let mut bytes_written = 0;
-
+        self.update_headers()?;
         let file_length = self.length() as u64;
+        self.write_headers_with_length(writer, file_length)
+    }
+
+    /// Write all headers with the given FL, without updating them first
+    pub(crate) fn write_headers_with_length(
+        &mut self,
+        writer: &mut (impl Write + Seek),
+        file_length: u64,
+    ) -> NitfResult<usize> {
+        let mut bytes_written = 0;
         bytes_written += self.nitf_header.write_header(writer, file_length)?;
         for seg in self.image_segments.iter_mut() {
             bytes_written += seg.write_header(writer)?;
@@ -102,9 +128,68 @@ impl Nitf {
         for seg in self.reserved_extension_segments.iter_mut() {
             bytes_written += seg.write_header(writer)?;
         }
+        bytes_written += self.write_overflow(writer)?;
         Ok(bytes_written)
     }
 
+    /// Recompute length fields after segment metadata has been modified.
+    ///
+    /// This spills TREs which do not fit in their header to `TRE_OVERFLOW`
+    /// segments, and updates the TRE data length and overflow fields of each
+    /// header, the subheader and data sizes recorded in the file header, and
+    /// the segment offsets.
+    pub fn update_headers(&mut self) -> NitfResult<()> {
+        self.update_overflow()?;
+        for (seg, info) in self
+            .image_segments
+            .iter_mut()
+            .zip(self.nitf_header.imheaders.iter_mut())
+        {
+            seg.header.update_lengths()?;
+            info.subheader_size.val = seg.header.length() as u32;
+            info.item_size.val = seg.data_size;
+        }
+        for (seg, info) in self
+            .graphic_segments
+            .iter_mut()
+            .zip(self.nitf_header.graphheaders.iter_mut())
+        {
+            seg.header.update_lengths()?;
+            info.subheader_size.val = seg.header.length() as u32;
+            info.item_size.val = seg.data_size;
+        }
+        for (seg, info) in self
+            .text_segments
+            .iter_mut()
+            .zip(self.nitf_header.textheaders.iter_mut())
+        {
+            seg.header.update_lengths()?;
+            info.subheader_size.val = seg.header.length() as u32;
+            info.item_size.val = seg.data_size;
+        }
+        for (seg, info) in self
+            .data_extension_segments
+            .iter_mut()
+            .zip(self.nitf_header.dextheaders.iter_mut())
+        {
+            seg.header.update_lengths()?;
+            info.subheader_size.val = seg.header.length() as u32;
+            info.item_size.val = seg.data_size;
+        }
+        for (seg, info) in self
+            .reserved_extension_segments
+            .iter_mut()
+            .zip(self.nitf_header.resheaders.iter_mut())
+        {
+            seg.header.update_lengths()?;
+            info.subheader_size.val = seg.header.length() as u32;
+            info.item_size.val = seg.data_size;
+        }
+        self.nitf_header.update_lengths()?;
+        self.update_offsets();
+        Ok(())
+    }
+
     /// Get the length of the [Nitf] file in bytes
     pub fn length(&self) -> usize {
         let mut length = 0;
@@ -0,0 +1,320 @@
+//! TRE_OVERFLOW data extension segments
+//!
+//! TREs which do not fit in a header are stored in a `TRE_OVERFLOW` DES,
+//! identified by DESOFLW and DESITEM. When reading, they are attached to the
+//! [ExtendedSubheader] they overflowed from. When writing, TREs which no longer
+//! fit are spilled to an overflow DES, which is created or removed as needed.
+use log::{debug, warn};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::headers::data_extension_hdr::OverflowedHeaderType;
+use crate::headers::DataExtensionHeader;
+use crate::types::{ExtendedSubheader, NitfField, Security};
+use crate::{DataExtensionSegment, Nitf, NitfResult};
+
+/// DESID of overflow segments
+const TRE_OVERFLOW: &str = "TRE_OVERFLOW";
+
+/// Header fields describing TREs which can overflow
+struct Target<'a> {
+    tres: &'a mut ExtendedSubheader,
+    length: &'a NitfField<u32>,
+    overflow: &'a mut NitfField<u16>,
+    security: &'a Security,
+}
+impl Target<'_> {
+    /// Number of TRE bytes which fit in the header
+    fn capacity(&self) -> usize {
+        ExtendedSubheader::capacity(self.length, self.overflow)
+    }
+}
+
+fn is_overflow(header: &DataExtensionHeader) -> bool {
+    header.desid.val.trim() == TRE_OVERFLOW
+}
+
+impl Nitf {
+    /// Header fields for TREs of type `kind` in item `item`, where item 0 is
+    /// the file header and other items are 1-based segment indices
+    fn overflow_target(&mut self, kind: OverflowedHeaderType, item: u16) -> Option<Target<'_>> {
+        use OverflowedHeaderType::*;
+        let index = (item as usize).checked_sub(1);
+        match kind {
+            UDHD | XHD if item != 0 => None,
+            UDHD => {
+                let hdr = &mut self.nitf_header;
+                Some(Target {
+                    tres: &mut hdr.udhd,
+                    length: &hdr.udhdl,
+                    overflow: &mut hdr.udhofl,
+                    security: &hdr.security,
+                })
+            }
+            XHD => {
+                let hdr = &mut self.nitf_header;
+                Some(Target {
+                    tres: &mut hdr.xhd,
+                    length: &hdr.xhdl,
+                    overflow: &mut hdr.xhdlofl,
+                    security: &hdr.security,
+                })
+            }
+            UDID => {
+                let hdr = &mut self.image_segments.get_mut(index?)?.header;
+                Some(Target {
+                    tres: &mut hdr.udid,
+                    length: &hdr.udidl,
+                    overflow: &mut hdr.udofl,
+                    security: &hdr.security,
+                })
+            }
+            IXSHD => {
+                let hdr = &mut self.image_segments.get_mut(index?)?.header;
+                Some(Target {
+                    tres: &mut hdr.ixshd,
+                    length: &hdr.ixshdl,
+                    overflow: &mut hdr.ixsofl,
+                    security: &hdr.security,
+                })
+            }
+            SXSHD => {
+                let hdr = &mut self.graphic_segments.get_mut(index?)?.header;
+                Some(Target {
+                    tres: &mut hdr.sxshd,
+                    length: &hdr.sxshdl,
+                    overflow: &mut hdr.sxsofl,
+                    security: &hdr.security,
+                })
+            }
+            TXSHD => {
+                let hdr = &mut self.text_segments.get_mut(index?)?.header;
+                Some(Target {
+                    tres: &mut hdr.txshd,
+                    length: &hdr.txshdl,
+                    overflow: &mut hdr.txsofl,
+                    security: &hdr.security,
+                })
+            }
+        }
+    }
+
+    /// Every header location which can hold TREs, in file order
+    fn overflow_locations(&self) -> Vec<(OverflowedHeaderType, u16)> {
+        use OverflowedHeaderType::*;
+        let mut locations = vec![(UDHD, 0), (XHD, 0)];
+        for item in 1..=self.image_segments.len() as u16 {
+            locations.push((UDID, item));
+            locations.push((IXSHD, item));
+        }
+        for item in 1..=self.graphic_segments.len() as u16 {
+            locations.push((SXSHD, item));
+        }
+        for item in 1..=self.text_segments.len() as u16 {
+            locations.push((TXSHD, item));
+        }
+        locations
+    }
+
+    /// Attach the TREs of each `TRE_OVERFLOW` DES to the header they
+    /// overflowed from
+    pub(crate) fn read_overflow(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        for i_seg in 0..self.data_extension_segments.len() {
+            let seg = &self.data_extension_segments[i_seg];
+            if !is_overflow(&seg.header) {
+                continue;
+            }
+            let kind = seg.header.desoflw.val;
+            let item = seg.header.desitem.val;
+            let mut bytes = vec![0; seg.data_size as usize];
+            reader.seek(SeekFrom::Start(seg.data_offset))?;
+            reader.read_exact(&mut bytes)?;
+            match self.overflow_target(kind, item) {
+                Some(target) => {
+                    debug!("Read {} overflow TRE bytes for {kind} {item}", bytes.len());
+                    target.tres.set_overflow(bytes, i_seg);
+                }
+                None => warn!("TRE_OVERFLOW segment {i_seg} refers to missing {kind} {item}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Spill TREs which do not fit in their header to `TRE_OVERFLOW` DESs, and
+    /// update the overflow fields and segments to match.
+    ///
+    /// Existing overflow segments are reused, new ones are appended, and ones
+    /// which are no longer needed are removed.
+    pub(crate) fn update_overflow(&mut self) -> NitfResult<()> {
+        let mut needed = vec![];
+        for (kind, item) in self.overflow_locations() {
+            if let Some(target) = self.overflow_target(kind, item) {
+                let capacity = target.capacity();
+                target.tres.spill(capacity)?;
+                if target.tres.overflow().is_empty() {
+                    target.overflow.val = 0;
+                } else {
+                    needed.push((kind, item));
+                }
+            }
+        }
+
+        let mut i_seg = 0;
+        while i_seg < self.data_extension_segments.len() {
+            let header = &self.data_extension_segments[i_seg].header;
+            let location = (header.desoflw.val, header.desitem.val);
+            if is_overflow(header) && !needed.contains(&location) {
+                debug!("Removing unused TRE_OVERFLOW segment {i_seg}");
+                self.data_extension_segments.remove(i_seg);
+                self.nitf_header.dextheaders.remove(i_seg);
+                self.nitf_header.numdes.val -= 1;
+            } else {
+                i_seg += 1;
+            }
+        }
+
+        for (kind, item) in needed {
+            let existing = self.data_extension_segments.iter().position(|seg| {
+                is_overflow(&seg.header)
+                    && seg.header.desoflw.val == kind
+                    && seg.header.desitem.val == item
+            });
+            let i_seg = match existing {
+                Some(i_seg) => i_seg,
+                None => {
+                    let security = match self.overflow_target(kind, item) {
+                        Some(target) => target.security.clone(),
+                        None => continue,
+                    };
+                    let mut seg = DataExtensionSegment::default();
+                    seg.header.desid.val = TRE_OVERFLOW.to_string();
+                    seg.header.desver.val = 1;
+                    seg.header.security = security;
+                    seg.header.desoflw.val = kind;
+                    seg.header.desitem.val = item;
+                    self.add_de(seg);
+                    debug!("Added TRE_OVERFLOW segment for {kind} {item}");
+                    self.data_extension_segments.len() - 1
+                }
+            };
+            if let Some(target) = self.overflow_target(kind, item) {
+                target.overflow.val = i_seg as u16 + 1;
+                target.tres.set_overflow_segment(i_seg);
+                let data_size = target.tres.overflow().len() as u64;
+                self.data_extension_segments[i_seg].data_size = data_size;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the data of each `TRE_OVERFLOW` DES
+    pub(crate) fn write_overflow(&mut self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        for i_seg in 0..self.data_extension_segments.len() {
+            let header = &self.data_extension_segments[i_seg].header;
+            if !is_overflow(header) {
+                continue;
+            }
+            let (kind, item) = (header.desoflw.val, header.desitem.val);
+            let Some(target) = self.overflow_target(kind, item) else {
+                continue;
+            };
+            let data = target.tres.overflow().clone();
+            bytes_written += self.data_extension_segments[i_seg].write_data(writer, &data)?;
+        }
+        Ok(bytes_written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::image_hdr::Band;
+    use crate::types::{RawTre, TreOrigin};
+    use crate::ImageSegment;
+    use std::io::Cursor;
+
+    fn nitf_with_tres(tres: &[RawTre]) -> Nitf {
+        let mut seg = ImageSegment::default();
+        seg.header.nbands.val = 1;
+        seg.header.bands = vec![Band::default()];
+        seg.header.ixshd.set_tres(tres).unwrap();
+        let mut nitf = Nitf::default();
+        nitf.add_im(seg);
+        nitf
+    }
+
+    #[test]
+    fn spill_and_reassemble() {
+        // Two entries are too large for the 5 digit IXSHDL together
+        let tres = [
+            RawTre::new("FIRSTA", vec![b'1'; 60000]),
+            RawTre::new("SECNDA", vec![b'2'; 60000]),
+        ];
+        let mut nitf = nitf_with_tres(&tres);
+        nitf.update_headers().unwrap();
+
+        assert_eq!(nitf.nitf_header.numdes.val, 1);
+        let des = &nitf.data_extension_segments[0].header;
+        assert_eq!(des.desid.val.trim(), TRE_OVERFLOW);
+        assert_eq!(des.desoflw.val, OverflowedHeaderType::IXSHD);
+        assert_eq!(des.desitem.val, 1);
+        let header = &nitf.image_segments[0].header;
+        assert_eq!(header.ixsofl.val, 1);
+        assert_eq!(header.ixshdl.val, 60011 + 3);
+        assert_eq!(nitf.data_extension_segments[0].data_size, 60011);
+
+        let mut file = Cursor::new(vec![]);
+        nitf.write_headers(&mut file).unwrap();
+        file.set_position(0);
+        let read = Nitf::from_reader(&mut file).unwrap();
+        assert_eq!(read.data_extension_segments.len(), 1);
+        let header = &read.image_segments[0].header;
+        assert_eq!(header.ixsofl.val, 1);
+        assert_eq!(header.ixshd.overflow_segment(), Some(0));
+        let read_tres = header.ixshd.tres().unwrap();
+        let origins: Vec<_> = read_tres.iter().map(|t| t.origin).collect();
+        assert_eq!(origins, [TreOrigin::Header, TreOrigin::Overflow(0)]);
+        for (read_tre, tre) in read_tres.iter().zip(&tres) {
+            assert_eq!((&read_tre.cetag, &read_tre.data), (&tre.cetag, &tre.data));
+        }
+
+        // The overflow segment is removed once everything fits again
+        let mut read = read;
+        read.image_segments[0]
+            .header
+            .ixshd
+            .remove_tre("SECNDA")
+            .unwrap();
+        read.update_headers().unwrap();
+        assert_eq!(read.nitf_header.numdes.val, 0);
+        assert!(read.data_extension_segments.is_empty());
+        assert_eq!(read.image_segments[0].header.ixsofl.val, 0);
+    }
+
+    #[test]
+    fn no_overflow() {
+        let mut nitf = nitf_with_tres(&[RawTre::new("SMALLA", vec![b'0'; 10])]);
+        nitf.update_headers().unwrap();
+        assert!(nitf.data_extension_segments.is_empty());
+        let header = &nitf.image_segments[0].header;
+        assert_eq!(header.ixsofl.val, 0);
+        assert_eq!(header.ixshd.tres().unwrap()[0].origin, TreOrigin::Header);
+    }
+
+    #[test]
+    fn capacity_boundary() {
+        // An 11 byte TRE header plus the data fill the 99996 bytes left by
+        // the 5 digit IXSHDL and the 3 digit IXSOFL
+        let mut nitf = nitf_with_tres(&[RawTre::new("EXACTA", vec![b'0'; 99985])]);
+        nitf.update_headers().unwrap();
+        assert!(nitf.data_extension_segments.is_empty());
+        assert_eq!(nitf.image_segments[0].header.ixshdl.val, 99999);
+
+        let mut nitf = nitf_with_tres(&[RawTre::new("EXCESS", vec![b'0'; 99986])]);
+        nitf.update_headers().unwrap();
+        assert_eq!(nitf.data_extension_segments.len(), 1);
+        let header = &nitf.image_segments[0].header;
+        assert_eq!(header.ixsofl.val, 1);
+        assert_eq!(header.ixshdl.val, 3);
+    }
+}
@@ -0,0 +1,340 @@
+//! SICD and SIDD product recognition
+//!
+//! SICD and SIDD files carry their metadata as XML in `XML_DATA_CONTENT`
+//! data extension segments, and name their image segments with IID1:
+//! - SICD image segments are `SICD000` for a single segment, or `SICD001`,
+//!   `SICD002`, ... when the image is split.
+//! - SIDD image segments are `SIDDjjjnnn` for segment `nnn` of product image
+//!   `jjj`. The `j`th SIDD XML segment describes product image `j`.
+//!
+//! Parsing the XML itself is left to downstream crates.
+use log::warn;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::{Read, Seek};
+
+use crate::{Nitf, NitfResult};
+
+/// Kind of XML metadata held by a data extension segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XmlKind {
+    /// Sensor Independent Complex Data
+    Sicd,
+    /// Sensor Independent Derived Data
+    Sidd,
+}
+
+/// XML_DATA_CONTENT segment with SICD or SIDD metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlSegment {
+    /// Index of the data extension segment
+    pub des_index: usize,
+    /// XML namespace, such as `urn:SICD:1.3.0`. Empty if it couldn't be found
+    pub namespace: String,
+    /// XML payload
+    pub xml: String,
+}
+
+/// SICD product
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SicdProduct {
+    /// SICD metadata
+    pub xml: XmlSegment,
+    /// Indices of the image segments holding the complex image, in order
+    pub image_segments: Vec<usize>,
+}
+
+/// Single product image of a SIDD
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiddImage {
+    /// SIDD metadata for this product image
+    pub xml: XmlSegment,
+    /// Indices of the image segments holding the product image, in order
+    pub image_segments: Vec<usize>,
+}
+
+/// SIDD product
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiddProduct {
+    /// Product images, in the order of their SIDD XML segments
+    pub images: Vec<SiddImage>,
+    /// SICD metadata of the source data, if included
+    pub sicd_xml: Vec<XmlSegment>,
+}
+
+/// Recognized SICD or SIDD product
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SarProduct {
+    Sicd(SicdProduct),
+    Sidd(SiddProduct),
+}
+
+/// Namespace and local name of the root element of an XML document
+fn xml_root(xml: &str) -> Option<(String, String)> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(e) | Event::Empty(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                let prefix = e.name().prefix().map(|p| p.as_ref().to_vec());
+                let xmlns = match &prefix {
+                    Some(prefix) => [b"xmlns:".as_slice(), prefix].concat(),
+                    None => b"xmlns".to_vec(),
+                };
+                let namespace = e
+                    .attributes()
+                    .flatten()
+                    .find(|attr| attr.key.as_ref() == xmlns.as_slice())
+                    .map(|attr| String::from_utf8_lossy(&attr.value).to_string())
+                    .unwrap_or_default();
+                return Some((namespace, name));
+            }
+            Event::Eof => return None,
+            _ => {}
+        }
+    }
+}
+
+/// Classify XML metadata from its namespace, or the root element name
+fn classify(namespace: &str, root: &str) -> Option<XmlKind> {
+    if namespace.starts_with("urn:SICD") || root == "SICD" {
+        Some(XmlKind::Sicd)
+    } else if namespace.starts_with("urn:SIDD") || root == "SIDD" {
+        Some(XmlKind::Sidd)
+    } else {
+        None
+    }
+}
+
+impl Nitf {
+    /// SICD and SIDD metadata in the `XML_DATA_CONTENT` segments, in segment
+    /// order
+    ///
+    /// Segments are classified by DESSHTN when it is set, and by the root of
+    /// the XML otherwise. Errors reading a SICD or SIDD segment are returned,
+    /// while segments which cannot be read or classified are skipped with a
+    /// warning.
+    pub fn sar_xml(
+        &self,
+        reader: &mut (impl Read + Seek),
+    ) -> NitfResult<Vec<(XmlKind, XmlSegment)>> {
+        let mut segments = vec![];
+        for (des_index, seg) in self.data_extension_segments.iter().enumerate() {
+            if !seg.header.is_xml_data_content() {
+                continue;
+            }
+            let subheader = match seg.header.xml_data_content() {
+                Ok(subheader) => subheader.unwrap_or_default(),
+                Err(e) => {
+                    warn!("Skipping XML segment {des_index}: {e}");
+                    continue;
+                }
+            };
+            let (xml, namespace, kind) = match subheader.desshtn.as_str() {
+                "" => {
+                    let xml = match seg.read_xml(reader) {
+                        Ok(xml) => xml,
+                        Err(e) => {
+                            warn!("Skipping XML segment {des_index}: {e}");
+                            continue;
+                        }
+                    };
+                    let (namespace, root) = xml_root(&xml).unwrap_or_default();
+                    let kind = classify(&namespace, &root);
+                    (xml, namespace, kind)
+                }
+                namespace => match classify(namespace, "") {
+                    Some(kind) => (seg.read_xml(reader)?, namespace.to_string(), Some(kind)),
+                    None => continue,
+                },
+            };
+            if let Some(kind) = kind {
+                let xml = XmlSegment {
+                    des_index,
+                    namespace,
+                    xml,
+                };
+                segments.push((kind, xml));
+            }
+        }
+        Ok(segments)
+    }
+
+    /// Recognize a SICD or SIDD product from its XML segments and image
+    /// segment layout. `None` if there is no SICD or SIDD XML.
+    ///
+    /// A file with any SIDD XML is a SIDD, and SICD XML segments within it
+    /// describe the source data.
+    pub fn sar_product(&self, reader: &mut (impl Read + Seek)) -> NitfResult<Option<SarProduct>> {
+        let (sidd_xml, sicd_xml): (Vec<_>, Vec<_>) = self
+            .sar_xml(reader)?
+            .into_iter()
+            .partition(|(kind, _)| *kind == XmlKind::Sidd);
+        let sicd_xml: Vec<_> = sicd_xml.into_iter().map(|(_, xml)| xml).collect();
+
+        if !sidd_xml.is_empty() {
+            let images = sidd_xml
+                .into_iter()
+                .enumerate()
+                .map(|(i_product, (_, xml))| {
+                    let image_segments = self.sidd_image_segments(i_product + 1);
+                    if image_segments.is_empty() {
+                        warn!("No image segments found for SIDD product {}", i_product + 1);
+                    }
+                    SiddImage {
+                        xml,
+                        image_segments,
+                    }
+                })
+                .collect();
+            return Ok(Some(SarProduct::Sidd(SiddProduct { images, sicd_xml })));
+        }
+
+        let Some(xml) = sicd_xml.into_iter().next() else {
+            return Ok(None);
+        };
+        let image_segments = self.sicd_image_segments();
+        if image_segments.is_empty() {
+            warn!("No SICD image segments found");
+        }
+        Ok(Some(SarProduct::Sicd(SicdProduct {
+            xml,
+            image_segments,
+        })))
+    }
+
+    /// Image segments named `SICDnnn`, ordered by `nnn`
+    fn sicd_image_segments(&self) -> Vec<usize> {
+        self.numbered_image_segments(|iid1| {
+            let number = iid1.strip_prefix("SICD")?;
+            (number.len() == 3).then(|| number.parse().ok())?
+        })
+    }
+
+    /// Image segments named `SIDDjjjnnn` for `product`, ordered by `nnn`
+    fn sidd_image_segments(&self, product: usize) -> Vec<usize> {
+        self.numbered_image_segments(|iid1| {
+            let numbers = iid1.strip_prefix("SIDD")?;
+            if numbers.len() != 6 || numbers.get(..3)?.parse::<usize>().ok()? != product {
+                return None;
+            }
+            numbers.get(3..)?.parse().ok()
+        })
+    }
+
+    fn numbered_image_segments(&self, number: impl Fn(&str) -> Option<usize>) -> Vec<usize> {
+        let mut segments: Vec<(usize, usize)> = self
+            .image_segments
+            .iter()
+            .enumerate()
+            .filter_map(|(i_seg, seg)| Some((number(seg.header.iid1.val.trim())?, i_seg)))
+            .collect();
+        segments.sort();
+        segments.into_iter().map(|(_, i_seg)| i_seg).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::image_hdr::Band;
+    use crate::headers::xml_data_content::XmlDataContent;
+    use crate::{DataExtensionSegment, ImageSegment};
+    use std::io::Cursor;
+
+    const SICD: &[u8] = br#"<SICD xmlns="urn:SICD:1.3.0"><CollectionInfo/></SICD>"#;
+
+    const SIDD: &[u8] = br#"<SIDD xmlns="urn:SIDD:2.0.0"><ProductCreation/></SIDD>"#;
+
+    /// File with an empty image segment for each IID1, and an XML segment for
+    /// each target namespace and payload
+    fn xml_file(iids: &[&str], segments: &[(&str, &[u8])]) -> (Nitf, Cursor<Vec<u8>>) {
+        let mut nitf = Nitf::default();
+        for iid1 in iids {
+            let mut seg = ImageSegment::default();
+            seg.header.iid1.val = iid1.to_string();
+            seg.header.nbands.val = 1;
+            seg.header.bands = vec![Band::default()];
+            nitf.add_im(seg);
+        }
+        for (desshtn, data) in segments {
+            let mut seg = DataExtensionSegment::default();
+            let xml = XmlDataContent {
+                desshl: 283,
+                desshtn: desshtn.to_string(),
+                ..Default::default()
+            };
+            seg.header.set_xml_data_content(&xml).unwrap();
+            seg.data_size = data.len() as u64;
+            nitf.add_de(seg);
+        }
+        let mut file = Cursor::new(vec![]);
+        nitf.write_headers(&mut file).unwrap();
+        for (seg, (_, data)) in nitf.data_extension_segments.iter_mut().zip(segments) {
+            seg.write_data(&mut file, data).unwrap();
+        }
+        file.set_position(0);
+        (Nitf::from_reader(&mut file).unwrap(), file)
+    }
+
+    #[test]
+    fn unrelated_xml_is_skipped() {
+        let (nitf, mut file) = xml_file(
+            &[],
+            &[
+                ("urn:example:other", b"\xff not UTF-8"),
+                ("", b"\xff not UTF-8"),
+                ("urn:SICD:1.3.0", SICD),
+            ],
+        );
+        let xml = nitf.sar_xml(&mut file).unwrap();
+        assert_eq!(xml.len(), 1);
+        assert_eq!(xml[0].0, XmlKind::Sicd);
+        assert_eq!(xml[0].1.des_index, 2);
+    }
+
+    #[test]
+    fn classified_by_root() {
+        let (nitf, mut file) = xml_file(&[], &[("", SICD)]);
+        let xml = nitf.sar_xml(&mut file).unwrap();
+        assert_eq!(xml[0].1.namespace, "urn:SICD:1.3.0");
+    }
+
+    #[test]
+    fn unreadable_sicd_is_an_error() {
+        let (nitf, mut file) = xml_file(&[], &[("urn:SICD:1.3.0", b"\xff not UTF-8")]);
+        assert!(nitf.sar_xml(&mut file).is_err());
+    }
+
+    #[test]
+    fn sidd_products() {
+        let (nitf, mut file) = xml_file(
+            &["SIDD002001", "SIDD001002", "SIDD001001", "SICD000"],
+            &[("urn:SIDD:2.0.0", SIDD), ("", SIDD), ("", SICD)],
+        );
+        let Some(SarProduct::Sidd(sidd)) = nitf.sar_product(&mut file).unwrap() else {
+            panic!("not a SIDD");
+        };
+        assert_eq!(sidd.images.len(), 2);
+        assert_eq!(sidd.images[0].xml.des_index, 0);
+        assert_eq!(sidd.images[0].image_segments, [2, 1]);
+        assert_eq!(sidd.images[1].xml.des_index, 1);
+        assert_eq!(sidd.images[1].image_segments, [0]);
+        // The SICD XML describes the source data of the SIDD
+        assert_eq!(sidd.sicd_xml.len(), 1);
+        assert_eq!(sidd.sicd_xml[0].des_index, 2);
+    }
+
+    #[test]
+    fn split_sicd() {
+        let (nitf, mut file) = xml_file(
+            &["SICD002", "OTHER", "SICD001"],
+            &[("urn:SICD:1.3.0", SICD)],
+        );
+        let Some(SarProduct::Sicd(sicd)) = nitf.sar_product(&mut file).unwrap() else {
+            panic!("not a SICD");
+        };
+        assert_eq!(sicd.xml.des_index, 0);
+        assert_eq!(sicd.image_segments, [2, 0]);
+    }
+}
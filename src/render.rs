@@ -0,0 +1,456 @@
+//! Composite rendering of a file in the common coordinate system (CCS)
+//!
+//! Image and graphic segments are drawn in increasing display level order
+//! over the file background color (FBKGC), so higher levels cover lower ones.
+use log::{debug, warn};
+use std::io::{Read, Seek};
+
+use crate::chip::{read_window, Layout};
+use crate::headers::image_hdr::{
+    Compression, ImageRepresentation, ImageRepresentationBand, PixelJustification, PixelValueType,
+};
+use crate::layout::{Placement, SegmentId};
+use crate::types::RowColumn;
+use crate::{ChipWindow, ImageSegment, Nitf, NitfError, NitfResult};
+
+/// Largest raster drawn by [Nitf::render()] and [Nitf::render_region()], in
+/// pixels
+pub const MAX_RENDER_PIXELS: usize = 1 << 26;
+
+/// Interleaved 8-bit RGB raster covering a region of the CCS
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RgbRaster {
+    /// CCS location of the first pixel
+    pub upper_left: RowColumn,
+    pub n_rows: usize,
+    pub n_cols: usize,
+    /// Red, green, and blue of each pixel, by row
+    pub data: Vec<u8>,
+}
+
+impl RgbRaster {
+    /// Raster filled with `background`
+    pub fn new(upper_left: RowColumn, n_rows: usize, n_cols: usize, background: [u8; 3]) -> Self {
+        Self {
+            upper_left,
+            n_rows,
+            n_cols,
+            data: background.repeat(n_rows * n_cols),
+        }
+    }
+
+    /// Index of the red value of the pixel at CCS `location`
+    fn index(&self, location: RowColumn) -> Option<usize> {
+        let row = usize::try_from(location.row - self.upper_left.row).ok()?;
+        let col = usize::try_from(location.col - self.upper_left.col).ok()?;
+        (row < self.n_rows && col < self.n_cols).then_some((row * self.n_cols + col) * 3)
+    }
+
+    /// Pixel at CCS `location`, if it is inside the raster
+    pub fn get(&self, location: RowColumn) -> Option<[u8; 3]> {
+        let i = self.index(location)?;
+        Some([self.data[i], self.data[i + 1], self.data[i + 2]])
+    }
+
+    /// Set the pixel at CCS `location`. Locations outside of the raster are
+    /// ignored.
+    pub fn set(&mut self, location: RowColumn, rgb: [u8; 3]) {
+        if let Some(i) = self.index(location) {
+            self.data[i..i + 3].copy_from_slice(&rgb);
+        }
+    }
+}
+
+impl Nitf {
+    /// Composite all image and graphic segments into one RGB raster.
+    ///
+    /// The raster covers the CCS origin and every segment placed by
+    /// [Nitf::ccs_layout()], and is filled with FBKGC before segments are
+    /// drawn in display level order. Pixel and graphic data is read from
+    /// `reader`, which should be the file this object was read from.
+    ///
+    /// Images must be uncompressed, with a whole number of bytes per pixel
+    /// and an integer or real pixel value type. Integers are scaled from
+    /// ABPP to 8 bits, or mapped through the band look-up tables, and real
+    /// values are stretched from their minimum to maximum within the drawn
+    /// region. Graphics are
+    /// drawn by [cgm::rasterize()](crate::cgm::rasterize). Segments which
+    /// cannot be drawn, or are not intended for display, are skipped with a
+    /// warning.
+    ///
+    /// Files covering more than [MAX_RENDER_PIXELS] are an error, and can be
+    /// drawn in parts with [Nitf::render_region()].
+    /// ```no_run
+    /// use nitf_rs::Nitf;
+    /// let mut nitf_file = std::fs::File::open("example.nitf").unwrap();
+    /// let nitf = Nitf::from_reader(&mut nitf_file).unwrap();
+    /// let raster = nitf.render(&mut nitf_file).unwrap();
+    /// println!("{} x {} pixels", raster.n_rows, raster.n_cols);
+    /// ```
+    pub fn render(&self, reader: &mut (impl Read + Seek)) -> NitfResult<RgbRaster> {
+        let origin = RowColumn::default();
+        let (upper_left, lower_right) = match self.ccs_layout()?.bounds() {
+            Some((ul, lr)) => (
+                RowColumn::new(ul.row.min(0), ul.col.min(0)),
+                RowColumn::new(lr.row.max(0), lr.col.max(0)),
+            ),
+            None => (origin, origin),
+        };
+        let size = |low: i32, high: i32| (high as i64 - low as i64 + 1) as usize;
+        self.render_region(
+            reader,
+            upper_left,
+            size(upper_left.row, lower_right.row),
+            size(upper_left.col, lower_right.col),
+        )
+    }
+
+    /// Composite the `n_rows` by `n_cols` region of the CCS starting at
+    /// `upper_left`, as [Nitf::render()] does for the whole file. Parts of
+    /// segments outside of the region are not drawn, and only the part of
+    /// each image inside the region is read.
+    pub fn render_region(
+        &self,
+        reader: &mut (impl Read + Seek),
+        upper_left: RowColumn,
+        n_rows: usize,
+        n_cols: usize,
+    ) -> NitfResult<RgbRaster> {
+        if n_rows.saturating_mul(n_cols) > MAX_RENDER_PIXELS {
+            Err(NitfError::Value(format!(
+                "Render region of {n_rows} x {n_cols} pixels is larger than {MAX_RENDER_PIXELS}"
+            )))?
+        }
+        let layout = self.ccs_layout()?;
+        let mut raster = RgbRaster::new(upper_left, n_rows, n_cols, self.nitf_header.fbkgc);
+        for placement in &layout.placements {
+            match placement.segment {
+                SegmentId::Image(i_seg) => {
+                    let Some(window) = visible_window(placement, &raster) else {
+                        continue;
+                    };
+                    let seg = &self.image_segments[i_seg];
+                    match image_rgb(seg, &window, reader)? {
+                        Some(pixels) => {
+                            let n_cols = window.n_cols as usize;
+                            for (i_pixel, rgb) in pixels.into_iter().enumerate() {
+                                let offset = RowColumn::new(
+                                    window.row as i32 + (i_pixel / n_cols) as i32,
+                                    window.col as i32 + (i_pixel % n_cols) as i32,
+                                );
+                                raster.set(placement.location + offset, rgb);
+                            }
+                        }
+                        None => warn!("Skipping image segment {i_seg}"),
+                    }
+                }
+                SegmentId::Graphic(i_seg) => {
+                    let seg = &self.graphic_segments[i_seg];
+                    let elements = seg.read_cgm(reader)?;
+                    crate::cgm::rasterize(
+                        &elements,
+                        seg.header.scolor.val,
+                        placement.location,
+                        &mut raster,
+                    );
+                }
+                SegmentId::Text(_) => {}
+            }
+            debug!("Rendered {:?}", placement.segment);
+        }
+        Ok(raster)
+    }
+}
+
+/// Part of an image placement inside the raster, in image pixels, or `None`
+/// if they do not overlap
+fn visible_window(placement: &Placement, raster: &RgbRaster) -> Option<ChipWindow> {
+    let (upper_left, lower_right) = (placement.upper_left, placement.lower_right);
+    let last = |first: i32, n: usize| first as i64 + n as i64 - 1;
+    let row0 = (raster.upper_left.row as i64).max(upper_left.row as i64);
+    let col0 = (raster.upper_left.col as i64).max(upper_left.col as i64);
+    let row1 = last(raster.upper_left.row, raster.n_rows).min(lower_right.row as i64);
+    let col1 = last(raster.upper_left.col, raster.n_cols).min(lower_right.col as i64);
+    if row1 < row0 || col1 < col0 {
+        return None;
+    }
+    let location = placement.location;
+    Some(ChipWindow::new(
+        (row0 - location.row as i64) as u32,
+        (col0 - location.col as i64) as u32,
+        (row1 - row0 + 1) as u32,
+        (col1 - col0 + 1) as u32,
+    ))
+}
+
+/// Bands drawn as red, green, and blue, or a single gray band
+fn display_bands(seg: &ImageSegment) -> Option<Vec<usize>> {
+    let header = &seg.header;
+    let n_bands = header.bands.len();
+    let find = |irepband: ImageRepresentationBand| {
+        header
+            .bands
+            .iter()
+            .position(|band| band.irepband.val == irepband)
+    };
+    let rgb = || {
+        use ImageRepresentationBand::*;
+        match (find(R), find(G), find(B)) {
+            (Some(r), Some(g), Some(b)) => Some(vec![r, g, b]),
+            _ => None,
+        }
+    };
+    match header.irep.val {
+        ImageRepresentation::MONO | ImageRepresentation::RGBLUT if n_bands > 0 => {
+            Some(vec![find(ImageRepresentationBand::M).unwrap_or(0)])
+        }
+        ImageRepresentation::RGB if n_bands >= 3 => rgb().or(Some(vec![0, 1, 2])),
+        ImageRepresentation::MULTI if n_bands > 0 => rgb().or(Some(vec![0])),
+        _ => None,
+    }
+}
+
+/// Convert the pixels of `window` of an image segment to RGB, by row, or
+/// `None` if the image cannot be drawn
+fn image_rgb(
+    seg: &ImageSegment,
+    window: &ChipWindow,
+    reader: &mut (impl Read + Seek),
+) -> NitfResult<Option<Vec<[u8; 3]>>> {
+    let header = &seg.header;
+    if header.ic.val != Compression::NC {
+        warn!("Cannot render image with compression {}", header.ic.val);
+        return Ok(None);
+    }
+    let nbpp = header.nbpp.val as usize;
+    let pvtype = header.pvtype.val;
+    let supported = match pvtype {
+        PixelValueType::INT | PixelValueType::SI => matches!(nbpp, 8 | 16 | 32 | 64),
+        PixelValueType::R => matches!(nbpp, 32 | 64),
+        PixelValueType::B | PixelValueType::C => false,
+    };
+    if !supported {
+        warn!("Cannot render {pvtype:?} image with {nbpp} bits per pixel");
+        return Ok(None);
+    }
+    let Some(bands) = display_bands(seg) else {
+        warn!("Cannot render image representation {:?}", header.irep.val);
+        return Ok(None);
+    };
+
+    let layout = Layout::new(header)?;
+    if (layout.data_size() as u64) > seg.data_size {
+        Err(NitfError::Fatal(
+            "Image data is smaller than the size described by the header".to_string(),
+        ))?
+    }
+    let window_layout = layout.window(window);
+    let data = read_window(reader, seg, &layout, &window_layout, window)?;
+
+    let (n_rows, n_cols) = (window.n_rows as usize, window.n_cols as usize);
+    let bpp = layout.bytes_per_pixel;
+    // Unused low bits of left justified integers
+    let shift = match header.pjust.val {
+        PixelJustification::L => nbpp.saturating_sub(header.abpp.val as usize),
+        PixelJustification::R => 0,
+    };
+    let abpp = match header.abpp.val as usize {
+        0 => nbpp,
+        abpp => abpp.min(nbpp),
+    };
+    let value = |row: usize, col: usize, band: usize| {
+        let offset = window_layout.offset(row, col, band);
+        let bytes = &data[offset..offset + bpp];
+        match pvtype {
+            PixelValueType::R if bpp == 4 => {
+                f32::from_be_bytes(bytes.try_into().unwrap_or_default()) as f64
+            }
+            PixelValueType::R => f64::from_be_bytes(bytes.try_into().unwrap_or_default()),
+            _ => {
+                let raw = bytes.iter().fold(0u64, |v, b| (v << 8) | *b as u64) >> shift;
+                match pvtype {
+                    // Sign extend from the significant bits
+                    PixelValueType::SI => ((raw << (64 - abpp)) as i64 >> (64 - abpp)) as f64,
+                    _ => raw as f64,
+                }
+            }
+        }
+    };
+
+    // Each displayed band as 8-bit values, or RGB through look-up tables
+    let mut channels: Vec<Vec<u8>> = vec![];
+    for band in bands.iter().copied() {
+        let values = (0..n_rows * n_cols)
+            .map(|i| value(i / n_cols, i % n_cols, band))
+            .collect::<Vec<_>>();
+        let luts = &header.bands[band].lutd;
+        if pvtype == PixelValueType::INT && !luts.is_empty() {
+            for lut in luts.iter().take(3) {
+                let mapped = values
+                    .iter()
+                    .map(|v| lut.get(*v as usize).copied().unwrap_or(0))
+                    .collect();
+                channels.push(mapped);
+            }
+            continue;
+        }
+        let (low, high) = match pvtype {
+            PixelValueType::R => values
+                .iter()
+                .filter(|v| v.is_finite())
+                .fold((f64::MAX, f64::MIN), |(low, high), v| {
+                    (low.min(*v), high.max(*v))
+                }),
+            PixelValueType::SI => {
+                let half = (1u64 << (abpp - 1)) as f64;
+                (-half, half - 1.0)
+            }
+            _ => (0.0, (u64::MAX >> (64 - abpp)) as f64),
+        };
+        let scale = match high > low {
+            true => 255.0 / (high - low),
+            false => 0.0,
+        };
+        channels.push(
+            values
+                .iter()
+                .map(|v| ((v - low) * scale).round().clamp(0.0, 255.0) as u8)
+                .collect(),
+        );
+    }
+    Ok(Some(
+        (0..n_rows * n_cols)
+            .map(|i| match channels.as_slice() {
+                [gray] => [gray[i]; 3],
+                [r, g, b, ..] => [r[i], g[i], b[i]],
+                // Two look-up tables are not a color mapping
+                [gray, ..] => [gray[i]; 3],
+                [] => [0; 3],
+            })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cgm::{CgmBuilder, Color, Point};
+    use crate::headers::image_hdr::{Band, Mode};
+    use std::io::Cursor;
+
+    /// File with a red filled square graphic at each location
+    fn graphics_file(locations: &[RowColumn]) -> Cursor<Vec<u8>> {
+        let mut builder = CgmBuilder::new("square");
+        builder
+            .fill(Some(Color::Rgb(255, 0, 0)))
+            .rectangle(Point::from_pixel(0, 0), Point::from_pixel(9, 9));
+        let mut nitf = Nitf::default();
+        let mut data = vec![];
+        for (i, location) in locations.iter().enumerate() {
+            let (seg, bytes) = builder.build(*location, i as u16 + 1, 0).unwrap();
+            nitf.add_sy(seg);
+            data.push(bytes);
+        }
+        let mut file = Cursor::new(vec![]);
+        nitf.write_headers(&mut file).unwrap();
+        for (seg, bytes) in nitf.graphic_segments.iter_mut().zip(&data) {
+            seg.write_data(&mut file, bytes).unwrap();
+        }
+        file.set_position(0);
+        file
+    }
+
+    #[test]
+    fn render_graphics() {
+        let mut file = graphics_file(&[RowColumn::new(5, 5)]);
+        let nitf = Nitf::from_reader(&mut file).unwrap();
+        let raster = nitf.render(&mut file).unwrap();
+        // The bounds of the graphic include a margin for its edge
+        assert_eq!((raster.n_rows, raster.n_cols), (16, 16));
+        assert_eq!(raster.get(RowColumn::new(0, 0)), Some([0; 3]));
+        assert_eq!(raster.get(RowColumn::new(10, 10)), Some([255, 0, 0]));
+    }
+
+    #[test]
+    fn large_files_are_rendered_by_region() {
+        let mut file = graphics_file(&[RowColumn::new(0, 0), RowColumn::new(90000, 90000)]);
+        let nitf = Nitf::from_reader(&mut file).unwrap();
+        assert!(nitf.render(&mut file).is_err());
+
+        let upper_left = RowColumn::new(89995, 89995);
+        let raster = nitf.render_region(&mut file, upper_left, 20, 20).unwrap();
+        assert_eq!(raster.get(upper_left), Some([0; 3]));
+        assert_eq!(raster.get(RowColumn::new(90005, 90005)), Some([255, 0, 0]));
+        assert!(nitf
+            .render_region(&mut file, upper_left, 1 << 20, 1 << 20)
+            .is_err());
+    }
+
+    #[test]
+    fn image_region() {
+        // 6 x 7 gray image in 4 x 3 blocks at ILOC (10, 20), over a large red
+        // square graphic
+        let mut seg = ImageSegment::default();
+        let header = &mut seg.header;
+        header.idlvl.val = 2;
+        header.iloc.val = RowColumn::new(10, 20);
+        header.nrows.val = 6;
+        header.ncols.val = 7;
+        header.nbpp.val = 8;
+        header.abpp.val = 8;
+        header.pvtype.val = PixelValueType::INT;
+        header.irep.val = ImageRepresentation::MONO;
+        header.nbands.val = 1;
+        header.bands = vec![Band::default()];
+        header.imode.val = Mode::B;
+        header.nppbv.val = 4;
+        header.nppbh.val = 3;
+        header.nbpr.val = 3;
+        header.nbpc.val = 2;
+        let layout = Layout::new(header).unwrap();
+        let mut data = vec![0; layout.data_size()];
+        for row in 0..6 {
+            for col in 0..7 {
+                data[layout.offset(row, col, 0)] = (row * 16 + col) as u8;
+            }
+        }
+        seg.data_size = data.len() as u64;
+
+        let mut builder = CgmBuilder::new("square");
+        builder
+            .fill(Some(Color::Rgb(255, 0, 0)))
+            .edge(Some((Color::Rgb(255, 0, 0), 3.0)))
+            .rectangle(Point::from_pixel(0, 0), Point::from_pixel(30000, 30000));
+        let (graphic, cgm) = builder.build(RowColumn::default(), 1, 0).unwrap();
+
+        let mut nitf = Nitf::default();
+        nitf.add_im(seg);
+        nitf.add_sy(graphic);
+        let mut file = Cursor::new(vec![]);
+        nitf.write_headers(&mut file).unwrap();
+        nitf.image_segments[0].write_data(&mut file, &data).unwrap();
+        nitf.graphic_segments[0]
+            .write_data(&mut file, &cgm)
+            .unwrap();
+        file.set_position(0);
+        let nitf = Nitf::from_reader(&mut file).unwrap();
+
+        // Crosses the second block row and column of the image
+        let upper_left = RowColumn::new(9, 22);
+        let raster = nitf.render_region(&mut file, upper_left, 4, 4).unwrap();
+        for row in 9..13 {
+            for col in 22..26 {
+                let expected = match row {
+                    9 => [255, 0, 0],
+                    _ => [((row - 10) * 16 + col - 20) as u8; 3],
+                };
+                let location = RowColumn::new(row, col);
+                assert_eq!(raster.get(location), Some(expected), "{location:?}");
+            }
+        }
+        // Regions away from the image only draw the clipped graphic
+        let upper_left = RowColumn::new(20000, 20000);
+        let raster = nitf.render_region(&mut file, upper_left, 2, 2).unwrap();
+        assert_eq!(raster.data, [255, 0, 0].repeat(4));
+    }
+}
@@ -0,0 +1,239 @@
+//! STREAMING_FILE_HEADER data extension segments
+//!
+//! Streaming producers write a placeholder file header, followed by the
+//! segments, and put the real file header in a `STREAMING_FILE_HEADER` DES at
+//! the end of the file. The DES data is
+//!
+//! | Field      | Length |                             |
+//! |------------|--------|-----------------------------|
+//! | SFH_L1     | 7      | Length of SFH_DR            |
+//! | SFH_DELIM1 | 4      | `0x0A6E1D97`                |
+//! | SFH_DR     | SFH_L1 | Real file header            |
+//! | SFH_DELIM2 | 4      | `0x0ECA14BF`                |
+//! | SFH_L2     | 7      | Length of SFH_DR, again     |
+//!
+//! so it can be found from the end of the file without trusting the
+//! placeholder.
+use log::debug;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+use crate::headers::file_hdr::Segment;
+use crate::headers::{NitfHeader, NitfSegmentHeader};
+use crate::{DataExtensionSegment, Nitf, NitfError, NitfResult};
+
+/// DESID of streaming file header segments
+const STREAMING_FILE_HEADER: &str = "STREAMING_FILE_HEADER";
+
+/// File length written in a placeholder header
+const PLACEHOLDER_FL: u64 = 999_999_999_999;
+
+const SFH_DELIM1: [u8; 4] = [0x0A, 0x6E, 0x1D, 0x97];
+const SFH_DELIM2: [u8; 4] = [0x0E, 0xCA, 0x14, 0xBF];
+/// Length of the SFH_L1 and SFH_L2 fields
+const SFH_L_LENGTH: usize = 7;
+
+/// Length of the DES data holding a header of `length` bytes
+fn sfh_length(length: usize) -> usize {
+    2 * SFH_L_LENGTH + SFH_DELIM1.len() + length + SFH_DELIM2.len()
+}
+
+/// Read an SFH_L field at `offset`. `None` if it is not a number
+fn read_sfh_length(reader: &mut (impl Read + Seek), offset: usize) -> NitfResult<Option<usize>> {
+    let mut bytes = [0; SFH_L_LENGTH];
+    reader.seek(SeekFrom::Start(offset as u64))?;
+    reader.read_exact(&mut bytes)?;
+    Ok(std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|s| s.parse().ok()))
+}
+
+/// Read the header stored at the end of a streaming file, if there is one.
+fn read_streaming_header(reader: &mut (impl Read + Seek)) -> NitfResult<Option<NitfHeader>> {
+    let end = reader.seek(SeekFrom::End(0))? as usize;
+    let Some(offset) = end.checked_sub(SFH_L_LENGTH) else {
+        return Ok(None);
+    };
+    let Some(sfh_l2) = read_sfh_length(reader, offset)? else {
+        return Ok(None);
+    };
+    let Some(start) = end.checked_sub(sfh_length(sfh_l2)) else {
+        return Ok(None);
+    };
+    if read_sfh_length(reader, start)? != Some(sfh_l2) {
+        return Ok(None);
+    }
+    let mut frame = vec![0; sfh_length(sfh_l2) - 2 * SFH_L_LENGTH];
+    reader.read_exact(&mut frame)?;
+    let (delim1, rest) = frame.split_at(SFH_DELIM1.len());
+    let (header, delim2) = rest.split_at(sfh_l2);
+    if delim1 != SFH_DELIM1 || delim2 != SFH_DELIM2 {
+        return Ok(None);
+    }
+    debug!("Found {STREAMING_FILE_HEADER} at offset {start}");
+    NitfHeader::from_reader(&mut Cursor::new(header)).map(Some)
+}
+
+impl Nitf {
+    /// Replace a placeholder file header with the real one from a
+    /// `STREAMING_FILE_HEADER` DES. The reader is returned to its position,
+    /// which should be the end of the placeholder.
+    ///
+    /// Only headers with the placeholder file length are replaced.
+    pub(crate) fn read_streaming_header(
+        &mut self,
+        reader: &mut (impl Read + Seek),
+    ) -> NitfResult<()> {
+        if self.nitf_header.fl.val != PLACEHOLDER_FL {
+            return Ok(());
+        }
+        let position = reader.stream_position()?;
+        let header = read_streaming_header(reader)?;
+        reader.seek(SeekFrom::Start(position))?;
+        if let Some(header) = header {
+            self.nitf_header = header;
+        }
+        Ok(())
+    }
+
+    /// Drop `STREAMING_FILE_HEADER` segments, whose contents have been applied
+    /// by [Nitf::read_streaming_header()]
+    pub(crate) fn remove_streaming_header(&mut self) {
+        let mut i_seg = 0;
+        while i_seg < self.data_extension_segments.len() {
+            if self.data_extension_segments[i_seg].header.desid.val.trim() == STREAMING_FILE_HEADER
+            {
+                self.data_extension_segments.remove(i_seg);
+                self.nitf_header.dextheaders.remove(i_seg);
+                self.nitf_header.numdes.val -= 1;
+            } else {
+                i_seg += 1;
+            }
+        }
+    }
+
+    /// Write the header information for all segments as a streaming file.
+    ///
+    /// The file header is written with an unknown file length, and the real
+    /// header is written in a `STREAMING_FILE_HEADER` DES after the end of the
+    /// segment data. Segment data is written afterwards with
+    /// [NitfSegment::write_data()](crate::types::NitfSegment::write_data) as
+    /// usual.
+    pub fn write_streaming_headers(
+        &mut self,
+        writer: &mut (impl Write + Seek),
+    ) -> NitfResult<usize> {
+        self.update_headers()?;
+        let mut bytes_written = self.write_headers_with_length(writer, PLACEHOLDER_FL)?;
+        let file_length = self.length();
+        self.nitf_header.fl.val = file_length as u64;
+
+        let mut des = DataExtensionSegment::default();
+        des.header.desid.val = STREAMING_FILE_HEADER.to_string();
+        des.header.desver.val = 1;
+        des.header.security = self.nitf_header.security.clone();
+        let des_header_length = des.header.length();
+
+        // The real header describes the file including this segment
+        let mut header = self.nitf_header.clone();
+        header.add_subheader(Segment::DataExtension, des_header_length as u32, 0);
+        let data_length = sfh_length(header.length());
+        if let Some(info) = header.dextheaders.last_mut() {
+            info.item_size.val = data_length as u64;
+        }
+        let mut header_bytes = Cursor::new(vec![]);
+        header.write_header(
+            &mut header_bytes,
+            (file_length + des_header_length + data_length) as u64,
+        )?;
+        let header_bytes = header_bytes.into_inner();
+        if sfh_length(header_bytes.len()) != data_length {
+            Err(NitfError::Fatal(format!(
+                "{STREAMING_FILE_HEADER} data is {} bytes, expected {data_length}",
+                sfh_length(header_bytes.len()),
+            )))?
+        }
+
+        let sfh_l = format!("{:0width$}", header_bytes.len(), width = SFH_L_LENGTH);
+        let mut data = Vec::with_capacity(data_length);
+        data.extend_from_slice(sfh_l.as_bytes());
+        data.extend_from_slice(&SFH_DELIM1);
+        data.extend_from_slice(&header_bytes);
+        data.extend_from_slice(&SFH_DELIM2);
+        data.extend_from_slice(sfh_l.as_bytes());
+
+        writer.seek(SeekFrom::Start(file_length as u64))?;
+        bytes_written += des.header.write(writer)?;
+        writer.write_all(&data)?;
+        bytes_written += data.len();
+        Ok(bytes_written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::text_hdr::TextFormat;
+    use crate::TextSegment;
+
+    #[test]
+    fn streaming_round_trip() {
+        let texts = ["FIRST TEXT", "SECOND, LONGER TEXT"];
+        let mut nitf = Nitf::default();
+        let mut data = vec![];
+        for text in texts {
+            let (seg, bytes) = TextSegment::from_text(text, TextFormat::STA).unwrap();
+            nitf.add_te(seg);
+            data.push(bytes);
+        }
+        let mut file = Cursor::new(vec![]);
+        nitf.write_streaming_headers(&mut file).unwrap();
+        for (seg, bytes) in nitf.text_segments.iter_mut().zip(&data) {
+            seg.write_data(&mut file, bytes).unwrap();
+        }
+        let file_length = file.get_ref().len() as u64;
+        // The file starts with the placeholder
+        file.set_position(0);
+        let mut placeholder = NitfHeader::default();
+        placeholder.read(&mut file).unwrap();
+        assert_eq!(placeholder.fl.val, PLACEHOLDER_FL);
+
+        file.set_position(0);
+        let read = Nitf::from_reader(&mut file).unwrap();
+        let header = &read.nitf_header;
+        assert_eq!(header.fl.val, file_length);
+        assert_eq!(header.numt.val, 2);
+        assert_eq!(header.numdes.val, 0);
+        assert!(read.data_extension_segments.is_empty());
+        for ((info, seg), text) in header
+            .textheaders
+            .iter()
+            .zip(&read.text_segments)
+            .zip(texts)
+        {
+            assert_eq!(info.item_size.val, text.len() as u64);
+            assert_eq!(seg.read_text(&mut file).unwrap().text, text);
+        }
+    }
+
+    #[test]
+    fn trailer_needs_placeholder() {
+        let mut nitf = Nitf::default();
+        let (seg, bytes) = TextSegment::from_text("TEXT", TextFormat::STA).unwrap();
+        nitf.add_te(seg);
+        let mut file = Cursor::new(vec![]);
+        nitf.write_streaming_headers(&mut file).unwrap();
+        nitf.text_segments[0].write_data(&mut file, &bytes).unwrap();
+
+        // A header with a real file length is kept, even with a trailer
+        let mut data = file.into_inner();
+        let placeholder = PLACEHOLDER_FL.to_string();
+        let fl = data
+            .windows(placeholder.len())
+            .position(|w| w == placeholder.as_bytes())
+            .unwrap();
+        data[fl..fl + placeholder.len()].copy_from_slice(b"000000001234");
+        let read = Nitf::from_reader(&mut Cursor::new(data)).unwrap();
+        assert_eq!(read.nitf_header.fl.val, 1234);
+        assert_eq!(read.nitf_header.numt.val, 1);
+    }
+}
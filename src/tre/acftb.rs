@@ -0,0 +1,200 @@
+//! ACFTB aircraft information TRE
+use std::io::{Read, Seek, Write};
+
+use crate::tre::{
+    read_optional, read_string, write_optional_float, write_optional_int, write_string, Tre,
+};
+use crate::NitfResult;
+
+/// ACFTB TRE
+///
+/// Numeric fields are `None` when blank or filled with `-`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Acftb {
+    /// Aircraft Mission Identification
+    pub ac_msn_id: String,
+    /// Aircraft Tail Number
+    pub ac_tail_no: String,
+    /// Aircraft Take-off, `CCYYMMDDhhmm`
+    pub ac_to: String,
+    /// Sensor ID Type
+    pub sensor_id_type: String,
+    /// Sensor ID
+    pub sensor_id: String,
+    /// Scene Source
+    pub scene_source: Option<u8>,
+    /// Scene Number
+    pub scnum: Option<u32>,
+    /// Processing Date, `CCYYMMDD`
+    pub pdate: String,
+    /// Immediate Scene Host
+    pub imhostno: Option<u32>,
+    /// Immediate Scene Request ID
+    pub imreqid: Option<u32>,
+    /// Mission Plan Mode
+    pub mplan: Option<u16>,
+    /// Entry Location
+    pub entloc: String,
+    /// Location Accuracy
+    pub loc_accy: Option<f64>,
+    /// Entry Elevation
+    pub entelv: Option<i32>,
+    /// Elevation Units, `f` or `m`
+    pub elv_unit: String,
+    /// Exit Location
+    pub exitloc: String,
+    /// Exit Elevation
+    pub exitelv: Option<i32>,
+    /// True Map Angle, degrees
+    pub tmap: Option<f64>,
+    /// Row Spacing
+    pub row_spacing: Option<f64>,
+    /// Row Spacing Units, `f`, `m`, `r`, or `u`
+    pub row_spacing_units: String,
+    /// Column Spacing
+    pub col_spacing: Option<f64>,
+    /// Column Spacing Units, `f`, `m`, `r`, or `u`
+    pub col_spacing_units: String,
+    /// Sensor Focal Length, cm
+    pub focal_length: Option<f64>,
+    /// Sensor Vendor's Serial Number
+    pub senserial: String,
+    /// Airborne Software Version
+    pub abswver: String,
+    /// Calibration Date, `CCYYMMDD`
+    pub cal_date: String,
+    /// Patch Total
+    pub patch_tot: Option<u16>,
+    /// MTI Total
+    pub mti_tot: Option<u16>,
+}
+
+impl Tre for Acftb {
+    const TAG: &'static str = "ACFTB";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.ac_msn_id = read_string(reader, 20, "AC_MSN_ID")?;
+        self.ac_tail_no = read_string(reader, 10, "AC_TAIL_NO")?;
+        self.ac_to = read_string(reader, 12, "AC_TO")?;
+        self.sensor_id_type = read_string(reader, 4, "SENSOR_ID_TYPE")?;
+        self.sensor_id = read_string(reader, 6, "SENSOR_ID")?;
+        self.scene_source = read_optional(reader, 1, "SCENE_SOURCE")?;
+        self.scnum = read_optional(reader, 6, "SCNUM")?;
+        self.pdate = read_string(reader, 8, "PDATE")?;
+        self.imhostno = read_optional(reader, 6, "IMHOSTNO")?;
+        self.imreqid = read_optional(reader, 5, "IMREQID")?;
+        self.mplan = read_optional(reader, 3, "MPLAN")?;
+        self.entloc = read_string(reader, 25, "ENTLOC")?;
+        self.loc_accy = read_optional(reader, 6, "LOC_ACCY")?;
+        self.entelv = read_optional(reader, 6, "ENTELV")?;
+        self.elv_unit = read_string(reader, 1, "ELV_UNIT")?;
+        self.exitloc = read_string(reader, 25, "EXITLOC")?;
+        self.exitelv = read_optional(reader, 6, "EXITELV")?;
+        self.tmap = read_optional(reader, 7, "TMAP")?;
+        self.row_spacing = read_optional(reader, 7, "ROW_SPACING")?;
+        self.row_spacing_units = read_string(reader, 1, "ROW_SPACING_UNITS")?;
+        self.col_spacing = read_optional(reader, 7, "COL_SPACING")?;
+        self.col_spacing_units = read_string(reader, 1, "COL_SPACING_UNITS")?;
+        self.focal_length = read_optional(reader, 6, "FOCAL_LENGTH")?;
+        self.senserial = read_string(reader, 6, "SENSERIAL")?;
+        self.abswver = read_string(reader, 7, "ABSWVER")?;
+        self.cal_date = read_string(reader, 8, "CAL_DATE")?;
+        self.patch_tot = read_optional(reader, 4, "PATCH_TOT")?;
+        self.mti_tot = read_optional(reader, 3, "MTI_TOT")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_string(writer, &self.ac_msn_id, 20, "AC_MSN_ID")?;
+        bytes_written += write_string(writer, &self.ac_tail_no, 10, "AC_TAIL_NO")?;
+        bytes_written += write_string(writer, &self.ac_to, 12, "AC_TO")?;
+        bytes_written += write_string(writer, &self.sensor_id_type, 4, "SENSOR_ID_TYPE")?;
+        bytes_written += write_string(writer, &self.sensor_id, 6, "SENSOR_ID")?;
+        bytes_written += write_optional_int(writer, self.scene_source, 1, "SCENE_SOURCE")?;
+        bytes_written += write_optional_int(writer, self.scnum, 6, "SCNUM")?;
+        bytes_written += write_string(writer, &self.pdate, 8, "PDATE")?;
+        bytes_written += write_optional_int(writer, self.imhostno, 6, "IMHOSTNO")?;
+        bytes_written += write_optional_int(writer, self.imreqid, 5, "IMREQID")?;
+        bytes_written += write_optional_int(writer, self.mplan, 3, "MPLAN")?;
+        bytes_written += write_string(writer, &self.entloc, 25, "ENTLOC")?;
+        bytes_written += write_optional_float(writer, self.loc_accy, 6, 2, false, "LOC_ACCY")?;
+        bytes_written += write_optional_int(writer, self.entelv, 6, "ENTELV")?;
+        bytes_written += write_string(writer, &self.elv_unit, 1, "ELV_UNIT")?;
+        bytes_written += write_string(writer, &self.exitloc, 25, "EXITLOC")?;
+        bytes_written += write_optional_int(writer, self.exitelv, 6, "EXITELV")?;
+        bytes_written += write_optional_float(writer, self.tmap, 7, 3, false, "TMAP")?;
+        bytes_written +=
+            write_optional_float(writer, self.row_spacing, 7, 3, false, "ROW_SPACING")?;
+        bytes_written += write_string(writer, &self.row_spacing_units, 1, "ROW_SPACING_UNITS")?;
+        bytes_written +=
+            write_optional_float(writer, self.col_spacing, 7, 3, false, "COL_SPACING")?;
+        bytes_written += write_string(writer, &self.col_spacing_units, 1, "COL_SPACING_UNITS")?;
+        bytes_written +=
+            write_optional_float(writer, self.focal_length, 6, 2, false, "FOCAL_LENGTH")?;
+        bytes_written += write_string(writer, &self.senserial, 6, "SENSERIAL")?;
+        bytes_written += write_string(writer, &self.abswver, 7, "ABSWVER")?;
+        bytes_written += write_string(writer, &self.cal_date, 8, "CAL_DATE")?;
+        bytes_written += write_optional_int(writer, self.patch_tot, 4, "PATCH_TOT")?;
+        bytes_written += write_optional_int(writer, self.mti_tot, 3, "MTI_TOT")?;
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        207
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tre::test_tre;
+
+    #[test]
+    fn acftb_round_trip() {
+        let (raw, written) = test_tre(
+            Acftb::TAG,
+            &[
+                ("MISSION", 20),
+                ("TAIL", 10),
+                ("BASE", 12),
+                ("EO", 4),
+                ("SENSOR", 6),
+                ("-", 1),
+                ("000123", 6),
+                ("20200101", 8),
+                ("", 6),
+                ("00001", 5),
+                ("---", 3),
+                ("", 25),
+                ("000.50", 6),
+                ("-00100", 6),
+                ("m", 1),
+                ("", 25),
+                ("------", 6),
+                ("012.500", 7),
+                ("", 7),
+                ("m", 1),
+                ("001.250", 7),
+                ("m", 1),
+                ("-----", 6),
+                ("SERIAL", 6),
+                ("1.0", 7),
+                ("20190101", 8),
+                ("0002", 4),
+                ("", 3),
+            ],
+        );
+        let acftb = Acftb::from_raw(&raw).unwrap();
+        assert_eq!(raw.cel, acftb.length());
+        assert_eq!(acftb.scene_source, None);
+        assert_eq!(acftb.scnum, Some(123));
+        assert_eq!(acftb.imhostno, None);
+        assert_eq!(acftb.mplan, None);
+        assert_eq!(acftb.loc_accy, Some(0.5));
+        assert_eq!(acftb.entelv, Some(-100));
+        assert_eq!(acftb.exitelv, None);
+        assert_eq!(acftb.row_spacing, None);
+        assert_eq!(acftb.col_spacing, Some(1.25));
+        assert_eq!(acftb.focal_length, None);
+        assert_eq!(acftb.mti_tot, None);
+        assert_eq!(acftb.to_raw().unwrap().data, written);
+    }
+}
@@ -0,0 +1,141 @@
+//! AIMIDB additional image identification TRE
+use std::io::{Read, Seek, Write};
+
+use crate::tre::{read_optional, read_string, write_optional_int, write_string, Tre};
+use crate::NitfResult;
+
+/// AIMIDB TRE
+///
+/// Numeric fields are `None` when blank or filled with `-`. Reserved fields
+/// are skipped when reading and written as spaces.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Aimidb {
+    /// Acquisition Date/Time, `CCYYMMDDhhmmss`
+    pub acquisition_date: String,
+    /// Mission Number
+    pub mission_no: String,
+    /// Name of the Mission
+    pub mission_identification: String,
+    /// Flight Number
+    pub flight_no: String,
+    /// Image Operation Number
+    pub op_num: Option<u16>,
+    /// Current Segment ID
+    pub current_segment: String,
+    /// Reprocess Number
+    pub repro_num: Option<u8>,
+    /// Replay
+    pub replay: String,
+    /// Starting Tile Column Number
+    pub start_tile_column: Option<u16>,
+    /// Starting Tile Row Number
+    pub start_tile_row: Option<u32>,
+    /// Ending Segment
+    pub end_segment: String,
+    /// Ending Tile Column Number
+    pub end_tile_column: Option<u16>,
+    /// Ending Tile Row Number
+    pub end_tile_row: Option<u32>,
+    /// Country Code
+    pub country: String,
+    /// Location, `ddmmXdddmmY`
+    pub location: String,
+}
+
+impl Tre for Aimidb {
+    const TAG: &'static str = "AIMIDB";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.acquisition_date = read_string(reader, 14, "ACQUISITION_DATE")?;
+        self.mission_no = read_string(reader, 4, "MISSION_NO")?;
+        self.mission_identification = read_string(reader, 10, "MISSION_IDENTIFICATION")?;
+        self.flight_no = read_string(reader, 2, "FLIGHT_NO")?;
+        self.op_num = read_optional(reader, 3, "OP_NUM")?;
+        self.current_segment = read_string(reader, 2, "CURRENT_SEGMENT")?;
+        self.repro_num = read_optional(reader, 2, "REPRO_NUM")?;
+        self.replay = read_string(reader, 3, "REPLAY")?;
+        read_string(reader, 1, "RESERVED")?;
+        self.start_tile_column = read_optional(reader, 3, "START_TILE_COLUMN")?;
+        self.start_tile_row = read_optional(reader, 5, "START_TILE_ROW")?;
+        self.end_segment = read_string(reader, 2, "END_SEGMENT")?;
+        self.end_tile_column = read_optional(reader, 3, "END_TILE_COLUMN")?;
+        self.end_tile_row = read_optional(reader, 5, "END_TILE_ROW")?;
+        self.country = read_string(reader, 2, "COUNTRY")?;
+        read_string(reader, 4, "RESERVED")?;
+        self.location = read_string(reader, 11, "LOCATION")?;
+        read_string(reader, 13, "RESERVED")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_string(writer, &self.acquisition_date, 14, "ACQUISITION_DATE")?;
+        bytes_written += write_string(writer, &self.mission_no, 4, "MISSION_NO")?;
+        bytes_written += write_string(
+            writer,
+            &self.mission_identification,
+            10,
+            "MISSION_IDENTIFICATION",
+        )?;
+        bytes_written += write_string(writer, &self.flight_no, 2, "FLIGHT_NO")?;
+        bytes_written += write_optional_int(writer, self.op_num, 3, "OP_NUM")?;
+        bytes_written += write_string(writer, &self.current_segment, 2, "CURRENT_SEGMENT")?;
+        bytes_written += write_optional_int(writer, self.repro_num, 2, "REPRO_NUM")?;
+        bytes_written += write_string(writer, &self.replay, 3, "REPLAY")?;
+        bytes_written += write_string(writer, "", 1, "RESERVED")?;
+        bytes_written +=
+            write_optional_int(writer, self.start_tile_column, 3, "START_TILE_COLUMN")?;
+        bytes_written += write_optional_int(writer, self.start_tile_row, 5, "START_TILE_ROW")?;
+        bytes_written += write_string(writer, &self.end_segment, 2, "END_SEGMENT")?;
+        bytes_written += write_optional_int(writer, self.end_tile_column, 3, "END_TILE_COLUMN")?;
+        bytes_written += write_optional_int(writer, self.end_tile_row, 5, "END_TILE_ROW")?;
+        bytes_written += write_string(writer, &self.country, 2, "COUNTRY")?;
+        bytes_written += write_string(writer, "", 4, "RESERVED")?;
+        bytes_written += write_string(writer, &self.location, 11, "LOCATION")?;
+        bytes_written += write_string(writer, "", 13, "RESERVED")?;
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        89
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tre::test_tre;
+
+    #[test]
+    fn aimidb_round_trip() {
+        let (raw, written) = test_tre(
+            Aimidb::TAG,
+            &[
+                ("20200101120000", 14),
+                ("0001", 4),
+                ("MISSION", 10),
+                ("01", 2),
+                ("123", 3),
+                ("AA", 2),
+                ("--", 2),
+                ("000", 3),
+                ("", 1),
+                ("", 3),
+                ("00012", 5),
+                ("AB", 2),
+                ("004", 3),
+                ("-----", 5),
+                ("US", 2),
+                ("", 4),
+                ("3000N11700W", 11),
+                ("", 13),
+            ],
+        );
+        let aimidb = Aimidb::from_raw(&raw).unwrap();
+        assert_eq!(raw.cel, aimidb.length());
+        assert_eq!(aimidb.op_num, Some(123));
+        assert_eq!(aimidb.repro_num, None);
+        assert_eq!(aimidb.start_tile_column, None);
+        assert_eq!(aimidb.start_tile_row, Some(12));
+        assert_eq!(aimidb.end_tile_column, Some(4));
+        assert_eq!(aimidb.end_tile_row, None);
+        assert_eq!(aimidb.to_raw().unwrap().data, written);
+    }
+}
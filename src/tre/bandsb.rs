@@ -0,0 +1,712 @@
+//! BANDSB general band parameters TRE
+//!
+//! Most fields are only present when the matching bit of the existence mask
+//! is set. Fields whose bit is not set are `None` or empty after reading and
+//! are skipped when writing. Bits are numbered with bit 31 as the most
+//! significant bit, as in the TRE definition.
+use std::io::{Read, Seek, Write};
+
+use crate::tre::{
+    read_optional, read_string, read_value, write_int, write_optional_int, write_optional_real,
+    write_string, Tre,
+};
+use crate::{NitfError, NitfResult};
+
+/// BANDSB TRE
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Bandsb {
+    /// Data Representation
+    pub radiometric_quantity: String,
+    /// Data Representation Unit
+    pub radiometric_quantity_unit: String,
+    /// Cube Scale Factor
+    pub scale_factor: f32,
+    /// Cube Additive Factor
+    pub additive_factor: f32,
+    /// Row Ground Sample Distance
+    pub row_gsd: Option<f64>,
+    /// Units of Row Ground Sample Distance
+    pub row_gsd_unit: String,
+    /// Column Ground Sample Distance
+    pub col_gsd: Option<f64>,
+    /// Units of Column Ground Sample Distance
+    pub col_gsd_unit: String,
+    /// Spatial Response Function, rows
+    pub spt_resp_row: Option<f64>,
+    /// Units of Spatial Response Function, rows
+    pub spt_resp_unit_row: String,
+    /// Spatial Response Function, columns
+    pub spt_resp_col: Option<f64>,
+    /// Units of Spatial Response Function, columns
+    pub spt_resp_unit_col: String,
+    /// Field reserved for future use
+    pub data_fld_1: String,
+    /// Bit-wise Existence Mask
+    pub existence_mask: u32,
+    /// Adjustment Surface (bit 31)
+    pub radiometric_adjustment_surface: String,
+    /// Adjustment Altitude Above WGS84 Ellipsoid (bit 31)
+    pub atmospheric_adjustment_altitude: f32,
+    /// Diameter of the lens (bit 30)
+    pub diameter: Option<f64>,
+    /// Field reserved for future use (bit 29)
+    pub data_fld_2: String,
+    /// Wave Length Units, `U` for micrometers or `W` for wavenumbers (any of
+    /// bits 24 to 19)
+    pub wave_length_unit: String,
+    /// Per band parameters, one for each band of the image
+    pub bands: Vec<BandsbBand>,
+    /// Auxiliary parameters with a value for each band (bit 0)
+    pub aux_band_parameters: Vec<AuxParameter>,
+    /// Auxiliary parameters with a single value for the cube (bit 0)
+    pub aux_cube_parameters: Vec<AuxParameter>,
+}
+
+/// BANDSB parameters of a single band
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BandsbBand {
+    /// Band Identifier (bit 28)
+    pub bandid: String,
+    /// Bad Band Flag, `1` if the band is valid (bit 27)
+    pub bad_band: Option<u8>,
+    /// NIIRS Value (bit 26)
+    pub niirs: Option<f64>,
+    /// Band Focal Length, centimeters (bit 25)
+    pub focal_len: Option<f64>,
+    /// Band Center Wavelength (bit 24)
+    pub cwave: Option<f64>,
+    /// Band Width, full width at half maximum (bit 23)
+    pub fwhm: Option<f64>,
+    /// Band Width Uncertainty (bit 22)
+    pub fwhm_unc: Option<f64>,
+    /// Nominal Wavelength (bit 21)
+    pub nom_wave: Option<f64>,
+    /// Nominal Wavelength Uncertainty (bit 21)
+    pub nom_wave_unc: Option<f64>,
+    /// Lower Wavelength Bound (bit 20)
+    pub lbound: Option<f64>,
+    /// Upper Wavelength Bound (bit 20)
+    pub ubound: Option<f64>,
+    /// Band Scale Factor (bit 19)
+    pub scale_factor: f32,
+    /// Band Additive Factor (bit 19)
+    pub additive_factor: f32,
+    /// Start Time, `YYYYMMDDhhmmss.s` (bit 18)
+    pub start_time: String,
+    /// Integration Time, milliseconds (bit 17)
+    pub int_time: Option<f64>,
+    /// Calibration Dark Current (bit 16)
+    pub caldrk: Option<f64>,
+    /// Calibration Sensitivity (bit 16)
+    pub calibration_sensitivity: Option<f64>,
+    /// Row Ground Sample Distance (bit 15)
+    pub row_gsd: Option<f64>,
+    /// Row Ground Sample Distance Uncertainty (bit 14)
+    pub row_gsd_unc: Option<f64>,
+    /// Units of Row Ground Sample Distance (bit 15)
+    pub row_gsd_unit: String,
+    /// Column Ground Sample Distance (bit 15)
+    pub col_gsd: Option<f64>,
+    /// Column Ground Sample Distance Uncertainty (bit 14)
+    pub col_gsd_unc: Option<f64>,
+    /// Units of Column Ground Sample Distance (bit 15)
+    pub col_gsd_unit: String,
+    /// Background Noise (bit 13)
+    pub bknoise: Option<f64>,
+    /// Scene Noise (bit 13)
+    pub scnnoise: Option<f64>,
+    /// Spatial Response Function, rows (bit 12)
+    pub spt_resp_function_row: Option<f64>,
+    /// Spatial Response Function Uncertainty, rows (bit 11)
+    pub spt_respunc_row: Option<f64>,
+    /// Units of Spatial Response Function, rows (bit 12)
+    pub spt_resp_unit_row: String,
+    /// Spatial Response Function, columns (bit 12)
+    pub spt_resp_function_col: Option<f64>,
+    /// Spatial Response Function Uncertainty, columns (bit 11)
+    pub spt_respunc_col: Option<f64>,
+    /// Units of Spatial Response Function, columns (bit 12)
+    pub spt_resp_unit_col: String,
+    /// Field reserved for future use (bit 10)
+    pub data_fld_3: String,
+    /// Field reserved for future use (bit 9)
+    pub data_fld_4: String,
+    /// Field reserved for future use (bit 8)
+    pub data_fld_5: String,
+    /// Field reserved for future use (bit 7)
+    pub data_fld_6: String,
+}
+
+/// BANDSB auxiliary parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuxParameter {
+    /// Unit of the parameter
+    pub unit: String,
+    /// Parameter values. Band parameters have one value for each band, and
+    /// cube parameters have a single value.
+    pub values: AuxValues,
+}
+
+/// Values of a BANDSB auxiliary parameter
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuxValues {
+    /// `I` format, 10 digit integers
+    Integer(Vec<Option<i64>>),
+    /// `R` format, 32-bit floats
+    Real(Vec<f32>),
+    /// `A` format, 20 character strings
+    Ascii(Vec<String>),
+}
+
+impl AuxValues {
+    fn len(&self) -> usize {
+        match self {
+            Self::Integer(values) => values.len(),
+            Self::Real(values) => values.len(),
+            Self::Ascii(values) => values.len(),
+        }
+    }
+    /// Format flag, `I`, `R`, or `A`
+    pub fn format(&self) -> &'static str {
+        match self {
+            Self::Integer(_) => "I",
+            Self::Real(_) => "R",
+            Self::Ascii(_) => "A",
+        }
+    }
+    fn value_length(&self) -> usize {
+        match self {
+            Self::Integer(_) => 10,
+            Self::Real(_) => 4,
+            Self::Ascii(_) => 20,
+        }
+    }
+    fn read(reader: &mut impl Read, format: &str, count: usize) -> NitfResult<Self> {
+        match format {
+            "I" => (0..count)
+                .map(|_| read_optional(reader, 10, "APN"))
+                .collect::<NitfResult<_>>()
+                .map(Self::Integer),
+            "R" => (0..count)
+                .map(|_| read_f32(reader, "APR"))
+                .collect::<NitfResult<_>>()
+                .map(Self::Real),
+            "A" => (0..count)
+                .map(|_| read_string(reader, 20, "APA"))
+                .collect::<NitfResult<_>>()
+                .map(Self::Ascii),
+            _ => Err(NitfError::ParseError(format!(
+                "BANDSB auxiliary parameter format {format:?}"
+            ))),
+        }
+    }
+    fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        match self {
+            Self::Integer(values) => {
+                for value in values {
+                    bytes_written += write_optional_int(writer, *value, 10, "APN")?;
+                }
+            }
+            Self::Real(values) => {
+                for value in values {
+                    bytes_written += write_f32(writer, *value)?;
+                }
+            }
+            Self::Ascii(values) => {
+                for value in values {
+                    bytes_written += write_string(writer, value, 20, "APA")?;
+                }
+            }
+        }
+        Ok(bytes_written)
+    }
+}
+
+/// Read a big endian IEEE 754 float
+fn read_f32(reader: &mut impl Read, name: &str) -> NitfResult<f32> {
+    let mut bytes = [0; 4];
+    reader
+        .read_exact(&mut bytes)
+        .or(Err(NitfError::ReadFatal(name.to_string())))?;
+    Ok(f32::from_be_bytes(bytes))
+}
+
+fn write_f32(writer: &mut impl Write, value: f32) -> NitfResult<usize> {
+    writer.write_all(&value.to_be_bytes())?;
+    Ok(4)
+}
+
+impl Bandsb {
+    /// Whether bit `n` of the existence mask is set
+    pub fn has(&self, n: u32) -> bool {
+        self.existence_mask & (1 << n) != 0
+    }
+
+    /// Whether WAVE_LENGTH_UNIT is present, which is when any of the
+    /// wavelength bits 24 to 19 is set
+    pub fn has_wave_length_unit(&self) -> bool {
+        self.existence_mask & 0x01F8_0000 != 0
+    }
+
+    fn read_band(&self, reader: &mut impl Read) -> NitfResult<BandsbBand> {
+        let mut band = BandsbBand::default();
+        if self.has(28) {
+            band.bandid = read_string(reader, 50, "BANDID")?;
+        }
+        if self.has(27) {
+            band.bad_band = read_optional(reader, 1, "BAD_BAND")?;
+        }
+        if self.has(26) {
+            band.niirs = read_optional(reader, 3, "NIIRS")?;
+        }
+        if self.has(25) {
+            band.focal_len = read_optional(reader, 5, "FOCAL_LEN")?;
+        }
+        if self.has(24) {
+            band.cwave = read_optional(reader, 7, "CWAVE")?;
+        }
+        if self.has(23) {
+            band.fwhm = read_optional(reader, 7, "FWHM")?;
+        }
+        if self.has(22) {
+            band.fwhm_unc = read_optional(reader, 7, "FWHM_UNC")?;
+        }
+        if self.has(21) {
+            band.nom_wave = read_optional(reader, 7, "NOM_WAVE")?;
+            band.nom_wave_unc = read_optional(reader, 7, "NOM_WAVE_UNC")?;
+        }
+        if self.has(20) {
+            band.lbound = read_optional(reader, 7, "LBOUND")?;
+            band.ubound = read_optional(reader, 7, "UBOUND")?;
+        }
+        if self.has(19) {
+            band.scale_factor = read_f32(reader, "SCALE_FACTOR")?;
+            band.additive_factor = read_f32(reader, "ADDITIVE_FACTOR")?;
+        }
+        if self.has(18) {
+            band.start_time = read_string(reader, 16, "START_TIME")?;
+        }
+        if self.has(17) {
+            band.int_time = read_optional(reader, 6, "INT_TIME")?;
+        }
+        if self.has(16) {
+            band.caldrk = read_optional(reader, 6, "CALDRK")?;
+            band.calibration_sensitivity = read_optional(reader, 5, "CALIBRATION_SENSITIVITY")?;
+        }
+        if self.has(15) {
+            band.row_gsd = read_optional(reader, 7, "ROW_GSD")?;
+            if self.has(14) {
+                band.row_gsd_unc = read_optional(reader, 7, "ROW_GSD_UNC")?;
+            }
+            band.row_gsd_unit = read_string(reader, 1, "ROW_GSD_UNIT")?;
+            band.col_gsd = read_optional(reader, 7, "COL_GSD")?;
+            if self.has(14) {
+                band.col_gsd_unc = read_optional(reader, 7, "COL_GSD_UNC")?;
+            }
+            band.col_gsd_unit = read_string(reader, 1, "COL_GSD_UNIT")?;
+        }
+        if self.has(13) {
+            band.bknoise = read_optional(reader, 5, "BKNOISE")?;
+            band.scnnoise = read_optional(reader, 5, "SCNNOISE")?;
+        }
+        if self.has(12) {
+            band.spt_resp_function_row = read_optional(reader, 7, "SPT_RESP_FUNCTION_ROW")?;
+            if self.has(11) {
+                band.spt_respunc_row = read_optional(reader, 7, "SPT_RESPUNC_ROW")?;
+            }
+            band.spt_resp_unit_row = read_string(reader, 1, "SPT_RESP_UNIT_ROW")?;
+            band.spt_resp_function_col = read_optional(reader, 7, "SPT_RESP_FUNCTION_COL")?;
+            if self.has(11) {
+                band.spt_respunc_col = read_optional(reader, 7, "SPT_RESPUNC_COL")?;
+            }
+            band.spt_resp_unit_col = read_string(reader, 1, "SPT_RESP_UNIT_COL")?;
+        }
+        if self.has(10) {
+            band.data_fld_3 = read_string(reader, 16, "DATA_FLD_3")?;
+        }
+        if self.has(9) {
+            band.data_fld_4 = read_string(reader, 24, "DATA_FLD_4")?;
+        }
+        if self.has(8) {
+            band.data_fld_5 = read_string(reader, 32, "DATA_FLD_5")?;
+        }
+        if self.has(7) {
+            band.data_fld_6 = read_string(reader, 48, "DATA_FLD_6")?;
+        }
+        Ok(band)
+    }
+
+    fn write_band(&self, writer: &mut impl Write, band: &BandsbBand) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        if self.has(28) {
+            bytes_written += write_string(writer, &band.bandid, 50, "BANDID")?;
+        }
+        if self.has(27) {
+            bytes_written += write_optional_int(writer, band.bad_band, 1, "BAD_BAND")?;
+        }
+        if self.has(26) {
+            bytes_written += write_optional_real(writer, band.niirs, 3, "NIIRS")?;
+        }
+        if self.has(25) {
+            bytes_written += write_optional_real(writer, band.focal_len, 5, "FOCAL_LEN")?;
+        }
+        if self.has(24) {
+            bytes_written += write_optional_real(writer, band.cwave, 7, "CWAVE")?;
+        }
+        if self.has(23) {
+            bytes_written += write_optional_real(writer, band.fwhm, 7, "FWHM")?;
+        }
+        if self.has(22) {
+            bytes_written += write_optional_real(writer, band.fwhm_unc, 7, "FWHM_UNC")?;
+        }
+        if self.has(21) {
+            bytes_written += write_optional_real(writer, band.nom_wave, 7, "NOM_WAVE")?;
+            bytes_written += write_optional_real(writer, band.nom_wave_unc, 7, "NOM_WAVE_UNC")?;
+        }
+        if self.has(20) {
+            bytes_written += write_optional_real(writer, band.lbound, 7, "LBOUND")?;
+            bytes_written += write_optional_real(writer, band.ubound, 7, "UBOUND")?;
+        }
+        if self.has(19) {
+            bytes_written += write_f32(writer, band.scale_factor)?;
+            bytes_written += write_f32(writer, band.additive_factor)?;
+        }
+        if self.has(18) {
+            bytes_written += write_string(writer, &band.start_time, 16, "START_TIME")?;
+        }
+        if self.has(17) {
+            bytes_written += write_optional_real(writer, band.int_time, 6, "INT_TIME")?;
+        }
+        if self.has(16) {
+            bytes_written += write_optional_real(writer, band.caldrk, 6, "CALDRK")?;
+            bytes_written += write_optional_real(
+                writer,
+                band.calibration_sensitivity,
+                5,
+                "CALIBRATION_SENSITIVITY",
+            )?;
+        }
+        if self.has(15) {
+            bytes_written += write_optional_real(writer, band.row_gsd, 7, "ROW_GSD")?;
+            if self.has(14) {
+                bytes_written += write_optional_real(writer, band.row_gsd_unc, 7, "ROW_GSD_UNC")?;
+            }
+            bytes_written += write_string(writer, &band.row_gsd_unit, 1, "ROW_GSD_UNIT")?;
+            bytes_written += write_optional_real(writer, band.col_gsd, 7, "COL_GSD")?;
+            if self.has(14) {
+                bytes_written += write_optional_real(writer, band.col_gsd_unc, 7, "COL_GSD_UNC")?;
+            }
+            bytes_written += write_string(writer, &band.col_gsd_unit, 1, "COL_GSD_UNIT")?;
+        }
+        if self.has(13) {
+            bytes_written += write_optional_real(writer, band.bknoise, 5, "BKNOISE")?;
+            bytes_written += write_optional_real(writer, band.scnnoise, 5, "SCNNOISE")?;
+        }
+        if self.has(12) {
+            bytes_written += write_optional_real(
+                writer,
+                band.spt_resp_function_row,
+                7,
+                "SPT_RESP_FUNCTION_ROW",
+            )?;
+            if self.has(11) {
+                bytes_written +=
+                    write_optional_real(writer, band.spt_respunc_row, 7, "SPT_RESPUNC_ROW")?;
+            }
+            bytes_written += write_string(writer, &band.spt_resp_unit_row, 1, "SPT_RESP_UNIT_ROW")?;
+            bytes_written += write_optional_real(
+                writer,
+                band.spt_resp_function_col,
+                7,
+                "SPT_RESP_FUNCTION_COL",
+            )?;
+            if self.has(11) {
+                bytes_written +=
+                    write_optional_real(writer, band.spt_respunc_col, 7, "SPT_RESPUNC_COL")?;
+            }
+            bytes_written += write_string(writer, &band.spt_resp_unit_col, 1, "SPT_RESP_UNIT_COL")?;
+        }
+        if self.has(10) {
+            bytes_written += write_string(writer, &band.data_fld_3, 16, "DATA_FLD_3")?;
+        }
+        if self.has(9) {
+            bytes_written += write_string(writer, &band.data_fld_4, 24, "DATA_FLD_4")?;
+        }
+        if self.has(8) {
+            bytes_written += write_string(writer, &band.data_fld_5, 32, "DATA_FLD_5")?;
+        }
+        if self.has(7) {
+            bytes_written += write_string(writer, &band.data_fld_6, 48, "DATA_FLD_6")?;
+        }
+        Ok(bytes_written)
+    }
+
+    /// Length of the fields repeated for each band
+    fn band_length(&self) -> usize {
+        let fields = [
+            (28, 50),
+            (27, 1),
+            (26, 3),
+            (25, 5),
+            (24, 7),
+            (23, 7),
+            (22, 7),
+            (21, 14),
+            (20, 14),
+            (19, 8),
+            (18, 16),
+            (17, 6),
+            (16, 11),
+            (15, 16),
+            (13, 10),
+            (12, 16),
+            (10, 16),
+            (9, 24),
+            (8, 32),
+            (7, 48),
+        ];
+        let mut length = fields
+            .iter()
+            .filter(|(bit, _)| self.has(*bit))
+            .map(|(_, len)| len)
+            .sum();
+        if self.has(15) && self.has(14) {
+            length += 14;
+        }
+        if self.has(12) && self.has(11) {
+            length += 14;
+        }
+        length
+    }
+}
+
+impl Tre for Bandsb {
+    const TAG: &'static str = "BANDSB";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        let count: usize = read_value(reader, 5, "COUNT")?;
+        self.radiometric_quantity = read_string(reader, 24, "RADIOMETRIC_QUANTITY")?;
+        self.radiometric_quantity_unit = read_string(reader, 1, "RADIOMETRIC_QUANTITY_UNIT")?;
+        self.scale_factor = read_f32(reader, "SCALE_FACTOR")?;
+        self.additive_factor = read_f32(reader, "ADDITIVE_FACTOR")?;
+        self.row_gsd = read_optional(reader, 7, "ROW_GSD")?;
+        self.row_gsd_unit = read_string(reader, 1, "ROW_GSD_UNIT")?;
+        self.col_gsd = read_optional(reader, 7, "COL_GSD")?;
+        self.col_gsd_unit = read_string(reader, 1, "COL_GSD_UNIT")?;
+        self.spt_resp_row = read_optional(reader, 7, "SPT_RESP_ROW")?;
+        self.spt_resp_unit_row = read_string(reader, 1, "SPT_RESP_UNIT_ROW")?;
+        self.spt_resp_col = read_optional(reader, 7, "SPT_RESP_COL")?;
+        self.spt_resp_unit_col = read_string(reader, 1, "SPT_RESP_UNIT_COL")?;
+        self.data_fld_1 = read_string(reader, 48, "DATA_FLD_1")?;
+        let mut mask = [0; 4];
+        reader
+            .read_exact(&mut mask)
+            .or(Err(NitfError::ReadFatal("EXISTENCE_MASK".to_string())))?;
+        self.existence_mask = u32::from_be_bytes(mask);
+        if self.has(31) {
+            self.radiometric_adjustment_surface =
+                read_string(reader, 24, "RADIOMETRIC_ADJUSTMENT_SURFACE")?;
+            self.atmospheric_adjustment_altitude =
+                read_f32(reader, "ATMOSPHERIC_ADJUSTMENT_ALTITUDE")?;
+        }
+        if self.has(30) {
+            self.diameter = read_optional(reader, 7, "DIAMETER")?;
+        }
+        if self.has(29) {
+            self.data_fld_2 = read_string(reader, 32, "DATA_FLD_2")?;
+        }
+        if self.has_wave_length_unit() {
+            self.wave_length_unit = read_string(reader, 1, "WAVE_LENGTH_UNIT")?;
+        }
+        self.bands = (0..count)
+            .map(|_| self.read_band(reader))
+            .collect::<NitfResult<_>>()?;
+        self.aux_band_parameters.clear();
+        self.aux_cube_parameters.clear();
+        if self.has(0) {
+            let num_aux_b: usize = read_value(reader, 2, "NUM_AUX_B")?;
+            let num_aux_c: usize = read_value(reader, 2, "NUM_AUX_C")?;
+            for _ in 0..num_aux_b {
+                let format = read_string(reader, 1, "BAPF")?;
+                let unit = read_string(reader, 7, "UBAP")?;
+                let values = AuxValues::read(reader, &format, count)?;
+                self.aux_band_parameters.push(AuxParameter { unit, values });
+            }
+            for _ in 0..num_aux_c {
+                let format = read_string(reader, 1, "CAPF")?;
+                let unit = read_string(reader, 7, "UCAP")?;
+                let values = AuxValues::read(reader, &format, 1)?;
+                self.aux_cube_parameters.push(AuxParameter { unit, values });
+            }
+        }
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_int(writer, self.bands.len() as i64, 5, "COUNT")?;
+        bytes_written += write_string(
+            writer,
+            &self.radiometric_quantity,
+            24,
+            "RADIOMETRIC_QUANTITY",
+        )?;
+        bytes_written += write_string(
+            writer,
+            &self.radiometric_quantity_unit,
+            1,
+            "RADIOMETRIC_QUANTITY_UNIT",
+        )?;
+        bytes_written += write_f32(writer, self.scale_factor)?;
+        bytes_written += write_f32(writer, self.additive_factor)?;
+        bytes_written += write_optional_real(writer, self.row_gsd, 7, "ROW_GSD")?;
+        bytes_written += write_string(writer, &self.row_gsd_unit, 1, "ROW_GSD_UNIT")?;
+        bytes_written += write_optional_real(writer, self.col_gsd, 7, "COL_GSD")?;
+        bytes_written += write_string(writer, &self.col_gsd_unit, 1, "COL_GSD_UNIT")?;
+        bytes_written += write_optional_real(writer, self.spt_resp_row, 7, "SPT_RESP_ROW")?;
+        bytes_written += write_string(writer, &self.spt_resp_unit_row, 1, "SPT_RESP_UNIT_ROW")?;
+        bytes_written += write_optional_real(writer, self.spt_resp_col, 7, "SPT_RESP_COL")?;
+        bytes_written += write_string(writer, &self.spt_resp_unit_col, 1, "SPT_RESP_UNIT_COL")?;
+        bytes_written += write_string(writer, &self.data_fld_1, 48, "DATA_FLD_1")?;
+        writer.write_all(&self.existence_mask.to_be_bytes())?;
+        bytes_written += 4;
+        if self.has(31) {
+            bytes_written += write_string(
+                writer,
+                &self.radiometric_adjustment_surface,
+                24,
+                "RADIOMETRIC_ADJUSTMENT_SURFACE",
+            )?;
+            bytes_written += write_f32(writer, self.atmospheric_adjustment_altitude)?;
+        }
+        if self.has(30) {
+            bytes_written += write_optional_real(writer, self.diameter, 7, "DIAMETER")?;
+        }
+        if self.has(29) {
+            bytes_written += write_string(writer, &self.data_fld_2, 32, "DATA_FLD_2")?;
+        }
+        if self.has_wave_length_unit() {
+            bytes_written += write_string(writer, &self.wave_length_unit, 1, "WAVE_LENGTH_UNIT")?;
+        }
+        for band in &self.bands {
+            bytes_written += self.write_band(writer, band)?;
+        }
+        if self.has(0) {
+            let num_aux_b = self.aux_band_parameters.len() as i64;
+            let num_aux_c = self.aux_cube_parameters.len() as i64;
+            bytes_written += write_int(writer, num_aux_b, 2, "NUM_AUX_B")?;
+            bytes_written += write_int(writer, num_aux_c, 2, "NUM_AUX_C")?;
+            for param in &self.aux_band_parameters {
+                if param.values.len() != self.bands.len() {
+                    Err(NitfError::TreError(format!(
+                        "BANDSB auxiliary band parameter has {} values for {} bands",
+                        param.values.len(),
+                        self.bands.len()
+                    )))?
+                }
+                bytes_written += write_string(writer, param.values.format(), 1, "BAPF")?;
+                bytes_written += write_string(writer, &param.unit, 7, "UBAP")?;
+                bytes_written += param.values.write(writer)?;
+            }
+            for param in &self.aux_cube_parameters {
+                if param.values.len() != 1 {
+                    Err(NitfError::TreError(
+                        "BANDSB auxiliary cube parameter must have one value".to_string(),
+                    ))?
+                }
+                bytes_written += write_string(writer, param.values.format(), 1, "CAPF")?;
+                bytes_written += write_string(writer, &param.unit, 7, "UCAP")?;
+                bytes_written += param.values.write(writer)?;
+            }
+        }
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        let mut length = 5 + 24 + 1 + 4 + 4 + 4 * 8 + 48 + 4;
+        if self.has(31) {
+            length += 28;
+        }
+        if self.has(30) {
+            length += 7;
+        }
+        if self.has(29) {
+            length += 32;
+        }
+        if self.has_wave_length_unit() {
+            length += 1;
+        }
+        length += self.bands.len() * self.band_length();
+        if self.has(0) {
+            length += 4;
+            for param in self
+                .aux_band_parameters
+                .iter()
+                .chain(&self.aux_cube_parameters)
+            {
+                length += 8 + param.values.len() * param.values.value_length();
+            }
+        }
+        length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synthetic(existence_mask: u32) -> Bandsb {
+        let band = |cwave: f64| BandsbBand {
+            bandid: format!("BAND {cwave}"),
+            cwave: Some(cwave),
+            ..Default::default()
+        };
+        Bandsb {
+            radiometric_quantity: "RADIANCE".to_string(),
+            radiometric_quantity_unit: "S".to_string(),
+            scale_factor: 1.0,
+            additive_factor: 0.0,
+            row_gsd: Some(1.5),
+            row_gsd_unit: "M".to_string(),
+            col_gsd: Some(2.25),
+            col_gsd_unit: "M".to_string(),
+            existence_mask,
+            wave_length_unit: "U".to_string(),
+            bands: vec![band(0.45), band(0.55)],
+            aux_band_parameters: vec![AuxParameter {
+                unit: "K".to_string(),
+                values: AuxValues::Integer(vec![Some(290), None]),
+            }],
+            aux_cube_parameters: vec![AuxParameter {
+                unit: "DEG".to_string(),
+                values: AuxValues::Ascii(vec!["NADIR".to_string()]),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bandsb_round_trip() {
+        // BANDID, CWAVE, and the auxiliary parameters. CWAVE alone makes
+        // WAVE_LENGTH_UNIT present.
+        let bandsb = synthetic(1 << 28 | 1 << 24 | 1);
+        assert!(bandsb.has_wave_length_unit());
+        let raw = bandsb.to_raw().unwrap();
+        assert_eq!(raw.cel, bandsb.length());
+        let decoded = Bandsb::from_raw(&raw).unwrap();
+        assert_eq!(decoded, bandsb);
+        assert_eq!(decoded.to_raw().unwrap().data, raw.data);
+    }
+
+    #[test]
+    fn no_wave_length_unit() {
+        let mut bandsb = synthetic(1 << 28);
+        bandsb.aux_band_parameters.clear();
+        bandsb.aux_cube_parameters.clear();
+        assert!(!bandsb.has_wave_length_unit());
+        let raw = bandsb.to_raw().unwrap();
+        assert_eq!(raw.cel, bandsb.length());
+        let decoded = Bandsb::from_raw(&raw).unwrap();
+        assert_eq!(decoded.wave_length_unit, "");
+        assert_eq!(decoded.bands[1].bandid, "BAND 0.55");
+        assert_eq!(decoded.bands[1].cwave, None);
+    }
+}
@@ -0,0 +1,128 @@
+//! BLOCKA image block information TRE
+use std::io::{Read, Seek, Write};
+
+use crate::geo::parse_dms;
+use crate::tre::{
+    read_optional, read_string, read_value, write_float, write_int, write_string, Tre,
+};
+use crate::{NitfError, NitfResult};
+
+/// BLOCKA TRE
+///
+/// Corner locations are `(latitude, longitude)` in decimal degrees. They are
+/// read in either the decimal degree or `ddmmss.ssH` format, and written in
+/// decimal degrees.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Blocka {
+    /// Block Number
+    pub block_instance: u8,
+    /// Number of Gray Fill Pixels
+    pub n_gray: u32,
+    /// Row Count
+    pub l_lines: u32,
+    /// Angle to North. `None` if blank
+    pub layover_angle: Option<u16>,
+    /// Angle to Shadow. `None` if blank
+    pub shadow_angle: Option<u16>,
+    /// First Row, Last Column location. `None` if blank
+    pub frlc_loc: Option<(f64, f64)>,
+    /// Last Row, Last Column location. `None` if blank
+    pub lrlc_loc: Option<(f64, f64)>,
+    /// Last Row, First Column location. `None` if blank
+    pub lrfc_loc: Option<(f64, f64)>,
+    /// First Row, First Column location. `None` if blank
+    pub frfc_loc: Option<(f64, f64)>,
+}
+impl Blocka {
+    /// Corner locations ordered as IGEOLO: first row first column, first row
+    /// last column, last row last column, and last row first column. `None`
+    /// unless all four are present.
+    pub fn corners(&self) -> Option<[(f64, f64); 4]> {
+        Some([
+            self.frfc_loc?,
+            self.frlc_loc?,
+            self.lrlc_loc?,
+            self.lrfc_loc?,
+        ])
+    }
+    /// Set the corner locations, ordered as in [Blocka::corners()]
+    pub fn set_corners(&mut self, corners: &[(f64, f64); 4]) {
+        self.frfc_loc = Some(corners[0]);
+        self.frlc_loc = Some(corners[1]);
+        self.lrlc_loc = Some(corners[2]);
+        self.lrfc_loc = Some(corners[3]);
+    }
+}
+
+fn read_location(reader: &mut impl Read, name: &str) -> NitfResult<Option<(f64, f64)>> {
+    let string = read_string(reader, 21, name)?;
+    if string.is_empty() {
+        return Ok(None);
+    }
+    let error = || NitfError::ParseError(format!("{name}: {string:?}"));
+    let (lat, lon) = string.split_at_checked(10).ok_or_else(error)?;
+    match lat.starts_with(['+', '-']) {
+        true => Ok(Some((
+            lat.parse().or(Err(error()))?,
+            lon.parse().or(Err(error()))?,
+        ))),
+        false => Ok(Some((parse_dms(lat, 2)?, parse_dms(lon, 3)?))),
+    }
+}
+
+fn write_location(
+    writer: &mut impl Write,
+    location: Option<(f64, f64)>,
+    name: &str,
+) -> NitfResult<usize> {
+    match location {
+        Some((lat, lon)) => {
+            let mut bytes_written = write_float(writer, lat, 10, 6, true, name)?;
+            bytes_written += write_float(writer, lon, 11, 6, true, name)?;
+            Ok(bytes_written)
+        }
+        None => write_string(writer, "", 21, name),
+    }
+}
+
+impl Tre for Blocka {
+    const TAG: &'static str = "BLOCKA";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.block_instance = read_value(reader, 2, "BLOCK_INSTANCE")?;
+        self.n_gray = read_value(reader, 5, "N_GRAY")?;
+        self.l_lines = read_value(reader, 5, "L_LINES")?;
+        self.layover_angle = read_optional(reader, 3, "LAYOVER_ANGLE")?;
+        self.shadow_angle = read_optional(reader, 3, "SHADOW_ANGLE")?;
+        read_string(reader, 16, "BLANKS")?;
+        self.frlc_loc = read_location(reader, "FRLC_LOC")?;
+        self.lrlc_loc = read_location(reader, "LRLC_LOC")?;
+        self.lrfc_loc = read_location(reader, "LRFC_LOC")?;
+        self.frfc_loc = read_location(reader, "FRFC_LOC")?;
+        read_string(reader, 5, "RESERVED")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_int(writer, self.block_instance.into(), 2, "BLOCK_INSTANCE")?;
+        bytes_written += write_int(writer, self.n_gray.into(), 5, "N_GRAY")?;
+        bytes_written += write_int(writer, self.l_lines.into(), 5, "L_LINES")?;
+        bytes_written += match self.layover_angle {
+            Some(angle) => write_int(writer, angle.into(), 3, "LAYOVER_ANGLE")?,
+            None => write_string(writer, "", 3, "LAYOVER_ANGLE")?,
+        };
+        bytes_written += match self.shadow_angle {
+            Some(angle) => write_int(writer, angle.into(), 3, "SHADOW_ANGLE")?,
+            None => write_string(writer, "", 3, "SHADOW_ANGLE")?,
+        };
+        bytes_written += write_string(writer, "", 16, "BLANKS")?;
+        bytes_written += write_location(writer, self.frlc_loc, "FRLC_LOC")?;
+        bytes_written += write_location(writer, self.lrlc_loc, "LRLC_LOC")?;
+        bytes_written += write_location(writer, self.lrfc_loc, "LRFC_LOC")?;
+        bytes_written += write_location(writer, self.frfc_loc, "FRFC_LOC")?;
+        bytes_written += write_string(writer, "010.0", 5, "RESERVED")?;
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        123
+    }
+}
@@ -0,0 +1,706 @@
+//! Data-driven TRE definitions
+//!
+//! TREs without a hand written [Tre](crate::tre::Tre) implementation can be
+//! described in XML, similar to the `nitf_spec.xml` format used by GDAL and
+//! NITRO, and interpreted at runtime into an ordered list of named values.
+//! ```xml
+//! <tres>
+//!   <tre name="MYTREA">
+//!     <field name="COUNT" length="2" type="integer"/>
+//!     <loop name="ITEM" counter="COUNT">
+//!       <field name="VALUE" length="6" type="real"/>
+//!     </loop>
+//!     <if cond="COUNT!=0">
+//!       <field name="UNITS" length="3"/>
+//!     </if>
+//!   </tre>
+//! </tres>
+//! ```
+//! A set of definitions is embedded in the crate and registered in
+//! [TreRegistry::builtin()](crate::tre::TreRegistry::builtin). More can be
+//! added to the global registry at runtime with [load()].
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::any::Any;
+use std::fmt::Display;
+use std::sync::Arc;
+
+use crate::tre::{AnyTre, TreRegistry};
+use crate::types::RawTre;
+use crate::{NitfError, NitfResult};
+
+/// Definitions embedded at compile time
+const EMBEDDED: &str = include_str!("definitions.xml");
+
+/// Description of a single TRE
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreDefinition {
+    /// CETAG of the extension
+    pub tag: String,
+    /// Ordered description of the TRE contents
+    pub elements: Vec<Element>,
+}
+
+/// Item within a [TreDefinition]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    /// Single fixed or variable length field
+    Field {
+        name: String,
+        length: Length,
+        kind: FieldKind,
+    },
+    /// Repeated group of elements
+    Loop {
+        name: String,
+        count: Length,
+        elements: Vec<Element>,
+    },
+    /// Group of elements present only when a condition holds
+    If {
+        condition: Condition,
+        elements: Vec<Element>,
+    },
+}
+
+/// Length of a field, or number of loop iterations
+#[derive(Debug, Clone, PartialEq)]
+pub enum Length {
+    /// Constant value
+    Fixed(usize),
+    /// Value of an earlier integer field
+    Field(String),
+}
+
+/// Expected contents of a field
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum FieldKind {
+    #[default]
+    /// Free text, left justified
+    String,
+    /// Integer, right justified and zero filled
+    Integer,
+    /// Decimal number, right justified and zero filled
+    Real,
+}
+
+/// Comparison of an earlier field against a value
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Condition {
+    /// Name of the field to compare
+    pub field: String,
+    /// Value to compare against
+    pub value: String,
+    /// True for `!=` comparisons
+    pub negate: bool,
+}
+
+/// Value of a field or loop
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreValue {
+    /// Field text, with surrounding spaces removed
+    Field(String),
+    /// Values of each loop iteration
+    Loop(Vec<TreFields>),
+}
+
+/// Ordered mapping of names to values
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TreFields(Vec<(String, TreValue)>);
+
+impl TreFields {
+    /// Get a value by name
+    pub fn get(&self, name: &str) -> Option<&TreValue> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+    /// Get the text of a field
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            TreValue::Field(text) => Some(text),
+            TreValue::Loop(_) => None,
+        }
+    }
+    /// Get a field parsed as an integer
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        self.get_str(name)?.parse().ok()
+    }
+    /// Get a field parsed as a decimal number
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        self.get_str(name)?.parse().ok()
+    }
+    /// Get the iterations of a loop
+    pub fn get_loop(&self, name: &str) -> Option<&[TreFields]> {
+        match self.get(name)? {
+            TreValue::Loop(items) => Some(items),
+            TreValue::Field(_) => None,
+        }
+    }
+    /// Set a value, replacing any existing value with the same name
+    pub fn insert(&mut self, name: &str, value: TreValue) {
+        match self.0.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((name.to_string(), value)),
+        }
+    }
+    /// Iterate over the names and values in order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &TreValue)> {
+        self.0.iter().map(|(n, v)| (n.as_str(), v))
+    }
+    /// Number of values
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    /// Check if there are no values
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+impl Display for TreFields {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, value)) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            match value {
+                TreValue::Field(text) => write!(f, "{name}: {text}")?,
+                TreValue::Loop(items) => {
+                    write!(f, "{name}: [")?;
+                    for (i_item, item) in items.iter().enumerate() {
+                        write!(f, "{i_item}: [{item}], ")?;
+                    }
+                    write!(f, "]")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fields which are in scope while interpreting a definition
+struct Scope<'a> {
+    parents: Vec<&'a TreFields>,
+}
+impl Scope<'_> {
+    fn lookup<'b>(&'b self, current: &'b TreFields, name: &str) -> Option<&'b str> {
+        std::iter::once(current)
+            .chain(self.parents.iter().rev().copied())
+            .find_map(|fields| fields.get_str(name))
+    }
+    fn resolve(&self, current: &TreFields, length: &Length, tag: &str) -> NitfResult<usize> {
+        match length {
+            Length::Fixed(n) => Ok(*n),
+            Length::Field(name) => self
+                .lookup(current, name)
+                .and_then(|v| v.parse().ok())
+                .ok_or(NitfError::TreError(format!(
+                    "{tag}: {name} is not a valid count or length"
+                ))),
+        }
+    }
+    fn check(&self, current: &TreFields, condition: &Condition) -> bool {
+        let value = self.lookup(current, &condition.field).unwrap_or("");
+        (value == condition.value) != condition.negate
+    }
+}
+
+impl TreDefinition {
+    /// Interpret the user defined data of a TRE
+    pub fn read(&self, raw: &RawTre) -> NitfResult<TreFields> {
+        if raw.cetag != self.tag {
+            Err(NitfError::TreError(format!(
+                "cannot read {} as {}",
+                raw.cetag, self.tag
+            )))?
+        }
+        let mut offset = 0;
+        let mut fields = TreFields::default();
+        let scope = Scope { parents: vec![] };
+        self.read_elements(&self.elements, &raw.data, &mut offset, &scope, &mut fields)?;
+        if offset != raw.cel {
+            Err(NitfError::TreError(format!(
+                "{}: read {offset} bytes, but CEL is {}",
+                self.tag, raw.cel
+            )))?
+        }
+        Ok(fields)
+    }
+
+    fn read_elements(
+        &self,
+        elements: &[Element],
+        data: &[u8],
+        offset: &mut usize,
+        scope: &Scope,
+        fields: &mut TreFields,
+    ) -> NitfResult<()> {
+        for element in elements {
+            match element {
+                Element::Field { name, length, kind } => {
+                    let length = scope.resolve(fields, length, &self.tag)?;
+                    let bytes = data
+                        .get(*offset..*offset + length)
+                        .ok_or(NitfError::TreError(format!(
+                            "{}: data ends before {name}",
+                            self.tag
+                        )))?;
+                    let text = String::from_utf8_lossy(bytes).trim().to_string();
+                    check_kind(&text, *kind, &self.tag, name)?;
+                    *offset += length;
+                    fields.insert(name, TreValue::Field(text));
+                }
+                Element::Loop {
+                    name,
+                    count,
+                    elements,
+                } => {
+                    let count = scope.resolve(fields, count, &self.tag)?;
+                    let mut items = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        let mut item = TreFields::default();
+                        let mut inner = Scope {
+                            parents: scope.parents.clone(),
+                        };
+                        inner.parents.push(fields);
+                        self.read_elements(elements, data, offset, &inner, &mut item)?;
+                        items.push(item);
+                    }
+                    fields.insert(name, TreValue::Loop(items));
+                }
+                Element::If {
+                    condition,
+                    elements,
+                } => {
+                    if scope.check(fields, condition) {
+                        self.read_elements(elements, data, offset, scope, fields)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize values into a TRE.
+    ///
+    /// Values are looked up by name, so their order does not matter. Loop
+    /// counts must agree with the number of loop iterations provided.
+    pub fn write(&self, fields: &TreFields) -> NitfResult<RawTre> {
+        let mut data = vec![];
+        let scope = Scope { parents: vec![] };
+        self.write_elements(&self.elements, fields, &scope, &mut data)?;
+        Ok(RawTre::new(&self.tag, data))
+    }
+
+    fn write_elements(
+        &self,
+        elements: &[Element],
+        fields: &TreFields,
+        scope: &Scope,
+        data: &mut Vec<u8>,
+    ) -> NitfResult<()> {
+        for element in elements {
+            match element {
+                Element::Field { name, length, kind } => {
+                    let length = scope.resolve(fields, length, &self.tag)?;
+                    let text = fields.get_str(name).ok_or(NitfError::TreError(format!(
+                        "{}: missing value for {name}",
+                        self.tag
+                    )))?;
+                    check_kind(text, *kind, &self.tag, name)?;
+                    let padded = match kind {
+                        FieldKind::String => format!("{text:<length$}"),
+                        FieldKind::Integer | FieldKind::Real => pad_numeric(text, length),
+                    };
+                    if padded.len() != length {
+                        Err(NitfError::TreError(format!(
+                            "{}: {name} value {text:?} is longer than {length} bytes",
+                            self.tag
+                        )))?
+                    }
+                    data.extend_from_slice(padded.as_bytes());
+                }
+                Element::Loop {
+                    name,
+                    count,
+                    elements,
+                } => {
+                    let count = scope.resolve(fields, count, &self.tag)?;
+                    let items = fields.get_loop(name).unwrap_or(&[]);
+                    if items.len() != count {
+                        Err(NitfError::TreError(format!(
+                            "{}: {name} has {} iterations, but its count is {count}",
+                            self.tag,
+                            items.len()
+                        )))?
+                    }
+                    for item in items {
+                        let mut inner = Scope {
+                            parents: scope.parents.clone(),
+                        };
+                        inner.parents.push(fields);
+                        self.write_elements(elements, item, &inner, data)?;
+                    }
+                }
+                Element::If {
+                    condition,
+                    elements,
+                } => {
+                    if scope.check(fields, condition) {
+                        self.write_elements(elements, fields, scope, data)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check that a value matches the kind of field it is stored in
+fn check_kind(text: &str, kind: FieldKind, tag: &str, name: &str) -> NitfResult<()> {
+    let valid = text.is_empty()
+        || match kind {
+            FieldKind::String => true,
+            FieldKind::Integer => text.parse::<i64>().is_ok(),
+            FieldKind::Real => text.parse::<f64>().is_ok(),
+        };
+    match valid {
+        true => Ok(()),
+        false => Err(NitfError::ParseError(format!("{tag}.{name}: {text:?}"))),
+    }
+}
+
+/// Right justify and zero fill a number, keeping any sign first
+fn pad_numeric(text: &str, length: usize) -> String {
+    if text.is_empty() {
+        return " ".repeat(length);
+    }
+    let (sign, digits) = match text.strip_prefix(['+', '-']) {
+        Some(digits) => (&text[..1], digits),
+        None => ("", text),
+    };
+    let width = length.saturating_sub(sign.len());
+    format!("{sign}{digits:0>width$}")
+}
+
+/// TRE interpreted with a [TreDefinition]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicTre {
+    /// Interpreted values
+    pub fields: TreFields,
+    definition: Arc<TreDefinition>,
+}
+impl DynamicTre {
+    /// Definition used to interpret the TRE
+    pub fn definition(&self) -> &TreDefinition {
+        &self.definition
+    }
+}
+impl AnyTre for DynamicTre {
+    fn tag(&self) -> &str {
+        &self.definition.tag
+    }
+    fn to_raw(&self) -> NitfResult<RawTre> {
+        self.definition.write(&self.fields)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Parse a set of TRE definitions
+pub fn parse(xml: &str) -> NitfResult<Vec<TreDefinition>> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut definitions = vec![];
+    // Open elements, each collecting its children
+    let mut stack: Vec<(Element, String)> = vec![];
+    let mut current_tag: Option<String> = None;
+    let mut root: Vec<Element> = vec![];
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| xml_error(&reader, &e.to_string()))?;
+        match event {
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == b"tres" => {}
+            Event::Start(e) if e.name().as_ref() == b"tre" => {
+                if current_tag.is_some() {
+                    Err(xml_error(&reader, "tre elements cannot be nested"))?
+                }
+                current_tag = Some(attribute(&reader, &e, "name")?);
+                root.clear();
+            }
+            Event::Start(e) | Event::Empty(e) if current_tag.is_none() => Err(xml_error(
+                &reader,
+                &format!("<{}> is outside of <tre>", name_of(&e)),
+            ))?,
+            Event::Start(e) => {
+                if e.name().as_ref() == b"field" {
+                    Err(xml_error(&reader, "field elements cannot have children"))?
+                }
+                let element = parse_element(&reader, &e)?;
+                stack.push((element, name_of(&e)));
+            }
+            Event::Empty(e) => {
+                let element = parse_element(&reader, &e)?;
+                match stack.last_mut() {
+                    Some((parent, _)) => children(&reader, parent)?.push(element),
+                    None => root.push(element),
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"tres" => {}
+                b"tre" => {
+                    if !stack.is_empty() {
+                        Err(xml_error(&reader, "mismatched closing element"))?
+                    }
+                    let tag = current_tag
+                        .take()
+                        .ok_or(xml_error(&reader, "unmatched </tre>"))?;
+                    definitions.push(TreDefinition {
+                        tag,
+                        elements: std::mem::take(&mut root),
+                    });
+                }
+                name => {
+                    let (element, open) = stack
+                        .pop()
+                        .ok_or(xml_error(&reader, "unmatched closing element"))?;
+                    if open.as_bytes() != name {
+                        Err(xml_error(&reader, "mismatched closing element"))?
+                    }
+                    match stack.last_mut() {
+                        Some((parent, _)) => children(&reader, parent)?.push(element),
+                        None => root.push(element),
+                    }
+                }
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if current_tag.is_some() || !stack.is_empty() {
+        Err(xml_error(&reader, "unexpected end of definitions"))?
+    }
+    Ok(definitions)
+}
+
+/// Definitions embedded in the crate
+pub fn embedded() -> Vec<TreDefinition> {
+    // The embedded file is part of the crate, so failure here is a bug
+    parse(EMBEDDED).expect("embedded TRE definitions are invalid")
+}
+
+/// Parse definitions and add them to the global registry
+pub fn load(xml: &str) -> NitfResult<()> {
+    for definition in parse(xml)? {
+        let tag = definition.tag.clone();
+        crate::tre::register_decoder(&tag, decoder(definition));
+    }
+    Ok(())
+}
+
+/// Create a registry decoder for a definition
+pub fn decoder(definition: TreDefinition) -> crate::tre::TreDecoder {
+    let definition = Arc::new(definition);
+    Arc::new(move |raw| {
+        let fields = definition.read(raw)?;
+        Ok(Box::new(DynamicTre {
+            fields,
+            definition: definition.clone(),
+        }) as Box<dyn AnyTre>)
+    })
+}
+
+impl TreRegistry {
+    /// Add decoders for each definition, replacing any existing decoders
+    pub fn register_definitions(&mut self, definitions: Vec<TreDefinition>) {
+        for definition in definitions {
+            let tag = definition.tag.clone();
+            self.register_decoder(&tag, decoder(definition));
+        }
+    }
+}
+
+fn children<'a>(
+    reader: &Reader<&[u8]>,
+    element: &'a mut Element,
+) -> NitfResult<&'a mut Vec<Element>> {
+    match element {
+        Element::Loop { elements, .. } | Element::If { elements, .. } => Ok(elements),
+        Element::Field { .. } => Err(xml_error(reader, "field elements cannot have children")),
+    }
+}
+
+fn name_of(e: &BytesStart) -> String {
+    String::from_utf8_lossy(e.name().as_ref()).to_string()
+}
+
+fn xml_error(reader: &Reader<&[u8]>, msg: &str) -> NitfError {
+    NitfError::TreError(format!(
+        "TRE definitions at byte {}: {msg}",
+        reader.buffer_position()
+    ))
+}
+
+fn optional_attribute(
+    reader: &Reader<&[u8]>,
+    e: &BytesStart,
+    key: &str,
+) -> NitfResult<Option<String>> {
+    for attr in e.attributes() {
+        let attr = attr.map_err(|err| xml_error(reader, &err.to_string()))?;
+        if attr.key.as_ref() == key.as_bytes() {
+            let value = attr
+                .unescape_value()
+                .map_err(|err| xml_error(reader, &err.to_string()))?;
+            return Ok(Some(value.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+fn attribute(reader: &Reader<&[u8]>, e: &BytesStart, key: &str) -> NitfResult<String> {
+    optional_attribute(reader, e, key)?.ok_or(xml_error(
+        reader,
+        &format!("<{}> is missing {key}", name_of(e)),
+    ))
+}
+
+/// Parse a fixed value or field reference from two alternative attributes
+fn length_attribute(
+    reader: &Reader<&[u8]>,
+    e: &BytesStart,
+    fixed: &str,
+    field: &str,
+) -> NitfResult<Length> {
+    if let Some(value) = optional_attribute(reader, e, fixed)? {
+        let n = value
+            .parse()
+            .map_err(|_| xml_error(reader, &format!("invalid {fixed} {value:?}")))?;
+        return Ok(Length::Fixed(n));
+    }
+    match optional_attribute(reader, e, field)? {
+        Some(name) => Ok(Length::Field(name)),
+        None => Err(xml_error(
+            reader,
+            &format!("<{}> needs either {fixed} or {field}", name_of(e)),
+        )),
+    }
+}
+
+fn parse_element(reader: &Reader<&[u8]>, e: &BytesStart) -> NitfResult<Element> {
+    match e.name().as_ref() {
+        b"field" => {
+            let kind = match optional_attribute(reader, e, "type")?.as_deref() {
+                None | Some("string") => FieldKind::String,
+                Some("integer") => FieldKind::Integer,
+                Some("real") => FieldKind::Real,
+                Some(other) => Err(xml_error(reader, &format!("unknown type {other:?}")))?,
+            };
+            Ok(Element::Field {
+                name: attribute(reader, e, "name")?,
+                length: length_attribute(reader, e, "length", "length_var")?,
+                kind,
+            })
+        }
+        b"loop" => Ok(Element::Loop {
+            name: optional_attribute(reader, e, "name")?.unwrap_or("LOOP".to_string()),
+            count: length_attribute(reader, e, "iterations", "counter")?,
+            elements: vec![],
+        }),
+        b"if" => {
+            let cond = attribute(reader, e, "cond")?;
+            let (field, value, negate) = match cond.split_once("!=") {
+                Some((field, value)) => (field, value, true),
+                None => match cond.split_once('=') {
+                    Some((field, value)) => (field, value, false),
+                    None => Err(xml_error(reader, &format!("invalid condition {cond:?}")))?,
+                },
+            };
+            Ok(Element::If {
+                condition: Condition {
+                    field: field.trim().to_string(),
+                    value: value.trim().to_string(),
+                    negate,
+                },
+                elements: vec![],
+            })
+        }
+        _ => Err(xml_error(
+            reader,
+            &format!("unknown element <{}>", name_of(e)),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Values for every field and loop of a definition. Every field is "1",
+    /// so counts and variable lengths are 1, and conditional elements are
+    /// included whether or not they are written.
+    fn sample(elements: &[Element], fields: &mut TreFields) {
+        for element in elements {
+            match element {
+                Element::Field { name, .. } => {
+                    fields.insert(name, TreValue::Field("1".to_string()));
+                }
+                Element::Loop {
+                    name,
+                    count,
+                    elements,
+                } => {
+                    let count = match count {
+                        Length::Fixed(n) => *n,
+                        Length::Field(_) => 1,
+                    };
+                    let mut item = TreFields::default();
+                    sample(elements, &mut item);
+                    fields.insert(name, TreValue::Loop(vec![item; count]));
+                }
+                Element::If { elements, .. } => sample(elements, fields),
+            }
+        }
+    }
+
+    #[test]
+    fn embedded_round_trip() {
+        let definitions = parse(EMBEDDED).unwrap();
+        assert!(!definitions.is_empty());
+        for definition in definitions {
+            let mut fields = TreFields::default();
+            sample(&definition.elements, &mut fields);
+            let raw = definition.write(&fields).unwrap();
+            assert_eq!(raw.cetag, definition.tag);
+            let read = definition.read(&raw).unwrap();
+            let rewritten = definition.write(&read).unwrap();
+            assert_eq!(rewritten.data, raw.data, "{}", definition.tag);
+            assert_eq!(definition.read(&rewritten).unwrap(), read);
+        }
+    }
+
+    #[test]
+    fn malformed_definitions() {
+        let malformed = [
+            // Field with children
+            r#"<tres><tre name="FOOBAR"><field name="A" length="1"><field name="B" length="1"/></field></tre></tres>"#,
+            // Elements outside of a TRE
+            r#"<tres><field name="A" length="1"/></tres>"#,
+            r#"<tres><loop counter="A"></loop></tres>"#,
+            // Nested and unclosed TREs
+            r#"<tres><tre name="A"><tre name="B"></tre></tre></tres>"#,
+            r#"<tres><tre name="A"><loop counter="N"></tre></tres>"#,
+            r#"<tres><tre name="A"><field name="A" length="1"/>"#,
+            // Unknown elements and missing attributes
+            r#"<tres><tre name="A"><group/></tre></tres>"#,
+            r#"<tres><tre name="A"><field length="1"/></tre></tres>"#,
+            r#"<tres><tre name="A"><field name="A"/></tre></tres>"#,
+            r#"<tres><tre name="A"><if cond="A"></if></tre></tres>"#,
+        ];
+        for xml in malformed {
+            assert!(parse(xml).is_err(), "{xml}");
+            assert!(load(xml).is_err(), "{xml}");
+        }
+    }
+}
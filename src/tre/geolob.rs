@@ -0,0 +1,59 @@
+//! GEOLOB local geographic coordinate system TRE
+use std::io::{Read, Seek, Write};
+
+use crate::tre::{read_value, write_float, write_int, Tre};
+use crate::NitfResult;
+
+/// GEOLOB TRE
+///
+/// Describes an image on a regular latitude/longitude grid, with the origin at
+/// the first pixel.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Geolob {
+    /// Longitude density, pixels per 360 degrees
+    pub arv: u32,
+    /// Latitude density, pixels per 360 degrees
+    pub brv: u32,
+    /// Longitude of Reference Origin, degrees
+    pub lso: f64,
+    /// Latitude of Reference Origin, degrees
+    pub pso: f64,
+}
+impl Geolob {
+    /// Longitude spacing between columns in degrees
+    pub fn lon_spacing(&self) -> f64 {
+        360.0 / self.arv as f64
+    }
+    /// Latitude spacing between rows in degrees
+    pub fn lat_spacing(&self) -> f64 {
+        360.0 / self.brv as f64
+    }
+    /// Ground `(latitude, longitude)` in degrees of a `(row, column)` pixel
+    pub fn pixel_to_ground(&self, row: f64, col: f64) -> (f64, f64) {
+        let lat = self.pso - row * self.lat_spacing();
+        let lon = self.lso + col * self.lon_spacing();
+        (lat, (lon + 180.0).rem_euclid(360.0) - 180.0)
+    }
+}
+
+impl Tre for Geolob {
+    const TAG: &'static str = "GEOLOB";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.arv = read_value(reader, 9, "ARV")?;
+        self.brv = read_value(reader, 9, "BRV")?;
+        self.lso = read_value(reader, 15, "LSO")?;
+        self.pso = read_value(reader, 15, "PSO")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_int(writer, self.arv.into(), 9, "ARV")?;
+        bytes_written += write_int(writer, self.brv.into(), 9, "BRV")?;
+        bytes_written += write_float(writer, self.lso, 15, 10, true, "LSO")?;
+        bytes_written += write_float(writer, self.pso, 15, 11, true, "PSO")?;
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        48
+    }
+}
@@ -0,0 +1,100 @@
+//! GEOPSB coordinate system reference TRE
+use std::io::{Read, Seek, Write};
+
+use crate::geo::Ellipsoid;
+use crate::tre::{
+    read_optional, read_string, read_value, write_float, write_int, write_string, Tre,
+};
+use crate::{NitfError, NitfResult};
+
+/// GEOPSB TRE
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Geopsb {
+    /// Coordinate System Type, `GEO`, `MAP`, or `DIG`
+    pub typ: String,
+    /// Coordinate Units, `SEC`, `DEG`, or `M`
+    pub uni: String,
+    /// Geodetic Datum Name
+    pub dag: String,
+    /// Geodetic Datum Code
+    pub dcd: String,
+    /// Ellipsoid Name
+    pub ell: String,
+    /// Ellipsoid Code
+    pub elc: String,
+    /// Vertical Datum Reference
+    pub dvr: String,
+    /// Code of Vertical Reference
+    pub vdcdvr: String,
+    /// Sounding Datum Name
+    pub sda: String,
+    /// Code for Sounding Datum
+    pub vdcsda: String,
+    /// Z Values False Origin
+    pub zor: f64,
+    /// Grid Code, e.g. `UTM`
+    pub grd: String,
+    /// Grid Description
+    pub grn: String,
+    /// Grid Zone Number. `None` if blank
+    pub zna: Option<u16>,
+}
+impl Geopsb {
+    /// Reference ellipsoid from the ellipsoid code
+    pub fn ellipsoid(&self) -> NitfResult<Ellipsoid> {
+        match self.elc.as_str() {
+            "WE" => Ok(Ellipsoid::WGS84),
+            "RF" => Ok(Ellipsoid::GRS80),
+            "CC" => Ok(Ellipsoid::CLARKE_1866),
+            "IN" => Ok(Ellipsoid::INTERNATIONAL_1924),
+            code => Err(NitfError::Fatal(format!(
+                "Unsupported GEOPSB ellipsoid code {code:?}"
+            ))),
+        }
+    }
+}
+
+impl Tre for Geopsb {
+    const TAG: &'static str = "GEOPSB";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.typ = read_string(reader, 3, "TYP")?;
+        self.uni = read_string(reader, 3, "UNI")?;
+        self.dag = read_string(reader, 80, "DAG")?;
+        self.dcd = read_string(reader, 4, "DCD")?;
+        self.ell = read_string(reader, 80, "ELL")?;
+        self.elc = read_string(reader, 3, "ELC")?;
+        self.dvr = read_string(reader, 80, "DVR")?;
+        self.vdcdvr = read_string(reader, 4, "VDCDVR")?;
+        self.sda = read_string(reader, 80, "SDA")?;
+        self.vdcsda = read_string(reader, 4, "VDCSDA")?;
+        self.zor = read_value(reader, 15, "ZOR")?;
+        self.grd = read_string(reader, 3, "GRD")?;
+        self.grn = read_string(reader, 80, "GRN")?;
+        self.zna = read_optional(reader, 4, "ZNA")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_string(writer, &self.typ, 3, "TYP")?;
+        bytes_written += write_string(writer, &self.uni, 3, "UNI")?;
+        bytes_written += write_string(writer, &self.dag, 80, "DAG")?;
+        bytes_written += write_string(writer, &self.dcd, 4, "DCD")?;
+        bytes_written += write_string(writer, &self.ell, 80, "ELL")?;
+        bytes_written += write_string(writer, &self.elc, 3, "ELC")?;
+        bytes_written += write_string(writer, &self.dvr, 80, "DVR")?;
+        bytes_written += write_string(writer, &self.vdcdvr, 4, "VDCDVR")?;
+        bytes_written += write_string(writer, &self.sda, 80, "SDA")?;
+        bytes_written += write_string(writer, &self.vdcsda, 4, "VDCSDA")?;
+        bytes_written += write_float(writer, self.zor, 15, 3, false, "ZOR")?;
+        bytes_written += write_string(writer, &self.grd, 3, "GRD")?;
+        bytes_written += write_string(writer, &self.grn, 80, "GRN")?;
+        bytes_written += match self.zna {
+            Some(zna) => write_int(writer, zna.into(), 4, "ZNA")?,
+            None => write_string(writer, "", 4, "ZNA")?,
+        };
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        443
+    }
+}
@@ -0,0 +1,322 @@
+//! ICHIPB chipping TRE
+//!
+//! Records the location of a chipped image within the original full image.
+//! Output product (OP) coordinates are the pixel coordinates of the chip, and
+//! full image (FI) coordinates are pixel coordinates of the original image.
+//! Corner `11` is the upper left, `12` the upper right, `21` the lower left,
+//! and `22` the lower right.
+//!
+//! Grid points follow the pixel area convention, where the first pixel covers
+//! `0.0..1.0` and its center is at `0.5`. The conversions here take and return
+//! pixel center coordinates, as RPC models use, where the first pixel is at
+//! `0.0`.
+use std::io::{Read, Seek, Write};
+
+use crate::tre::{read_value, write_float, write_int, Tre};
+use crate::{NitfError, NitfResult};
+
+/// ICHIPB TRE
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Ichipb {
+    /// Non-linear Transformation Flag
+    pub xfrm_flag: u8,
+    /// Scale Factor Relative to R0
+    pub scale_factor: f64,
+    /// Anamorphic Correction Indicator
+    pub anamrph_corr: u8,
+    /// Scan Block Number
+    pub scanblk_num: u8,
+    /// Output Product Row Number Component of Grid Point Index (1,1)
+    pub op_row_11: f64,
+    /// Output Product Column Number Component of Grid Point Index (1,1)
+    pub op_col_11: f64,
+    /// Output Product Row Number Component of Grid Point Index (1,2)
+    pub op_row_12: f64,
+    /// Output Product Column Number Component of Grid Point Index (1,2)
+    pub op_col_12: f64,
+    /// Output Product Row Number Component of Grid Point Index (2,1)
+    pub op_row_21: f64,
+    /// Output Product Column Number Component of Grid Point Index (2,1)
+    pub op_col_21: f64,
+    /// Output Product Row Number Component of Grid Point Index (2,2)
+    pub op_row_22: f64,
+    /// Output Product Column Number Component of Grid Point Index (2,2)
+    pub op_col_22: f64,
+    /// Grid Point (1,1) Row Number in Full Image Coordinate System
+    pub fi_row_11: f64,
+    /// Grid Point (1,1) Column Number in Full Image Coordinate System
+    pub fi_col_11: f64,
+    /// Grid Point (1,2) Row Number in Full Image Coordinate System
+    pub fi_row_12: f64,
+    /// Grid Point (1,2) Column Number in Full Image Coordinate System
+    pub fi_col_12: f64,
+    /// Grid Point (2,1) Row Number in Full Image Coordinate System
+    pub fi_row_21: f64,
+    /// Grid Point (2,1) Column Number in Full Image Coordinate System
+    pub fi_col_21: f64,
+    /// Grid Point (2,2) Row Number in Full Image Coordinate System
+    pub fi_row_22: f64,
+    /// Grid Point (2,2) Column Number in Full Image Coordinate System
+    pub fi_col_22: f64,
+    /// Full Image Number of Rows
+    pub fi_row: u32,
+    /// Full Image Number of Columns
+    pub fi_col: u32,
+}
+
+/// Affine transform `[offset, row coefficient, column coefficient]` for each
+/// of the output row and column
+type Affine = ([f64; 3], [f64; 3]);
+
+impl Ichipb {
+    /// Create an ICHIPB for a chip of a full image with unit scale.
+    ///
+    /// # Parameters
+    ///
+    /// row, col: Full image pixel of the chip's first pixel
+    ///
+    /// n_rows, n_cols: Size of the chip
+    ///
+    /// fi_rows, fi_cols: Size of the full image
+    pub fn from_window(
+        row: u32,
+        col: u32,
+        n_rows: u32,
+        n_cols: u32,
+        fi_rows: u32,
+        fi_cols: u32,
+    ) -> Self {
+        // Grid points are the centers of the corner pixels
+        let (r0, c0) = (row as f64, col as f64);
+        let (nr, nc) = (n_rows as f64, n_cols as f64);
+        Self {
+            xfrm_flag: 0,
+            scale_factor: 1.0,
+            anamrph_corr: 0,
+            scanblk_num: 0,
+            op_row_11: 0.5,
+            op_col_11: 0.5,
+            op_row_12: 0.5,
+            op_col_12: nc - 0.5,
+            op_row_21: nr - 0.5,
+            op_col_21: 0.5,
+            op_row_22: nr - 0.5,
+            op_col_22: nc - 0.5,
+            fi_row_11: r0 + 0.5,
+            fi_col_11: c0 + 0.5,
+            fi_row_12: r0 + 0.5,
+            fi_col_12: c0 + nc - 0.5,
+            fi_row_21: r0 + nr - 0.5,
+            fi_col_21: c0 + 0.5,
+            fi_row_22: r0 + nr - 0.5,
+            fi_col_22: c0 + nc - 0.5,
+            fi_row: fi_rows,
+            fi_col: fi_cols,
+        }
+    }
+
+    /// Affine transform which maps the `from` grid points onto the `to` points
+    fn solve(from: [(f64, f64); 3], to: [(f64, f64); 3]) -> NitfResult<Affine> {
+        let [(r1, c1), (r2, c2), (r3, c3)] = from;
+        let det = r1 * (c2 - c3) - c1 * (r2 - r3) + (r2 * c3 - r3 * c2);
+        if det.abs() < f64::EPSILON {
+            Err(NitfError::Fatal(
+                "ICHIPB grid points are degenerate".to_string(),
+            ))?
+        }
+        // Cramer's rule for [1, r, c] . x = value at each point
+        let coefficients = |v1: f64, v2: f64, v3: f64| {
+            let a0 = v1 * (r2 * c3 - r3 * c2) - r1 * (v2 * c3 - v3 * c2) + c1 * (v2 * r3 - v3 * r2);
+            let a1 = (v2 - v3) * c1 - (v1 - v3) * c2 + (v1 - v2) * c3;
+            let a2 = (v1 - v3) * r2 - (v2 - v3) * r1 - (v1 - v2) * r3;
+            [a0 / det, -a1 / det, -a2 / det]
+        };
+        let [(t1r, t1c), (t2r, t2c), (t3r, t3c)] = to;
+        Ok((coefficients(t1r, t2r, t3r), coefficients(t1c, t2c, t3c)))
+    }
+
+    fn op_points(&self) -> [(f64, f64); 3] {
+        [
+            (self.op_row_11, self.op_col_11),
+            (self.op_row_12, self.op_col_12),
+            (self.op_row_21, self.op_col_21),
+        ]
+    }
+
+    fn fi_points(&self) -> [(f64, f64); 3] {
+        [
+            (self.fi_row_11, self.fi_col_11),
+            (self.fi_row_12, self.fi_col_12),
+            (self.fi_row_21, self.fi_col_21),
+        ]
+    }
+
+    fn apply((row_xfm, col_xfm): Affine, row: f64, col: f64) -> (f64, f64) {
+        (
+            row_xfm[0] + row_xfm[1] * row + row_xfm[2] * col,
+            col_xfm[0] + col_xfm[1] * row + col_xfm[2] * col,
+        )
+    }
+
+    /// Map pixel center coordinates through the affine transform of grid
+    /// points
+    fn map(
+        &self,
+        from: [(f64, f64); 3],
+        to: [(f64, f64); 3],
+        row: f64,
+        col: f64,
+    ) -> NitfResult<(f64, f64)> {
+        if self.xfrm_flag != 0 {
+            Err(NitfError::Value(format!(
+                "ICHIPB XFRM_FLAG {:02}: chip transform is not applicable",
+                self.xfrm_flag
+            )))?
+        }
+        let xfm = Self::solve(from, to)?;
+        let (row, col) = Self::apply(xfm, row + 0.5, col + 0.5);
+        Ok((row - 0.5, col - 0.5))
+    }
+
+    /// Convert chip `(row, column)` coordinates to full image coordinates.
+    /// Errors if XFRM_FLAG says the transform is not applicable.
+    pub fn chip_to_full(&self, row: f64, col: f64) -> NitfResult<(f64, f64)> {
+        self.map(self.op_points(), self.fi_points(), row, col)
+    }
+
+    /// Convert full image `(row, column)` coordinates to chip coordinates.
+    /// Errors if XFRM_FLAG says the transform is not applicable.
+    pub fn full_to_chip(&self, row: f64, col: f64) -> NitfResult<(f64, f64)> {
+        self.map(self.fi_points(), self.op_points(), row, col)
+    }
+}
+
+impl Tre for Ichipb {
+    const TAG: &'static str = "ICHIPB";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.xfrm_flag = read_value(reader, 2, "XFRM_FLAG")?;
+        self.scale_factor = read_value(reader, 10, "SCALE_FACTOR")?;
+        self.anamrph_corr = read_value(reader, 2, "ANAMRPH_CORR")?;
+        self.scanblk_num = read_value(reader, 2, "SCANBLK_NUM")?;
+        self.op_row_11 = read_value(reader, 12, "OP_ROW_11")?;
+        self.op_col_11 = read_value(reader, 12, "OP_COL_11")?;
+        self.op_row_12 = read_value(reader, 12, "OP_ROW_12")?;
+        self.op_col_12 = read_value(reader, 12, "OP_COL_12")?;
+        self.op_row_21 = read_value(reader, 12, "OP_ROW_21")?;
+        self.op_col_21 = read_value(reader, 12, "OP_COL_21")?;
+        self.op_row_22 = read_value(reader, 12, "OP_ROW_22")?;
+        self.op_col_22 = read_value(reader, 12, "OP_COL_22")?;
+        self.fi_row_11 = read_value(reader, 12, "FI_ROW_11")?;
+        self.fi_col_11 = read_value(reader, 12, "FI_COL_11")?;
+        self.fi_row_12 = read_value(reader, 12, "FI_ROW_12")?;
+        self.fi_col_12 = read_value(reader, 12, "FI_COL_12")?;
+        self.fi_row_21 = read_value(reader, 12, "FI_ROW_21")?;
+        self.fi_col_21 = read_value(reader, 12, "FI_COL_21")?;
+        self.fi_row_22 = read_value(reader, 12, "FI_ROW_22")?;
+        self.fi_col_22 = read_value(reader, 12, "FI_COL_22")?;
+        self.fi_row = read_value(reader, 8, "FI_ROW")?;
+        self.fi_col = read_value(reader, 8, "FI_COL")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_int(writer, self.xfrm_flag.into(), 2, "XFRM_FLAG")?;
+        bytes_written += write_float(writer, self.scale_factor, 10, 5, false, "SCALE_FACTOR")?;
+        bytes_written += write_int(writer, self.anamrph_corr.into(), 2, "ANAMRPH_CORR")?;
+        bytes_written += write_int(writer, self.scanblk_num.into(), 2, "SCANBLK_NUM")?;
+        let grid_points = [
+            (self.op_row_11, "OP_ROW_11"),
+            (self.op_col_11, "OP_COL_11"),
+            (self.op_row_12, "OP_ROW_12"),
+            (self.op_col_12, "OP_COL_12"),
+            (self.op_row_21, "OP_ROW_21"),
+            (self.op_col_21, "OP_COL_21"),
+            (self.op_row_22, "OP_ROW_22"),
+            (self.op_col_22, "OP_COL_22"),
+            (self.fi_row_11, "FI_ROW_11"),
+            (self.fi_col_11, "FI_COL_11"),
+            (self.fi_row_12, "FI_ROW_12"),
+            (self.fi_col_12, "FI_COL_12"),
+            (self.fi_row_21, "FI_ROW_21"),
+            (self.fi_col_21, "FI_COL_21"),
+            (self.fi_row_22, "FI_ROW_22"),
+            (self.fi_col_22, "FI_COL_22"),
+        ];
+        for (value, name) in grid_points {
+            bytes_written += write_float(writer, value, 12, 3, false, name)?;
+        }
+        bytes_written += write_int(writer, self.fi_row.into(), 8, "FI_ROW")?;
+        bytes_written += write_int(writer, self.fi_col.into(), 8, "FI_COL")?;
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        224
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close((row, col): (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (row - expected.0).abs() < 1e-9 && (col - expected.1).abs() < 1e-9,
+            "{:?} != {expected:?}",
+            (row, col)
+        );
+    }
+
+    #[test]
+    fn window_round_trip() {
+        let ichipb = Ichipb::from_window(100, 200, 50, 60, 1000, 2000);
+        assert_close(ichipb.chip_to_full(0.0, 0.0).unwrap(), (100.0, 200.0));
+        assert_close(ichipb.chip_to_full(49.0, 59.0).unwrap(), (149.0, 259.0));
+        assert_close(ichipb.full_to_chip(120.25, 210.5).unwrap(), (20.25, 10.5));
+        let full = ichipb.chip_to_full(12.5, 7.0).unwrap();
+        assert_close(ichipb.full_to_chip(full.0, full.1).unwrap(), (12.5, 7.0));
+
+        let raw = ichipb.to_raw().unwrap();
+        assert_eq!(raw.cel, 224);
+        assert_eq!(Ichipb::from_raw(&raw).unwrap(), ichipb);
+    }
+
+    #[test]
+    fn scaled_chip() {
+        // Chip of 10 x 10 pixels, each covering 2 x 2 full image pixels
+        // from (100, 200)
+        let ichipb = Ichipb {
+            scale_factor: 0.5,
+            op_row_11: 0.5,
+            op_col_11: 0.5,
+            op_row_12: 0.5,
+            op_col_12: 9.5,
+            op_row_21: 9.5,
+            op_col_21: 0.5,
+            op_row_22: 9.5,
+            op_col_22: 9.5,
+            fi_row_11: 101.0,
+            fi_col_11: 201.0,
+            fi_row_12: 101.0,
+            fi_col_12: 219.0,
+            fi_row_21: 119.0,
+            fi_col_21: 201.0,
+            fi_row_22: 119.0,
+            fi_col_22: 219.0,
+            fi_row: 1000,
+            fi_col: 1000,
+            ..Default::default()
+        };
+        // The first chip pixel is centered between full pixels 100 and 101
+        assert_close(ichipb.chip_to_full(0.0, 0.0).unwrap(), (100.5, 200.5));
+        assert_close(ichipb.chip_to_full(9.0, 4.0).unwrap(), (118.5, 208.5));
+        assert_close(ichipb.full_to_chip(100.0, 200.0).unwrap(), (-0.25, -0.25));
+    }
+
+    #[test]
+    fn transform_not_applicable() {
+        let mut ichipb = Ichipb::from_window(0, 0, 10, 10, 100, 100);
+        ichipb.xfrm_flag = 1;
+        assert!(ichipb.chip_to_full(0.0, 0.0).is_err());
+        assert!(ichipb.full_to_chip(0.0, 0.0).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+//! MAPLOB local cartographic coordinate system TRE
+use std::io::{Read, Seek, Write};
+
+use crate::tre::{read_string, read_value, write_float, write_int, write_string, Tre};
+use crate::{NitfError, NitfResult};
+
+/// MAPLOB TRE
+///
+/// Describes an image on a regular map projection grid, with the origin at
+/// the first pixel. The projection itself is described by GEOPSB.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Maplob {
+    /// Length Units, e.g. `M`, `KM`, or `FT`
+    pub uniloa: String,
+    /// Easting Interval
+    pub lod: u32,
+    /// Northing Interval
+    pub lad: u32,
+    /// Easting of Reference Origin
+    pub lso: f64,
+    /// Northing of Reference Origin
+    pub pso: f64,
+}
+impl Maplob {
+    /// Size of a unit of UNILOA in meters
+    pub fn unit_meters(&self) -> NitfResult<f64> {
+        match self.uniloa.as_str() {
+            "M" => Ok(1.0),
+            "KM" => Ok(1000.0),
+            "DM" => Ok(0.1),
+            "CM" => Ok(0.01),
+            "MM" => Ok(0.001),
+            "FT" => Ok(0.3048),
+            "US" => Ok(1200.0 / 3937.0),
+            "IN" => Ok(0.0254),
+            unit => Err(NitfError::Fatal(format!(
+                "Unsupported MAPLOB unit {unit:?}"
+            ))),
+        }
+    }
+    /// Map `(easting, northing)` in meters of a `(row, column)` pixel
+    pub fn pixel_to_map(&self, row: f64, col: f64) -> NitfResult<(f64, f64)> {
+        let unit = self.unit_meters()?;
+        let easting = (self.lso + col * self.lod as f64) * unit;
+        let northing = (self.pso - row * self.lad as f64) * unit;
+        Ok((easting, northing))
+    }
+}
+
+impl Tre for Maplob {
+    const TAG: &'static str = "MAPLOB";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.uniloa = read_string(reader, 3, "UNILOA")?;
+        self.lod = read_value(reader, 5, "LOD")?;
+        self.lad = read_value(reader, 5, "LAD")?;
+        self.lso = read_value(reader, 15, "LSO")?;
+        self.pso = read_value(reader, 15, "PSO")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_string(writer, &self.uniloa, 3, "UNILOA")?;
+        bytes_written += write_int(writer, self.lod.into(), 5, "LOD")?;
+        bytes_written += write_int(writer, self.lad.into(), 5, "LAD")?;
+        bytes_written += write_float(writer, self.lso, 15, 3, false, "LSO")?;
+        bytes_written += write_float(writer, self.pso, 15, 3, false, "PSO")?;
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        43
+    }
+}
@@ -0,0 +1,539 @@
+//! Tagged record extension (TRE) definitions
+//!
+//! TREs are stored in the [ExtendedSubheader] fields of each header. Typed
+//! TREs implement the [Tre] trait, and can be read directly from a subheader
+//! ```no_run
+//! # use nitf_rs::tre::Tre;
+//! # #[derive(Debug, Default, Clone)]
+//! # struct Mytre;
+//! # impl Tre for Mytre {
+//! #     const TAG: &'static str = "MYTRE_";
+//! #     fn read(&mut self, _: &mut (impl std::io::Read + std::io::Seek)) -> nitf_rs::NitfResult<()> { Ok(()) }
+//! #     fn write(&self, _: &mut (impl std::io::Write + std::io::Seek)) -> nitf_rs::NitfResult<usize> { Ok(0) }
+//! #     fn length(&self) -> usize { 0 }
+//! # }
+//! let mut nitf_file = std::fs::File::open("example.nitf").unwrap();
+//! let nitf = nitf_rs::Nitf::from_reader(&mut nitf_file).unwrap();
+//! let tre: Option<Mytre> = nitf.image_segments[0].header.get_tre().unwrap();
+//! ```
+//!
+//! Every TRE in a subheader can also be decoded through the [TreRegistry],
+//! which maps each CETAG to a decoder. Tags without a decoder are left as
+//! [RawTre] bytes. Types defined outside of this crate can be added to the
+//! global registry with [register()], and TREs described in XML can be
+//! interpreted without any code using the [definition] module.
+use log::warn;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::{Cursor, Read, Seek, Write};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::types::{ExtendedSubheader, RawTre};
+use crate::{NitfError, NitfResult};
+
+pub mod acftb;
+pub mod aimidb;
+pub mod bandsb;
+pub mod blocka;
+pub mod definition;
+pub mod geolob;
+pub mod geopsb;
+pub mod ichipb;
+pub mod maplob;
+pub mod piaimc;
+pub mod rpc;
+pub mod sensrb;
+pub mod stdidc;
+pub mod use00a;
+
+pub use acftb::Acftb;
+pub use aimidb::Aimidb;
+pub use bandsb::Bandsb;
+pub use blocka::Blocka;
+pub use geolob::Geolob;
+pub use geopsb::Geopsb;
+pub use ichipb::Ichipb;
+pub use maplob::Maplob;
+pub use piaimc::Piaimc;
+pub use rpc::{Rpc, Rpc00a, Rpc00b};
+pub use sensrb::Sensrb;
+pub use stdidc::Stdidc;
+pub use use00a::Use00a;
+
+/// Typed TRE interface definition
+///
+/// Provide implementations for `read()`, `write()`, and `length()`. Conversion
+/// to and from a [RawTre] is defined automatically.
+pub trait Tre
+where
+    Self: Sized + Default + Debug + Clone + Send + Sync + 'static,
+{
+    /// Six character CETAG identifying the extension
+    const TAG: &'static str;
+
+    /// Read the TRE fields from stream
+    ///
+    /// # Parameters
+    ///
+    /// reader: Stream containing only the user defined data of the TRE
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()>;
+
+    /// Write the TRE fields to stream
+    ///
+    /// # Parameters
+    ///
+    /// writer: Stream to which the user defined data is written
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize>;
+
+    /// Length of the user defined data, in bytes
+    fn length(&self) -> usize;
+
+    /// CETAG of the extension
+    fn tag(&self) -> &str {
+        Self::TAG
+    }
+
+    /// Parse the TRE from a raw entry
+    fn from_raw(raw: &RawTre) -> NitfResult<Self> {
+        if raw.cetag != Self::TAG {
+            Err(NitfError::TreError(format!(
+                "cannot read {} as {}",
+                raw.cetag,
+                Self::TAG
+            )))?
+        }
+        let mut reader = Cursor::new(raw.data.as_slice());
+        let mut tre = Self::default();
+        tre.read(&mut reader)?;
+        let n_read = reader.position() as usize;
+        if n_read != raw.cel {
+            Err(NitfError::TreError(format!(
+                "{}: read {n_read} bytes, but CEL is {}",
+                Self::TAG,
+                raw.cel
+            )))?
+        }
+        Ok(tre)
+    }
+
+    /// Serialize the TRE to a raw entry
+    fn to_raw(&self) -> NitfResult<RawTre> {
+        let mut writer = Cursor::new(Vec::with_capacity(self.length()));
+        self.write(&mut writer)?;
+        Ok(RawTre::new(self.tag(), writer.into_inner()))
+    }
+}
+
+/// Object safe view of a decoded TRE, as produced by a [TreRegistry]
+pub trait AnyTre: Debug + Send + Sync {
+    /// CETAG of the extension
+    fn tag(&self) -> &str;
+    /// Serialize the TRE to a raw entry
+    fn to_raw(&self) -> NitfResult<RawTre>;
+    /// Access the concrete type, for use with `downcast_ref()`
+    fn as_any(&self) -> &dyn Any;
+}
+impl<T: Tre> AnyTre for T {
+    fn tag(&self) -> &str {
+        Tre::tag(self)
+    }
+    fn to_raw(&self) -> NitfResult<RawTre> {
+        Tre::to_raw(self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// TRE as decoded by a [TreRegistry]
+#[derive(Debug)]
+pub enum DecodedTre {
+    /// TRE with a registered decoder
+    Typed(Box<dyn AnyTre>),
+    /// TRE without a registered decoder, or which failed to decode
+    Raw(RawTre),
+}
+impl DecodedTre {
+    /// CETAG of the extension
+    pub fn tag(&self) -> &str {
+        match self {
+            Self::Typed(tre) => tre.tag(),
+            Self::Raw(raw) => &raw.cetag,
+        }
+    }
+    /// Get the typed TRE, if this is a `T`
+    pub fn downcast_ref<T: Tre>(&self) -> Option<&T> {
+        match self {
+            Self::Typed(tre) => tre.as_any().downcast_ref(),
+            Self::Raw(_) => None,
+        }
+    }
+    /// Serialize the TRE to a raw entry
+    pub fn to_raw(&self) -> NitfResult<RawTre> {
+        match self {
+            Self::Typed(tre) => tre.to_raw(),
+            Self::Raw(raw) => Ok(raw.clone()),
+        }
+    }
+}
+
+/// Function which decodes a raw entry into a typed TRE
+pub type TreDecoder = Arc<dyn Fn(&RawTre) -> NitfResult<Box<dyn AnyTre>> + Send + Sync>;
+
+/// Mapping of CETAG to TRE decoder
+#[derive(Clone, Default)]
+pub struct TreRegistry {
+    decoders: HashMap<String, TreDecoder>,
+}
+impl TreRegistry {
+    /// Registry without any decoders
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Registry containing the TREs defined in this crate
+    ///
+    /// Typed TREs take precedence over embedded [definition]s with the same tag.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register_definitions(definition::embedded());
+        registry.register::<Acftb>();
+        registry.register::<Aimidb>();
+        registry.register::<Bandsb>();
+        registry.register::<Blocka>();
+        registry.register::<Geolob>();
+        registry.register::<Geopsb>();
+        registry.register::<Ichipb>();
+        registry.register::<Maplob>();
+        registry.register::<Piaimc>();
+        registry.register::<Rpc00a>();
+        registry.register::<Rpc00b>();
+        registry.register::<Sensrb>();
+        registry.register::<Stdidc>();
+        registry.register::<Use00a>();
+        registry
+    }
+    /// Add a typed TRE, replacing any existing decoder for its tag
+    pub fn register<T: Tre>(&mut self) {
+        self.register_decoder(
+            T::TAG,
+            Arc::new(|raw| Ok(Box::new(T::from_raw(raw)?) as Box<dyn AnyTre>)),
+        );
+    }
+    /// Add a decoder for a tag, replacing any existing decoder
+    pub fn register_decoder(&mut self, cetag: &str, decoder: TreDecoder) {
+        self.decoders.insert(cetag.trim().to_string(), decoder);
+    }
+    /// Check if there is a decoder for a tag
+    pub fn contains(&self, cetag: &str) -> bool {
+        self.decoders.contains_key(cetag.trim())
+    }
+    /// Registered tags
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.decoders.keys().map(|k| k.as_str())
+    }
+    /// Decode a raw entry.
+    ///
+    /// Tags without a decoder are returned as [DecodedTre::Raw].
+    pub fn decode(&self, raw: &RawTre) -> NitfResult<DecodedTre> {
+        match self.decoders.get(&raw.cetag) {
+            Some(decoder) => Ok(DecodedTre::Typed(decoder(raw)?)),
+            None => Ok(DecodedTre::Raw(raw.clone())),
+        }
+    }
+}
+impl Debug for TreRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut tags: Vec<_> = self.tags().collect();
+        tags.sort();
+        f.debug_struct("TreRegistry").field("tags", &tags).finish()
+    }
+}
+
+/// Global registry used by [ExtendedSubheader::decode_tres()]
+fn global_registry() -> &'static RwLock<TreRegistry> {
+    static REGISTRY: OnceLock<RwLock<TreRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(TreRegistry::builtin()))
+}
+
+/// Add a typed TRE to the global registry
+pub fn register<T: Tre>() {
+    global_registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .register::<T>();
+}
+
+/// Add a decoder to the global registry
+pub fn register_decoder(cetag: &str, decoder: TreDecoder) {
+    global_registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .register_decoder(cetag, decoder);
+}
+
+/// Get a copy of the global registry
+pub fn registry() -> TreRegistry {
+    global_registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+impl ExtendedSubheader {
+    /// Decode each TRE in the subheader using the global registry.
+    ///
+    /// Entries which fail to decode are logged and returned as
+    /// [DecodedTre::Raw].
+    pub fn decode_tres(&self) -> NitfResult<Vec<DecodedTre>> {
+        let registry = global_registry().read().unwrap_or_else(|e| e.into_inner());
+        self.tres()?
+            .into_iter()
+            .map(|raw| {
+                Ok(registry.decode(&raw).unwrap_or_else(|e| {
+                    warn!("Non-fatal error decoding {}: {e}", raw.cetag);
+                    DecodedTre::Raw(raw)
+                }))
+            })
+            .collect()
+    }
+    /// Read the first TRE matching the tag of `T`, if present
+    pub fn get_tre<T: Tre>(&self) -> NitfResult<Option<T>> {
+        for raw in self.iter_tres() {
+            let raw = raw?;
+            if raw.cetag == T::TAG {
+                return T::from_raw(&raw).map(Some);
+            }
+        }
+        Ok(None)
+    }
+    /// Replace the first TRE matching the tag of `T`, appending if there is none
+    pub fn put_tre<T: Tre>(&mut self, tre: &T) -> NitfResult<()> {
+        self.replace_tre(tre.to_raw()?)?;
+        Ok(())
+    }
+}
+
+// Field helpers for implementing `Tre`
+
+/// Read a field as a trimmed string
+pub fn read_string(reader: &mut impl Read, length: usize, name: &str) -> NitfResult<String> {
+    let mut bytes = vec![0; length];
+    reader
+        .read_exact(&mut bytes)
+        .or(Err(NitfError::ReadFatal(name.to_string())))?;
+    Ok(String::from_utf8_lossy(&bytes).trim().to_string())
+}
+
+/// Read and parse a required field
+pub fn read_value<T: FromStr>(reader: &mut impl Read, length: usize, name: &str) -> NitfResult<T> {
+    let string = read_string(reader, length, name)?;
+    string
+        .parse()
+        .or(Err(NitfError::ParseError(format!("{name}: {string:?}"))))
+}
+
+/// Read and parse an optional field.
+///
+/// Fields which are blank, or filled with `-`, are `None`.
+pub fn read_optional<T: FromStr>(
+    reader: &mut impl Read,
+    length: usize,
+    name: &str,
+) -> NitfResult<Option<T>> {
+    let string = read_string(reader, length, name)?;
+    if string.is_empty() || string.chars().all(|c| c == '-') {
+        return Ok(None);
+    }
+    string
+        .parse()
+        .map(Some)
+        .or(Err(NitfError::ParseError(format!("{name}: {string:?}"))))
+}
+
+/// Write a left justified, space filled field
+pub fn write_string(
+    writer: &mut impl Write,
+    value: &str,
+    length: usize,
+    name: &str,
+) -> NitfResult<usize> {
+    if value.len() > length {
+        Err(NitfError::TreError(format!(
+            "{name}: {value:?} is longer than {length} bytes"
+        )))?
+    }
+    writer.write_all(format!("{value:<length$}").as_bytes())?;
+    Ok(length)
+}
+
+/// Write a right justified, zero filled integer field
+pub fn write_int(
+    writer: &mut impl Write,
+    value: i64,
+    length: usize,
+    name: &str,
+) -> NitfResult<usize> {
+    write_string(writer, &format!("{value:0length$}"), length, name)
+}
+
+/// Write a zero filled decimal field with a fixed number of decimal places.
+///
+/// If `signed`, the value always has a leading `+` or `-`.
+pub fn write_float(
+    writer: &mut impl Write,
+    value: f64,
+    length: usize,
+    decimals: usize,
+    signed: bool,
+    name: &str,
+) -> NitfResult<usize> {
+    let string = match signed {
+        true => format!("{value:+0length$.decimals$}"),
+        false => format!("{value:0length$.decimals$}"),
+    };
+    write_string(writer, &string, length, name)
+}
+
+/// Write an optional integer field, blank filled if `None`
+pub fn write_optional_int(
+    writer: &mut impl Write,
+    value: Option<impl Into<i64>>,
+    length: usize,
+    name: &str,
+) -> NitfResult<usize> {
+    match value {
+        Some(value) => write_int(writer, value.into(), length, name),
+        None => write_string(writer, "", length, name),
+    }
+}
+
+/// Write an optional decimal field, blank filled if `None`
+pub fn write_optional_float(
+    writer: &mut impl Write,
+    value: Option<f64>,
+    length: usize,
+    decimals: usize,
+    signed: bool,
+    name: &str,
+) -> NitfResult<usize> {
+    match value {
+        Some(value) => write_float(writer, value, length, decimals, signed, name),
+        None => write_string(writer, "", length, name),
+    }
+}
+
+/// Write a decimal field with as many decimal places as fit in `length`
+pub fn write_real(
+    writer: &mut impl Write,
+    value: f64,
+    length: usize,
+    name: &str,
+) -> NitfResult<usize> {
+    let mut decimals = length.saturating_sub(2);
+    let string = loop {
+        let string = format!("{value:0length$.decimals$}");
+        if string.len() <= length || decimals == 0 {
+            break string;
+        }
+        decimals -= 1;
+    };
+    write_string(writer, &string, length, name)
+}
+
+/// Write an optional decimal field with as many decimal places as fit,
+/// blank filled if `None`
+pub fn write_optional_real(
+    writer: &mut impl Write,
+    value: Option<f64>,
+    length: usize,
+    name: &str,
+) -> NitfResult<usize> {
+    match value {
+        Some(value) => write_real(writer, value, length, name),
+        None => write_string(writer, "", length, name),
+    }
+}
+
+/// Read a one byte `Y`/`N` flag. Blank flags are `None`
+pub fn read_flag(reader: &mut impl Read, name: &str) -> NitfResult<Option<bool>> {
+    match read_string(reader, 1, name)?.as_str() {
+        "" => Ok(None),
+        "Y" => Ok(Some(true)),
+        "N" => Ok(Some(false)),
+        flag => Err(NitfError::ParseError(format!("{name}: {flag:?}"))),
+    }
+}
+
+/// Write a one byte `Y`/`N` flag, blank filled if `None`
+pub fn write_flag(writer: &mut impl Write, flag: Option<bool>, name: &str) -> NitfResult<usize> {
+    let value = match flag {
+        Some(true) => "Y",
+        Some(false) => "N",
+        None => "",
+    };
+    write_string(writer, value, 1, name)
+}
+
+/// Write a field in the `±n.nnnnnnE±n` exponential notation used by TREs.
+///
+/// The number of mantissa digits is chosen to fill `length`.
+pub fn write_exp(
+    writer: &mut impl Write,
+    value: f64,
+    length: usize,
+    name: &str,
+) -> NitfResult<usize> {
+    let string = format_exp(value, length);
+    write_string(writer, &string, length, name)
+}
+
+/// Write an optional exponential field, blank filled if `None`
+pub fn write_optional_exp(
+    writer: &mut impl Write,
+    value: Option<f64>,
+    length: usize,
+    name: &str,
+) -> NitfResult<usize> {
+    match value {
+        Some(value) => write_exp(writer, value, length, name),
+        None => write_string(writer, "", length, name),
+    }
+}
+
+/// Format a value as `±n.nnnnnnE±n`, filling `length` characters
+pub(crate) fn format_exp(value: f64, length: usize) -> String {
+    // Sign, leading digit, decimal point, 'E', exponent sign, exponent digit
+    let mut decimals = length.saturating_sub(6);
+    loop {
+        let formatted = format!("{:.1$E}", value.abs(), decimals);
+        let (mantissa, exponent) = formatted.split_once('E').unwrap_or((&formatted, "0"));
+        let exponent: i32 = exponent.parse().unwrap_or(0);
+        let sign = if value.is_sign_negative() { '-' } else { '+' };
+        let exp_sign = if exponent < 0 { '-' } else { '+' };
+        let string = format!("{sign}{mantissa}E{exp_sign}{}", exponent.abs());
+        // Multi-digit exponents take space from the mantissa
+        if string.len() <= length || decimals == 0 {
+            return string;
+        }
+        decimals -= 1;
+    }
+}
+
+/// Raw TRE with each value left justified in a field of its length, and the
+/// data it is written back as, with fields of `-` left blank
+#[cfg(test)]
+pub(crate) fn test_tre(cetag: &str, fields: &[(&str, usize)]) -> (RawTre, Vec<u8>) {
+    let mut data = String::new();
+    let mut written = String::new();
+    for (value, length) in fields {
+        data += &format!("{value:<length$}");
+        let value = match value.chars().all(|c| c == '-') {
+            true => "",
+            false => value,
+        };
+        written += &format!("{value:<length$}");
+    }
+    (RawTre::new(cetag, data.into_bytes()), written.into_bytes())
+}
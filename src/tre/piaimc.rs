@@ -0,0 +1,149 @@
+//! PIAIMC profile for imagery access image TRE
+use std::io::{Read, Seek, Write};
+
+use crate::tre::{
+    read_flag, read_optional, read_string, write_flag, write_optional_float, write_optional_int,
+    write_string, Tre,
+};
+use crate::NitfResult;
+
+/// PIAIMC TRE
+///
+/// Numeric fields are `None` when blank or filled with `-`, and `Y`/`N` flags
+/// are `None` when blank.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Piaimc {
+    /// Cloud Cover, percent. Unknown cloud cover is stored as 999
+    pub cloudcvr: Option<u16>,
+    /// Standard Radiometric Product
+    pub srp: Option<bool>,
+    /// Sensor Mode
+    pub sensmode: String,
+    /// Sensor Name
+    pub sensname: String,
+    /// Source
+    pub source: String,
+    /// Compression Generation
+    pub comgen: Option<u8>,
+    /// Subjective Quality
+    pub subqual: String,
+    /// PIA Mission Number
+    pub piamsnnum: String,
+    /// Camera Specs
+    pub camspecs: String,
+    /// Project ID Code
+    pub projid: String,
+    /// Generation
+    pub generation: Option<u8>,
+    /// Exploitation Support Data
+    pub esd: Option<bool>,
+    /// Other Conditions
+    pub othercond: String,
+    /// Mean GSD, inches
+    pub meangsd: Option<f64>,
+    /// Image Datum
+    pub idatum: String,
+    /// Image Ellipsoid
+    pub iellip: String,
+    /// Image Processing Level Code
+    pub preproc: String,
+    /// Image Projection System
+    pub iproj: String,
+    /// Satellite Track, path and row
+    pub sattrack: String,
+}
+
+impl Tre for Piaimc {
+    const TAG: &'static str = "PIAIMC";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.cloudcvr = read_optional(reader, 3, "CLOUDCVR")?;
+        self.srp = read_flag(reader, "SRP")?;
+        self.sensmode = read_string(reader, 12, "SENSMODE")?;
+        self.sensname = read_string(reader, 18, "SENSNAME")?;
+        self.source = read_string(reader, 255, "SOURCE")?;
+        self.comgen = read_optional(reader, 2, "COMGEN")?;
+        self.subqual = read_string(reader, 1, "SUBQUAL")?;
+        self.piamsnnum = read_string(reader, 7, "PIAMSNNUM")?;
+        self.camspecs = read_string(reader, 32, "CAMSPECS")?;
+        self.projid = read_string(reader, 2, "PROJID")?;
+        self.generation = read_optional(reader, 1, "GENERATION")?;
+        self.esd = read_flag(reader, "ESD")?;
+        self.othercond = read_string(reader, 2, "OTHERCOND")?;
+        self.meangsd = read_optional(reader, 7, "MEANGSD")?;
+        self.idatum = read_string(reader, 3, "IDATUM")?;
+        self.iellip = read_string(reader, 3, "IELLIP")?;
+        self.preproc = read_string(reader, 2, "PREPROC")?;
+        self.iproj = read_string(reader, 2, "IPROJ")?;
+        self.sattrack = read_string(reader, 8, "SATTRACK")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_optional_int(writer, self.cloudcvr, 3, "CLOUDCVR")?;
+        bytes_written += write_flag(writer, self.srp, "SRP")?;
+        bytes_written += write_string(writer, &self.sensmode, 12, "SENSMODE")?;
+        bytes_written += write_string(writer, &self.sensname, 18, "SENSNAME")?;
+        bytes_written += write_string(writer, &self.source, 255, "SOURCE")?;
+        bytes_written += write_optional_int(writer, self.comgen, 2, "COMGEN")?;
+        bytes_written += write_string(writer, &self.subqual, 1, "SUBQUAL")?;
+        bytes_written += write_string(writer, &self.piamsnnum, 7, "PIAMSNNUM")?;
+        bytes_written += write_string(writer, &self.camspecs, 32, "CAMSPECS")?;
+        bytes_written += write_string(writer, &self.projid, 2, "PROJID")?;
+        bytes_written += write_optional_int(writer, self.generation, 1, "GENERATION")?;
+        bytes_written += write_flag(writer, self.esd, "ESD")?;
+        bytes_written += write_string(writer, &self.othercond, 2, "OTHERCOND")?;
+        bytes_written += write_optional_float(writer, self.meangsd, 7, 1, false, "MEANGSD")?;
+        bytes_written += write_string(writer, &self.idatum, 3, "IDATUM")?;
+        bytes_written += write_string(writer, &self.iellip, 3, "IELLIP")?;
+        bytes_written += write_string(writer, &self.preproc, 2, "PREPROC")?;
+        bytes_written += write_string(writer, &self.iproj, 2, "IPROJ")?;
+        bytes_written += write_string(writer, &self.sattrack, 8, "SATTRACK")?;
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        362
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tre::test_tre;
+
+    #[test]
+    fn piaimc_round_trip() {
+        let (raw, written) = test_tre(
+            Piaimc::TAG,
+            &[
+                ("---", 3),
+                ("Y", 1),
+                ("FRAMING", 12),
+                ("SENSOR", 18),
+                ("SOURCE", 255),
+                ("", 2),
+                ("P", 1),
+                ("MSN0001", 7),
+                ("CAMERA", 32),
+                ("AA", 2),
+                ("1", 1),
+                ("", 1),
+                ("NA", 2),
+                ("00012.5", 7),
+                ("WGE", 3),
+                ("WE", 3),
+                ("", 2),
+                ("", 2),
+                ("", 8),
+            ],
+        );
+        let piaimc = Piaimc::from_raw(&raw).unwrap();
+        assert_eq!(raw.cel, piaimc.length());
+        assert_eq!(piaimc.cloudcvr, None);
+        assert_eq!(piaimc.srp, Some(true));
+        assert_eq!(piaimc.comgen, None);
+        assert_eq!(piaimc.generation, Some(1));
+        assert_eq!(piaimc.esd, None);
+        assert_eq!(piaimc.meangsd, Some(12.5));
+        assert_eq!(piaimc.to_raw().unwrap().data, written);
+    }
+}
@@ -0,0 +1,443 @@
+//! Rational polynomial camera model TREs, RPC00A and RPC00B
+use std::io::{Read, Seek, Write};
+use std::ops::{Deref, DerefMut};
+
+use crate::tre::{read_optional, read_value, write_exp, write_float, write_int, write_string, Tre};
+use crate::{NitfError, NitfResult};
+
+/// Number of coefficients in each polynomial
+pub const N_COEFF: usize = 20;
+
+/// Ordering of the polynomial terms
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum RpcType {
+    /// Term order used by RPC00A
+    A,
+    #[default]
+    /// Term order used by RPC00B
+    B,
+}
+
+/// Rational polynomial coefficients, shared by [Rpc00a] and [Rpc00b]
+///
+/// Latitude and longitude are in decimal degrees, height is in meters above
+/// the WGS-84 ellipsoid, and image coordinates are in full image pixels.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Rpc {
+    /// Term ordering of the coefficients
+    pub kind: RpcType,
+    /// Success flag
+    pub success: bool,
+    /// Error - Bias, meters. `None` if unavailable
+    pub err_bias: Option<f64>,
+    /// Error - Random, meters. `None` if unavailable
+    pub err_rand: Option<f64>,
+    /// Line Offset
+    pub line_off: u32,
+    /// Sample Offset
+    pub samp_off: u32,
+    /// Geodetic Latitude Offset
+    pub lat_off: f64,
+    /// Geodetic Longitude Offset
+    pub long_off: f64,
+    /// Geodetic Height Offset
+    pub height_off: i32,
+    /// Line Scale
+    pub line_scale: u32,
+    /// Sample Scale
+    pub samp_scale: u32,
+    /// Geodetic Latitude Scale
+    pub lat_scale: f64,
+    /// Geodetic Longitude Scale
+    pub long_scale: f64,
+    /// Geodetic Height Scale
+    pub height_scale: i32,
+    /// Line Numerator Coefficients
+    pub line_num_coeff: [f64; N_COEFF],
+    /// Line Denominator Coefficients
+    pub line_den_coeff: [f64; N_COEFF],
+    /// Sample Numerator Coefficients
+    pub samp_num_coeff: [f64; N_COEFF],
+    /// Sample Denominator Coefficients
+    pub samp_den_coeff: [f64; N_COEFF],
+}
+
+/// RPC00A TRE
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rpc00a(pub Rpc);
+
+/// RPC00B TRE
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Rpc00b(pub Rpc);
+
+impl Default for Rpc00a {
+    fn default() -> Self {
+        Self(Rpc {
+            kind: RpcType::A,
+            ..Default::default()
+        })
+    }
+}
+
+impl RpcType {
+    /// Evaluate the polynomial terms for normalized coordinates
+    fn terms(&self, p: f64, l: f64, h: f64) -> [f64; N_COEFF] {
+        match self {
+            Self::A => [
+                1.0,
+                l,
+                p,
+                h,
+                l * p,
+                l * h,
+                p * h,
+                p * l * h,
+                l * l,
+                p * p,
+                h * h,
+                l * l * l,
+                l * p * p,
+                l * h * h,
+                l * l * p,
+                p * p * p,
+                p * h * h,
+                l * l * h,
+                p * p * h,
+                h * h * h,
+            ],
+            Self::B => [
+                1.0,
+                l,
+                p,
+                h,
+                l * p,
+                l * h,
+                p * h,
+                l * l,
+                p * p,
+                h * h,
+                p * l * h,
+                l * l * l,
+                l * p * p,
+                l * h * h,
+                l * l * p,
+                p * p * p,
+                p * h * h,
+                l * l * h,
+                p * p * h,
+                h * h * h,
+            ],
+        }
+    }
+}
+
+fn dot(coeff: &[f64; N_COEFF], terms: &[f64; N_COEFF]) -> f64 {
+    coeff.iter().zip(terms).map(|(c, t)| c * t).sum()
+}
+
+impl Rpc {
+    /// Error if a normalization scale is zero, which would make every
+    /// projection NaN or infinite
+    fn check_scales(&self) -> NitfResult<()> {
+        for (scale, name) in [
+            (self.line_scale as f64, "LINE_SCALE"),
+            (self.samp_scale as f64, "SAMP_SCALE"),
+            (self.lat_scale, "LAT_SCALE"),
+            (self.long_scale, "LONG_SCALE"),
+            (self.height_scale as f64, "HEIGHT_SCALE"),
+        ] {
+            if scale == 0.0 {
+                Err(NitfError::Value(format!("RPC {name} is zero")))?
+            }
+        }
+        Ok(())
+    }
+
+    /// Project a ground point to image coordinates
+    ///
+    /// # Parameters
+    ///
+    /// lat, lon: Geodetic coordinates, in degrees
+    ///
+    /// height: Height above the ellipsoid, in meters
+    ///
+    /// Returns the `(row, column)` of the point in full image pixels
+    pub fn ground_to_image(&self, lat: f64, lon: f64, height: f64) -> NitfResult<(f64, f64)> {
+        self.check_scales()?;
+        let (row, col) = self.normalized_ground_to_image(
+            (lat - self.lat_off) / self.lat_scale,
+            (lon - self.long_off) / self.long_scale,
+            (height - self.height_off as f64) / self.height_scale as f64,
+        );
+        Ok((
+            row * self.line_scale as f64 + self.line_off as f64,
+            col * self.samp_scale as f64 + self.samp_off as f64,
+        ))
+    }
+
+    fn normalized_ground_to_image(&self, p: f64, l: f64, h: f64) -> (f64, f64) {
+        let terms = self.kind.terms(p, l, h);
+        let row = dot(&self.line_num_coeff, &terms) / dot(&self.line_den_coeff, &terms);
+        let col = dot(&self.samp_num_coeff, &terms) / dot(&self.samp_den_coeff, &terms);
+        (row, col)
+    }
+
+    /// Project image coordinates to the ground at a given height
+    ///
+    /// The inverse is found iteratively with Newton's method, starting from the
+    /// ground offset point.
+    ///
+    /// # Parameters
+    ///
+    /// row, col: Full image pixel coordinates
+    ///
+    /// height: Height above the ellipsoid, in meters
+    ///
+    /// Returns the `(latitude, longitude)` of the point in degrees
+    pub fn image_to_ground(&self, row: f64, col: f64, height: f64) -> NitfResult<(f64, f64)> {
+        const MAX_ITER: usize = 50;
+        self.check_scales()?;
+        // Normalized image space tolerance, well below a thousandth of a pixel
+        let tolerance = 1e-6 / (self.line_scale.max(self.samp_scale).max(1) as f64);
+        let step = 1e-6;
+
+        let target_row = (row - self.line_off as f64) / self.line_scale as f64;
+        let target_col = (col - self.samp_off as f64) / self.samp_scale as f64;
+        let h = (height - self.height_off as f64) / self.height_scale as f64;
+        let (mut p, mut l) = (0.0, 0.0);
+        for _ in 0..MAX_ITER {
+            let (r0, c0) = self.normalized_ground_to_image(p, l, h);
+            let (dr, dc) = (target_row - r0, target_col - c0);
+            if dr.abs() < tolerance && dc.abs() < tolerance {
+                return Ok((
+                    p * self.lat_scale + self.lat_off,
+                    l * self.long_scale + self.long_off,
+                ));
+            }
+            // Numerical jacobian of (row, col) with respect to (p, l)
+            let (rp, cp) = self.normalized_ground_to_image(p + step, l, h);
+            let (rl, cl) = self.normalized_ground_to_image(p, l + step, h);
+            let (drdp, dcdp) = ((rp - r0) / step, (cp - c0) / step);
+            let (drdl, dcdl) = ((rl - r0) / step, (cl - c0) / step);
+            let det = drdp * dcdl - drdl * dcdp;
+            if det == 0.0 || !det.is_finite() {
+                break;
+            }
+            p += (dcdl * dr - drdl * dc) / det;
+            l += (drdp * dc - dcdp * dr) / det;
+        }
+        Err(NitfError::Fatal(format!(
+            "RPC image to ground did not converge for ({row}, {col})"
+        )))
+    }
+
+    fn read_fields(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        self.success = read_value::<u8>(reader, 1, "SUCCESS")? == 1;
+        self.err_bias = read_optional(reader, 7, "ERR_BIAS")?;
+        self.err_rand = read_optional(reader, 7, "ERR_RAND")?;
+        self.line_off = read_value(reader, 6, "LINE_OFF")?;
+        self.samp_off = read_value(reader, 5, "SAMP_OFF")?;
+        self.lat_off = read_value(reader, 8, "LAT_OFF")?;
+        self.long_off = read_value(reader, 9, "LONG_OFF")?;
+        self.height_off = read_value(reader, 5, "HEIGHT_OFF")?;
+        self.line_scale = read_value(reader, 6, "LINE_SCALE")?;
+        self.samp_scale = read_value(reader, 5, "SAMP_SCALE")?;
+        self.lat_scale = read_value(reader, 8, "LAT_SCALE")?;
+        self.long_scale = read_value(reader, 9, "LONG_SCALE")?;
+        self.height_scale = read_value(reader, 5, "HEIGHT_SCALE")?;
+        for coeff in self.line_num_coeff.iter_mut() {
+            *coeff = read_value(reader, 12, "LINE_NUM_COEFF")?;
+        }
+        for coeff in self.line_den_coeff.iter_mut() {
+            *coeff = read_value(reader, 12, "LINE_DEN_COEFF")?;
+        }
+        for coeff in self.samp_num_coeff.iter_mut() {
+            *coeff = read_value(reader, 12, "SAMP_NUM_COEFF")?;
+        }
+        for coeff in self.samp_den_coeff.iter_mut() {
+            *coeff = read_value(reader, 12, "SAMP_DEN_COEFF")?;
+        }
+        Ok(())
+    }
+
+    fn write_fields(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_int(writer, self.success as i64, 1, "SUCCESS")?;
+        bytes_written += match self.err_bias {
+            Some(err) => write_float(writer, err, 7, 2, false, "ERR_BIAS")?,
+            None => write_string(writer, "", 7, "ERR_BIAS")?,
+        };
+        bytes_written += match self.err_rand {
+            Some(err) => write_float(writer, err, 7, 2, false, "ERR_RAND")?,
+            None => write_string(writer, "", 7, "ERR_RAND")?,
+        };
+        bytes_written += write_int(writer, self.line_off.into(), 6, "LINE_OFF")?;
+        bytes_written += write_int(writer, self.samp_off.into(), 5, "SAMP_OFF")?;
+        bytes_written += write_float(writer, self.lat_off, 8, 4, true, "LAT_OFF")?;
+        bytes_written += write_float(writer, self.long_off, 9, 4, true, "LONG_OFF")?;
+        bytes_written += write_float(writer, self.height_off.into(), 5, 0, true, "HEIGHT_OFF")?;
+        bytes_written += write_int(writer, self.line_scale.into(), 6, "LINE_SCALE")?;
+        bytes_written += write_int(writer, self.samp_scale.into(), 5, "SAMP_SCALE")?;
+        bytes_written += write_float(writer, self.lat_scale, 8, 4, true, "LAT_SCALE")?;
+        bytes_written += write_float(writer, self.long_scale, 9, 4, true, "LONG_SCALE")?;
+        bytes_written += write_float(writer, self.height_scale.into(), 5, 0, true, "HEIGHT_SCALE")?;
+        for coeff in self.line_num_coeff {
+            bytes_written += write_exp(writer, coeff, 12, "LINE_NUM_COEFF")?;
+        }
+        for coeff in self.line_den_coeff {
+            bytes_written += write_exp(writer, coeff, 12, "LINE_DEN_COEFF")?;
+        }
+        for coeff in self.samp_num_coeff {
+            bytes_written += write_exp(writer, coeff, 12, "SAMP_NUM_COEFF")?;
+        }
+        for coeff in self.samp_den_coeff {
+            bytes_written += write_exp(writer, coeff, 12, "SAMP_DEN_COEFF")?;
+        }
+        Ok(bytes_written)
+    }
+
+    /// Length of the TRE data
+    pub fn length(&self) -> usize {
+        81 + 4 * N_COEFF * 12
+    }
+}
+
+impl Tre for Rpc00a {
+    const TAG: &'static str = "RPC00A";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.0.kind = RpcType::A;
+        self.0.read_fields(reader)
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        self.0.write_fields(writer)
+    }
+    fn length(&self) -> usize {
+        self.0.length()
+    }
+}
+impl Tre for Rpc00b {
+    const TAG: &'static str = "RPC00B";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.0.kind = RpcType::B;
+        self.0.read_fields(reader)
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        self.0.write_fields(writer)
+    }
+    fn length(&self) -> usize {
+        self.0.length()
+    }
+}
+impl Deref for Rpc00a {
+    type Target = Rpc;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl DerefMut for Rpc00a {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+impl Deref for Rpc00b {
+    type Target = Rpc;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl DerefMut for Rpc00b {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Model with a few terms, including L^2 which RPC00A orders differently
+    fn synthetic(kind: RpcType) -> Rpc {
+        let mut rpc = Rpc {
+            kind,
+            success: true,
+            err_bias: Some(1.5),
+            err_rand: None,
+            line_off: 5000,
+            samp_off: 4000,
+            lat_off: 35.0,
+            long_off: -117.0,
+            height_off: 100,
+            line_scale: 5000,
+            samp_scale: 4000,
+            lat_scale: 0.5,
+            long_scale: 0.5,
+            height_scale: 500,
+            ..Default::default()
+        };
+        rpc.line_num_coeff[0] = 0.1;
+        rpc.line_num_coeff[2] = -1.0;
+        rpc.line_num_coeff[7] = 0.05;
+        rpc.line_den_coeff[0] = 1.0;
+        rpc.line_den_coeff[3] = 0.01;
+        rpc.samp_num_coeff[0] = -0.02;
+        rpc.samp_num_coeff[1] = 1.0;
+        rpc.samp_num_coeff[2] = 0.1;
+        rpc.samp_den_coeff[0] = 1.0;
+        rpc
+    }
+
+    #[test]
+    fn evaluate_rpc00b() {
+        let rpc = synthetic(RpcType::B);
+        let (row, col) = rpc.ground_to_image(35.1, -116.8, 350.0).unwrap();
+        // P = 0.2, L = 0.4, H = 0.5
+        let line = (0.1 - 0.2 + 0.05 * 0.4 * 0.4) / (1.0 + 0.01 * 0.5);
+        let samp = -0.02 + 0.4 + 0.1 * 0.2;
+        assert!((row - (line * 5000.0 + 5000.0)).abs() < 1e-9);
+        assert!((col - (samp * 4000.0 + 4000.0)).abs() < 1e-9);
+
+        // RPC00A has P*L*H as its eighth term
+        let rpc = synthetic(RpcType::A);
+        let (row, _) = rpc.ground_to_image(35.1, -116.8, 350.0).unwrap();
+        let line = (0.1 - 0.2 + 0.05 * 0.2 * 0.4 * 0.5) / (1.0 + 0.01 * 0.5);
+        assert!((row - (line * 5000.0 + 5000.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ground_image_ground() {
+        for kind in [RpcType::A, RpcType::B] {
+            let rpc = synthetic(kind);
+            for (lat, lon, height) in [(35.1, -116.8, 350.0), (34.7, -117.3, -50.0)] {
+                let (row, col) = rpc.ground_to_image(lat, lon, height).unwrap();
+                let (lat2, lon2) = rpc.image_to_ground(row, col, height).unwrap();
+                assert!((lat2 - lat).abs() < 1e-8, "{kind:?} {lat} {lat2}");
+                assert!((lon2 - lon).abs() < 1e-8, "{kind:?} {lon} {lon2}");
+            }
+        }
+    }
+
+    #[test]
+    fn zero_scale() {
+        let mut rpc = synthetic(RpcType::B);
+        rpc.height_scale = 0;
+        assert!(rpc.ground_to_image(35.0, -117.0, 0.0).is_err());
+        assert!(rpc.image_to_ground(5000.0, 4000.0, 0.0).is_err());
+    }
+
+    #[test]
+    fn rpc_round_trip() {
+        let rpc00a = Rpc00a(synthetic(RpcType::A));
+        let raw = rpc00a.to_raw().unwrap();
+        assert_eq!(raw.cetag, "RPC00A");
+        assert_eq!(raw.cel, 1041);
+        assert_eq!(&raw.data[..21], b"10001.50       005000");
+        assert_eq!(Rpc00a::from_raw(&raw).unwrap(), rpc00a);
+
+        let rpc00b = Rpc00b(synthetic(RpcType::B));
+        let raw = rpc00b.to_raw().unwrap();
+        assert_eq!(raw.cetag, "RPC00B");
+        let decoded = Rpc00b::from_raw(&raw).unwrap();
+        assert_eq!(decoded, rpc00b);
+        assert_eq!(decoded.to_raw().unwrap().data, raw.data);
+    }
+}
@@ -0,0 +1,1236 @@
+//! SENSRB general electro-optical sensor model TRE
+//!
+//! SENSRB is made of fifteen modules. Modules 5 and 6 are always present,
+//! modules 1 to 4 and 7 to 10 are present when their `Y`/`N` flag is set, and
+//! modules 11 to 15 are repeated sets preceded by a count. Modules which are
+//! not present are `None` or empty.
+//!
+//! Numeric fields are `None` when blank or filled with `-`. Reals are written
+//! with as many decimal places as fit in the field, and distortion
+//! coefficients in exponential notation.
+use std::io::{Read, Seek, Write};
+
+use crate::tre::{
+    read_flag, read_optional, read_string, read_value, write_flag, write_int, write_optional_exp,
+    write_optional_int, write_optional_real, write_string, Tre,
+};
+use crate::{NitfError, NitfResult};
+
+/// Largest number of image formation transform parameters
+pub const MAX_TRANSFORM_PARAMS: usize = 8;
+
+/// SENSRB TRE
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Sensrb {
+    /// Module 1
+    pub general_data: Option<GeneralData>,
+    /// Module 2
+    pub sensor_array_data: Option<SensorArrayData>,
+    /// Module 3
+    pub sensor_calibration_data: Option<SensorCalibrationData>,
+    /// Module 4
+    pub image_formation_data: Option<ImageFormationData>,
+    /// Module 5
+    pub reference_time: ReferenceTime,
+    /// Module 6
+    pub sensor_position: SensorPosition,
+    /// Module 7
+    pub attitude_euler_angles: Option<AttitudeEulerAngles>,
+    /// Module 8
+    pub attitude_unit_vectors: Option<AttitudeUnitVectors>,
+    /// Module 9
+    pub attitude_quaternion: Option<AttitudeQuaternion>,
+    /// Module 10
+    pub sensor_velocity: Option<SensorVelocity>,
+    /// Module 11
+    pub point_sets: Vec<PointSet>,
+    /// Module 12
+    pub time_stamped_data: Vec<TimeStampedSet>,
+    /// Module 13
+    pub pixel_referenced_data: Vec<PixelReferencedSet>,
+    /// Module 14
+    pub uncertainty_data: Vec<Uncertainty>,
+    /// Module 15
+    pub additional_parameters: Vec<AdditionalParameter>,
+}
+
+/// SENSRB Module 1, general data
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GeneralData {
+    /// Sensor Name
+    pub sensor: String,
+    /// Sensor URI
+    pub sensor_uri: String,
+    /// Platform Common Name
+    pub platform: String,
+    /// Platform URI
+    pub platform_uri: String,
+    /// Operation Domain
+    pub operation_domain: String,
+    /// Content Level
+    pub content_level: Option<u32>,
+    /// Geodetic Reference System
+    pub geodetic_system: String,
+    /// Geodetic Coordinate Type, `G` or `C`
+    pub geodetic_type: String,
+    /// Elevation and Altitude Datum
+    pub elevation_datum: String,
+    /// Length Unit
+    pub length_unit: String,
+    /// Angular Unit
+    pub angular_unit: String,
+    /// Start Date, `CCYYMMDD`
+    pub start_date: String,
+    /// Start Time, seconds from midnight
+    pub start_time: Option<f64>,
+    /// End Date, `CCYYMMDD`
+    pub end_date: String,
+    /// End Time, seconds from midnight
+    pub end_time: Option<f64>,
+    /// Generation Count
+    pub generation_count: Option<u32>,
+    /// Generation Date, `CCYYMMDD`
+    pub generation_date: String,
+    /// Generation Time, `hhmmss.sss`
+    pub generation_time: String,
+}
+impl GeneralData {
+    const LENGTH: usize = 203;
+    fn read(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        self.sensor = read_string(reader, 25, "SENSOR")?;
+        self.sensor_uri = read_string(reader, 32, "SENSOR_URI")?;
+        self.platform = read_string(reader, 25, "PLATFORM")?;
+        self.platform_uri = read_string(reader, 32, "PLATFORM_URI")?;
+        self.operation_domain = read_string(reader, 10, "OPERATION_DOMAIN")?;
+        self.content_level = read_optional(reader, 1, "CONTENT_LEVEL")?;
+        self.geodetic_system = read_string(reader, 5, "GEODETIC_SYSTEM")?;
+        self.geodetic_type = read_string(reader, 1, "GEODETIC_TYPE")?;
+        self.elevation_datum = read_string(reader, 3, "ELEVATION_DATUM")?;
+        self.length_unit = read_string(reader, 2, "LENGTH_UNIT")?;
+        self.angular_unit = read_string(reader, 3, "ANGULAR_UNIT")?;
+        self.start_date = read_string(reader, 8, "START_DATE")?;
+        self.start_time = read_optional(reader, 14, "START_TIME")?;
+        self.end_date = read_string(reader, 8, "END_DATE")?;
+        self.end_time = read_optional(reader, 14, "END_TIME")?;
+        self.generation_count = read_optional(reader, 2, "GENERATION_COUNT")?;
+        self.generation_date = read_string(reader, 8, "GENERATION_DATE")?;
+        self.generation_time = read_string(reader, 10, "GENERATION_TIME")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_string(writer, &self.sensor, 25, "SENSOR")?;
+        bytes_written += write_string(writer, &self.sensor_uri, 32, "SENSOR_URI")?;
+        bytes_written += write_string(writer, &self.platform, 25, "PLATFORM")?;
+        bytes_written += write_string(writer, &self.platform_uri, 32, "PLATFORM_URI")?;
+        bytes_written += write_string(writer, &self.operation_domain, 10, "OPERATION_DOMAIN")?;
+        bytes_written += write_optional_int(writer, self.content_level, 1, "CONTENT_LEVEL")?;
+        bytes_written += write_string(writer, &self.geodetic_system, 5, "GEODETIC_SYSTEM")?;
+        bytes_written += write_string(writer, &self.geodetic_type, 1, "GEODETIC_TYPE")?;
+        bytes_written += write_string(writer, &self.elevation_datum, 3, "ELEVATION_DATUM")?;
+        bytes_written += write_string(writer, &self.length_unit, 2, "LENGTH_UNIT")?;
+        bytes_written += write_string(writer, &self.angular_unit, 3, "ANGULAR_UNIT")?;
+        bytes_written += write_string(writer, &self.start_date, 8, "START_DATE")?;
+        bytes_written += write_optional_real(writer, self.start_time, 14, "START_TIME")?;
+        bytes_written += write_string(writer, &self.end_date, 8, "END_DATE")?;
+        bytes_written += write_optional_real(writer, self.end_time, 14, "END_TIME")?;
+        bytes_written += write_optional_int(writer, self.generation_count, 2, "GENERATION_COUNT")?;
+        bytes_written += write_string(writer, &self.generation_date, 8, "GENERATION_DATE")?;
+        bytes_written += write_string(writer, &self.generation_time, 10, "GENERATION_TIME")?;
+        Ok(bytes_written)
+    }
+}
+
+/// SENSRB Module 2, sensor array data
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SensorArrayData {
+    /// Detection Type
+    pub detection: String,
+    /// Number of Detectors Used in Row
+    pub row_detectors: Option<u32>,
+    /// Number of Detectors Used in Column
+    pub column_detectors: Option<u32>,
+    /// Length of Detector Array in Row Direction
+    pub row_metric: Option<f64>,
+    /// Length of Detector Array in Column Direction
+    pub column_metric: Option<f64>,
+    /// Focal Length
+    pub focal_length: Option<f64>,
+    /// Field of View along Sensor Array Row
+    pub row_fov: Option<f64>,
+    /// Field of View along Sensor Array Column
+    pub column_fov: Option<f64>,
+    /// Focal Length is Calibrated
+    pub calibrated: Option<bool>,
+}
+impl SensorArrayData {
+    const LENGTH: usize = 77;
+    fn read(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        self.detection = read_string(reader, 20, "DETECTION")?;
+        self.row_detectors = read_optional(reader, 8, "ROW_DETECTORS")?;
+        self.column_detectors = read_optional(reader, 8, "COLUMN_DETECTORS")?;
+        self.row_metric = read_optional(reader, 8, "ROW_METRIC")?;
+        self.column_metric = read_optional(reader, 8, "COLUMN_METRIC")?;
+        self.focal_length = read_optional(reader, 8, "FOCAL_LENGTH")?;
+        self.row_fov = read_optional(reader, 8, "ROW_FOV")?;
+        self.column_fov = read_optional(reader, 8, "COLUMN_FOV")?;
+        self.calibrated = read_flag(reader, "CALIBRATED")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_string(writer, &self.detection, 20, "DETECTION")?;
+        bytes_written += write_optional_int(writer, self.row_detectors, 8, "ROW_DETECTORS")?;
+        bytes_written += write_optional_int(writer, self.column_detectors, 8, "COLUMN_DETECTORS")?;
+        bytes_written += write_optional_real(writer, self.row_metric, 8, "ROW_METRIC")?;
+        bytes_written += write_optional_real(writer, self.column_metric, 8, "COLUMN_METRIC")?;
+        bytes_written += write_optional_real(writer, self.focal_length, 8, "FOCAL_LENGTH")?;
+        bytes_written += write_optional_real(writer, self.row_fov, 8, "ROW_FOV")?;
+        bytes_written += write_optional_real(writer, self.column_fov, 8, "COLUMN_FOV")?;
+        bytes_written += write_flag(writer, self.calibrated, "CALIBRATED")?;
+        Ok(bytes_written)
+    }
+}
+
+/// SENSRB Module 3, sensor calibration data
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SensorCalibrationData {
+    /// Calibration Unit System
+    pub calibration_unit: String,
+    /// Principal Point Offset, x
+    pub principal_point_offset_x: Option<f64>,
+    /// Principal Point Offset, y
+    pub principal_point_offset_y: Option<f64>,
+    /// Radial Distortion Coefficient 1
+    pub radial_distort_1: Option<f64>,
+    /// Radial Distortion Coefficient 2
+    pub radial_distort_2: Option<f64>,
+    /// Radial Distortion Coefficient 3
+    pub radial_distort_3: Option<f64>,
+    /// Radial Distortion Fit Limit
+    pub radial_distort_limit: Option<f64>,
+    /// Decentering Distortion Coefficient 1
+    pub decent_distort_1: Option<f64>,
+    /// Decentering Distortion Coefficient 2
+    pub decent_distort_2: Option<f64>,
+    /// Affinity Distortion Coefficient 1
+    pub affinity_distort_1: Option<f64>,
+    /// Affinity Distortion Coefficient 2
+    pub affinity_distort_2: Option<f64>,
+    /// Calibration Date, `CCYYMMDD`
+    pub calibration_date: String,
+}
+impl SensorCalibrationData {
+    const LENGTH: usize = 121;
+    fn read(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        self.calibration_unit = read_string(reader, 2, "CALIBRATION_UNIT")?;
+        self.principal_point_offset_x = read_optional(reader, 9, "PRINCIPAL_POINT_OFFSET_X")?;
+        self.principal_point_offset_y = read_optional(reader, 9, "PRINCIPAL_POINT_OFFSET_Y")?;
+        self.radial_distort_1 = read_optional(reader, 12, "RADIAL_DISTORT_1")?;
+        self.radial_distort_2 = read_optional(reader, 12, "RADIAL_DISTORT_2")?;
+        self.radial_distort_3 = read_optional(reader, 12, "RADIAL_DISTORT_3")?;
+        self.radial_distort_limit = read_optional(reader, 9, "RADIAL_DISTORT_LIMIT")?;
+        self.decent_distort_1 = read_optional(reader, 12, "DECENT_DISTORT_1")?;
+        self.decent_distort_2 = read_optional(reader, 12, "DECENT_DISTORT_2")?;
+        self.affinity_distort_1 = read_optional(reader, 12, "AFFINITY_DISTORT_1")?;
+        self.affinity_distort_2 = read_optional(reader, 12, "AFFINITY_DISTORT_2")?;
+        self.calibration_date = read_string(reader, 8, "CALIBRATION_DATE")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_string(writer, &self.calibration_unit, 2, "CALIBRATION_UNIT")?;
+        bytes_written += write_optional_real(
+            writer,
+            self.principal_point_offset_x,
+            9,
+            "PRINCIPAL_POINT_OFFSET_X",
+        )?;
+        bytes_written += write_optional_real(
+            writer,
+            self.principal_point_offset_y,
+            9,
+            "PRINCIPAL_POINT_OFFSET_Y",
+        )?;
+        bytes_written += write_optional_exp(writer, self.radial_distort_1, 12, "RADIAL_DISTORT_1")?;
+        bytes_written += write_optional_exp(writer, self.radial_distort_2, 12, "RADIAL_DISTORT_2")?;
+        bytes_written += write_optional_exp(writer, self.radial_distort_3, 12, "RADIAL_DISTORT_3")?;
+        bytes_written +=
+            write_optional_real(writer, self.radial_distort_limit, 9, "RADIAL_DISTORT_LIMIT")?;
+        bytes_written += write_optional_exp(writer, self.decent_distort_1, 12, "DECENT_DISTORT_1")?;
+        bytes_written += write_optional_exp(writer, self.decent_distort_2, 12, "DECENT_DISTORT_2")?;
+        bytes_written +=
+            write_optional_exp(writer, self.affinity_distort_1, 12, "AFFINITY_DISTORT_1")?;
+        bytes_written +=
+            write_optional_exp(writer, self.affinity_distort_2, 12, "AFFINITY_DISTORT_2")?;
+        bytes_written += write_string(writer, &self.calibration_date, 8, "CALIBRATION_DATE")?;
+        Ok(bytes_written)
+    }
+}
+
+/// SENSRB Module 4, image formation data
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImageFormationData {
+    /// Imaging Method
+    pub method: String,
+    /// Imaging Mode
+    pub mode: String,
+    /// Row Count
+    pub row_count: Option<u32>,
+    /// Column Count
+    pub column_count: Option<u32>,
+    /// Row Detection Set
+    pub row_set: Option<u32>,
+    /// Column Detection Set
+    pub column_set: Option<u32>,
+    /// Row Detection Rate
+    pub row_rate: Option<f64>,
+    /// Column Detection Rate
+    pub column_rate: Option<f64>,
+    /// First Collected Pixel Row
+    pub first_pixel_row: Option<u32>,
+    /// First Collected Pixel Column
+    pub first_pixel_column: Option<u32>,
+    /// Image Transform Parameters, at most 8
+    pub transform_params: Vec<Option<f64>>,
+}
+impl ImageFormationData {
+    /// Length of the fields before the transform parameters
+    const FIXED_LENGTH: usize = 87;
+    fn read(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        self.method = read_string(reader, 15, "METHOD")?;
+        self.mode = read_string(reader, 3, "MODE")?;
+        self.row_count = read_optional(reader, 8, "ROW_COUNT")?;
+        self.column_count = read_optional(reader, 8, "COLUMN_COUNT")?;
+        self.row_set = read_optional(reader, 8, "ROW_SET")?;
+        self.column_set = read_optional(reader, 8, "COLUMN_SET")?;
+        self.row_rate = read_optional(reader, 10, "ROW_RATE")?;
+        self.column_rate = read_optional(reader, 10, "COLUMN_RATE")?;
+        self.first_pixel_row = read_optional(reader, 8, "FIRST_PIXEL_ROW")?;
+        self.first_pixel_column = read_optional(reader, 8, "FIRST_PIXEL_COLUMN")?;
+        let n_params: usize = read_value(reader, 1, "TRANSFORM_PARAMS")?;
+        self.transform_params = (0..n_params)
+            .map(|_| read_optional(reader, 12, "TRANSFORM_PARAM"))
+            .collect::<NitfResult<_>>()?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_string(writer, &self.method, 15, "METHOD")?;
+        bytes_written += write_string(writer, &self.mode, 3, "MODE")?;
+        bytes_written += write_optional_int(writer, self.row_count, 8, "ROW_COUNT")?;
+        bytes_written += write_optional_int(writer, self.column_count, 8, "COLUMN_COUNT")?;
+        bytes_written += write_optional_int(writer, self.row_set, 8, "ROW_SET")?;
+        bytes_written += write_optional_int(writer, self.column_set, 8, "COLUMN_SET")?;
+        bytes_written += write_optional_real(writer, self.row_rate, 10, "ROW_RATE")?;
+        bytes_written += write_optional_real(writer, self.column_rate, 10, "COLUMN_RATE")?;
+        bytes_written += write_optional_int(writer, self.first_pixel_row, 8, "FIRST_PIXEL_ROW")?;
+        bytes_written +=
+            write_optional_int(writer, self.first_pixel_column, 8, "FIRST_PIXEL_COLUMN")?;
+        let n_params = self.transform_params.len();
+        if n_params > MAX_TRANSFORM_PARAMS {
+            Err(NitfError::TreError(format!(
+                "TRANSFORM_PARAMS: {n_params} is more than {MAX_TRANSFORM_PARAMS}"
+            )))?
+        }
+        bytes_written += write_int(writer, n_params as i64, 1, "TRANSFORM_PARAMS")?;
+        for param in &self.transform_params {
+            bytes_written += write_optional_real(writer, *param, 12, "TRANSFORM_PARAM")?;
+        }
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        Self::FIXED_LENGTH + 12 * self.transform_params.len()
+    }
+}
+
+/// SENSRB Module 5, reference time and pixel
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReferenceTime {
+    /// Reference Time, seconds from the start time
+    pub reference_time: Option<f64>,
+    /// Reference Pixel Row
+    pub reference_row: Option<f64>,
+    /// Reference Pixel Column
+    pub reference_column: Option<f64>,
+}
+impl ReferenceTime {
+    const LENGTH: usize = 28;
+    fn read(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        self.reference_time = read_optional(reader, 12, "REFERENCE_TIME")?;
+        self.reference_row = read_optional(reader, 8, "REFERENCE_ROW")?;
+        self.reference_column = read_optional(reader, 8, "REFERENCE_COLUMN")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_optional_real(writer, self.reference_time, 12, "REFERENCE_TIME")?;
+        bytes_written += write_optional_real(writer, self.reference_row, 8, "REFERENCE_ROW")?;
+        bytes_written += write_optional_real(writer, self.reference_column, 8, "REFERENCE_COLUMN")?;
+        Ok(bytes_written)
+    }
+}
+
+/// SENSRB Module 6, sensor position
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SensorPosition {
+    /// Latitude or ECEF X
+    pub latitude_or_x: Option<f64>,
+    /// Longitude or ECEF Y
+    pub longitude_or_y: Option<f64>,
+    /// Altitude or ECEF Z
+    pub altitude_or_z: Option<f64>,
+    /// Sensor Location Offset, x
+    pub sensor_x_offset: Option<f64>,
+    /// Sensor Location Offset, y
+    pub sensor_y_offset: Option<f64>,
+    /// Sensor Location Offset, z
+    pub sensor_z_offset: Option<f64>,
+}
+impl SensorPosition {
+    const LENGTH: usize = 58;
+    fn read(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        self.latitude_or_x = read_optional(reader, 11, "LATITUDE_OR_X")?;
+        self.longitude_or_y = read_optional(reader, 12, "LONGITUDE_OR_Y")?;
+        self.altitude_or_z = read_optional(reader, 11, "ALTITUDE_OR_Z")?;
+        self.sensor_x_offset = read_optional(reader, 8, "SENSOR_X_OFFSET")?;
+        self.sensor_y_offset = read_optional(reader, 8, "SENSOR_Y_OFFSET")?;
+        self.sensor_z_offset = read_optional(reader, 8, "SENSOR_Z_OFFSET")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_optional_real(writer, self.latitude_or_x, 11, "LATITUDE_OR_X")?;
+        bytes_written += write_optional_real(writer, self.longitude_or_y, 12, "LONGITUDE_OR_Y")?;
+        bytes_written += write_optional_real(writer, self.altitude_or_z, 11, "ALTITUDE_OR_Z")?;
+        bytes_written += write_optional_real(writer, self.sensor_x_offset, 8, "SENSOR_X_OFFSET")?;
+        bytes_written += write_optional_real(writer, self.sensor_y_offset, 8, "SENSOR_Y_OFFSET")?;
+        bytes_written += write_optional_real(writer, self.sensor_z_offset, 8, "SENSOR_Z_OFFSET")?;
+        Ok(bytes_written)
+    }
+}
+
+/// SENSRB Module 7, attitude Euler angles
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AttitudeEulerAngles {
+    /// Sensor Angle Model
+    pub sensor_angle_model: Option<u32>,
+    /// Sensor Angle 1
+    pub sensor_angle_1: Option<f64>,
+    /// Sensor Angle 2
+    pub sensor_angle_2: Option<f64>,
+    /// Sensor Angle 3
+    pub sensor_angle_3: Option<f64>,
+    /// Sensor Angles are Relative to the Platform
+    pub platform_relative: Option<bool>,
+    /// Platform Heading
+    pub platform_heading: Option<f64>,
+    /// Platform Pitch
+    pub platform_pitch: Option<f64>,
+    /// Platform Roll
+    pub platform_roll: Option<f64>,
+}
+impl AttitudeEulerAngles {
+    const LENGTH: usize = 59;
+    fn read(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        self.sensor_angle_model = read_optional(reader, 1, "SENSOR_ANGLE_MODEL")?;
+        self.sensor_angle_1 = read_optional(reader, 10, "SENSOR_ANGLE_1")?;
+        self.sensor_angle_2 = read_optional(reader, 9, "SENSOR_ANGLE_2")?;
+        self.sensor_angle_3 = read_optional(reader, 10, "SENSOR_ANGLE_3")?;
+        self.platform_relative = read_flag(reader, "PLATFORM_RELATIVE")?;
+        self.platform_heading = read_optional(reader, 9, "PLATFORM_HEADING")?;
+        self.platform_pitch = read_optional(reader, 9, "PLATFORM_PITCH")?;
+        self.platform_roll = read_optional(reader, 10, "PLATFORM_ROLL")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written +=
+            write_optional_int(writer, self.sensor_angle_model, 1, "SENSOR_ANGLE_MODEL")?;
+        bytes_written += write_optional_real(writer, self.sensor_angle_1, 10, "SENSOR_ANGLE_1")?;
+        bytes_written += write_optional_real(writer, self.sensor_angle_2, 9, "SENSOR_ANGLE_2")?;
+        bytes_written += write_optional_real(writer, self.sensor_angle_3, 10, "SENSOR_ANGLE_3")?;
+        bytes_written += write_flag(writer, self.platform_relative, "PLATFORM_RELATIVE")?;
+        bytes_written += write_optional_real(writer, self.platform_heading, 9, "PLATFORM_HEADING")?;
+        bytes_written += write_optional_real(writer, self.platform_pitch, 9, "PLATFORM_PITCH")?;
+        bytes_written += write_optional_real(writer, self.platform_roll, 10, "PLATFORM_ROLL")?;
+        Ok(bytes_written)
+    }
+}
+
+/// SENSRB Module 8, attitude unit vectors
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AttitudeUnitVectors {
+    /// Image Coordinate X Unit Vector, North or ECEF X
+    pub icx_north_or_x: Option<f64>,
+    /// Image Coordinate X Unit Vector, East or ECEF Y
+    pub icx_east_or_y: Option<f64>,
+    /// Image Coordinate X Unit Vector, Down or ECEF Z
+    pub icx_down_or_z: Option<f64>,
+    /// Image Coordinate Y Unit Vector, North or ECEF X
+    pub icy_north_or_x: Option<f64>,
+    /// Image Coordinate Y Unit Vector, East or ECEF Y
+    pub icy_east_or_y: Option<f64>,
+    /// Image Coordinate Y Unit Vector, Down or ECEF Z
+    pub icy_down_or_z: Option<f64>,
+    /// Image Coordinate Z Unit Vector, North or ECEF X
+    pub icz_north_or_x: Option<f64>,
+    /// Image Coordinate Z Unit Vector, East or ECEF Y
+    pub icz_east_or_y: Option<f64>,
+    /// Image Coordinate Z Unit Vector, Down or ECEF Z
+    pub icz_down_or_z: Option<f64>,
+}
+impl AttitudeUnitVectors {
+    const LENGTH: usize = 90;
+    fn read(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        self.icx_north_or_x = read_optional(reader, 10, "ICX_NORTH_OR_X")?;
+        self.icx_east_or_y = read_optional(reader, 10, "ICX_EAST_OR_Y")?;
+        self.icx_down_or_z = read_optional(reader, 10, "ICX_DOWN_OR_Z")?;
+        self.icy_north_or_x = read_optional(reader, 10, "ICY_NORTH_OR_X")?;
+        self.icy_east_or_y = read_optional(reader, 10, "ICY_EAST_OR_Y")?;
+        self.icy_down_or_z = read_optional(reader, 10, "ICY_DOWN_OR_Z")?;
+        self.icz_north_or_x = read_optional(reader, 10, "ICZ_NORTH_OR_X")?;
+        self.icz_east_or_y = read_optional(reader, 10, "ICZ_EAST_OR_Y")?;
+        self.icz_down_or_z = read_optional(reader, 10, "ICZ_DOWN_OR_Z")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_optional_real(writer, self.icx_north_or_x, 10, "ICX_NORTH_OR_X")?;
+        bytes_written += write_optional_real(writer, self.icx_east_or_y, 10, "ICX_EAST_OR_Y")?;
+        bytes_written += write_optional_real(writer, self.icx_down_or_z, 10, "ICX_DOWN_OR_Z")?;
+        bytes_written += write_optional_real(writer, self.icy_north_or_x, 10, "ICY_NORTH_OR_X")?;
+        bytes_written += write_optional_real(writer, self.icy_east_or_y, 10, "ICY_EAST_OR_Y")?;
+        bytes_written += write_optional_real(writer, self.icy_down_or_z, 10, "ICY_DOWN_OR_Z")?;
+        bytes_written += write_optional_real(writer, self.icz_north_or_x, 10, "ICZ_NORTH_OR_X")?;
+        bytes_written += write_optional_real(writer, self.icz_east_or_y, 10, "ICZ_EAST_OR_Y")?;
+        bytes_written += write_optional_real(writer, self.icz_down_or_z, 10, "ICZ_DOWN_OR_Z")?;
+        Ok(bytes_written)
+    }
+}
+
+/// SENSRB Module 9, attitude quaternion
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AttitudeQuaternion {
+    /// Attitude Quaternion Vector Component 1
+    pub attitude_q1: Option<f64>,
+    /// Attitude Quaternion Vector Component 2
+    pub attitude_q2: Option<f64>,
+    /// Attitude Quaternion Vector Component 3
+    pub attitude_q3: Option<f64>,
+    /// Attitude Quaternion Scalar Component
+    pub attitude_q4: Option<f64>,
+}
+impl AttitudeQuaternion {
+    const LENGTH: usize = 40;
+    fn read(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        self.attitude_q1 = read_optional(reader, 10, "ATTITUDE_Q1")?;
+        self.attitude_q2 = read_optional(reader, 10, "ATTITUDE_Q2")?;
+        self.attitude_q3 = read_optional(reader, 10, "ATTITUDE_Q3")?;
+        self.attitude_q4 = read_optional(reader, 10, "ATTITUDE_Q4")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_optional_real(writer, self.attitude_q1, 10, "ATTITUDE_Q1")?;
+        bytes_written += write_optional_real(writer, self.attitude_q2, 10, "ATTITUDE_Q2")?;
+        bytes_written += write_optional_real(writer, self.attitude_q3, 10, "ATTITUDE_Q3")?;
+        bytes_written += write_optional_real(writer, self.attitude_q4, 10, "ATTITUDE_Q4")?;
+        Ok(bytes_written)
+    }
+}
+
+/// SENSRB Module 10, sensor velocity
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SensorVelocity {
+    /// Sensor Velocity, North or ECEF X
+    pub velocity_north_or_x: Option<f64>,
+    /// Sensor Velocity, East or ECEF Y
+    pub velocity_east_or_y: Option<f64>,
+    /// Sensor Velocity, Down or ECEF Z
+    pub velocity_down_or_z: Option<f64>,
+}
+impl SensorVelocity {
+    const LENGTH: usize = 27;
+    fn read(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        self.velocity_north_or_x = read_optional(reader, 9, "VELOCITY_NORTH_OR_X")?;
+        self.velocity_east_or_y = read_optional(reader, 9, "VELOCITY_EAST_OR_Y")?;
+        self.velocity_down_or_z = read_optional(reader, 9, "VELOCITY_DOWN_OR_Z")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written +=
+            write_optional_real(writer, self.velocity_north_or_x, 9, "VELOCITY_NORTH_OR_X")?;
+        bytes_written +=
+            write_optional_real(writer, self.velocity_east_or_y, 9, "VELOCITY_EAST_OR_Y")?;
+        bytes_written +=
+            write_optional_real(writer, self.velocity_down_or_z, 9, "VELOCITY_DOWN_OR_Z")?;
+        Ok(bytes_written)
+    }
+}
+
+/// Field lengths of modules 1 to 10, by module and field letter, used to size
+/// time-stamped and pixel-referenced values
+const FIELD_LENGTHS: [&[usize]; 10] = [
+    &[25, 32, 25, 32, 10, 1, 5, 1, 3, 2, 3, 8, 14, 8, 14, 2, 8, 10],
+    &[20, 8, 8, 8, 8, 8, 8, 8, 1],
+    &[2, 9, 9, 12, 12, 12, 9, 12, 12, 12, 12, 8],
+    &[
+        15, 3, 8, 8, 8, 8, 10, 10, 8, 8, 1, 12, 12, 12, 12, 12, 12, 12, 12,
+    ],
+    &[12, 8, 8],
+    &[11, 12, 11, 8, 8, 8],
+    &[1, 10, 9, 10, 1, 9, 9, 10],
+    &[10, 10, 10, 10, 10, 10, 10, 10, 10],
+    &[10, 10, 10, 10],
+    &[9, 9, 9],
+];
+
+/// Length of the value of a module 1 to 10 field, from a type code such as
+/// `06a` for LATITUDE_OR_X
+pub fn field_length(code: &str) -> NitfResult<usize> {
+    let error = || NitfError::TreError(format!("Unknown SENSRB field code {code:?}"));
+    let module: usize = code
+        .get(..2)
+        .and_then(|m| m.parse().ok())
+        .ok_or_else(error)?;
+    let letter = code.as_bytes().get(2).ok_or_else(error)?;
+    let index = letter.checked_sub(b'a').ok_or_else(error)? as usize;
+    module
+        .checked_sub(1)
+        .and_then(|m| FIELD_LENGTHS.get(m))
+        .and_then(|fields| fields.get(index))
+        .copied()
+        .ok_or_else(error)
+}
+
+/// SENSRB module 11 point
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Point {
+    /// Point Row Location
+    pub p_row: Option<f64>,
+    /// Point Column Location
+    pub p_column: Option<f64>,
+    /// Point Latitude
+    pub p_latitude: Option<f64>,
+    /// Point Longitude
+    pub p_longitude: Option<f64>,
+    /// Point Elevation
+    pub p_elevation: Option<f64>,
+    /// Point Range
+    pub p_range: Option<f64>,
+}
+impl Point {
+    const LENGTH: usize = 51;
+}
+
+/// SENSRB module 11 point set
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PointSet {
+    /// Point Set Type
+    pub point_set_type: String,
+    /// Points in the set
+    pub points: Vec<Point>,
+}
+
+/// SENSRB module 12 time-stamped value
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TimeStampedValue {
+    /// Time Stamp Time, seconds from the reference time
+    pub time: Option<f64>,
+    /// Time Stamp Value, formatted as the field given by the set type
+    pub value: String,
+}
+
+/// SENSRB module 12 time-stamped data set
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TimeStampedSet {
+    /// Time Stamped Parameter Type, a field code such as `06a`
+    pub time_stamp_type: String,
+    /// Values in the set
+    pub values: Vec<TimeStampedValue>,
+}
+
+/// SENSRB module 13 pixel-referenced value
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PixelReferencedValue {
+    /// Pixel Reference Row
+    pub row: Option<f64>,
+    /// Pixel Reference Column
+    pub column: Option<f64>,
+    /// Pixel Reference Value, formatted as the field given by the set type
+    pub value: String,
+}
+
+/// SENSRB module 13 pixel-referenced data set
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PixelReferencedSet {
+    /// Pixel Referenced Parameter Type, a field code such as `06a`
+    pub pixel_reference_type: String,
+    /// Values in the set
+    pub values: Vec<PixelReferencedValue>,
+}
+
+/// SENSRB module 14 uncertainty
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Uncertainty {
+    /// Uncertainty First Index, a field code
+    pub first_type: String,
+    /// Uncertainty Second Index, a field code. Blank for a standard deviation
+    pub second_type: String,
+    /// Uncertainty Value
+    pub value: Option<f64>,
+}
+impl Uncertainty {
+    const LENGTH: usize = 32;
+}
+
+/// SENSRB module 15 additional parameter
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AdditionalParameter {
+    /// Parameter Name
+    pub name: String,
+    /// Parameter Size, bytes per value
+    pub size: usize,
+    /// Parameter Values
+    pub values: Vec<String>,
+}
+
+fn read_module<T: Default, R: Read>(
+    reader: &mut R,
+    name: &str,
+    read: impl FnOnce(&mut T, &mut R) -> NitfResult<()>,
+) -> NitfResult<Option<T>> {
+    match read_flag(reader, name)? {
+        Some(true) => {
+            let mut module = T::default();
+            read(&mut module, reader)?;
+            Ok(Some(module))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn write_module<T, W: Write>(
+    writer: &mut W,
+    module: &Option<T>,
+    name: &str,
+    write: impl FnOnce(&T, &mut W) -> NitfResult<usize>,
+) -> NitfResult<usize> {
+    let mut bytes_written = write_flag(writer, Some(module.is_some()), name)?;
+    if let Some(module) = module {
+        bytes_written += write(module, writer)?;
+    }
+    Ok(bytes_written)
+}
+
+impl Sensrb {
+    fn read_point_sets(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        let n_sets: usize = read_value(reader, 2, "POINT_SET_DATA")?;
+        for _ in 0..n_sets {
+            let point_set_type = read_string(reader, 25, "POINT_SET_TYPE")?;
+            let n_points: usize = read_value(reader, 3, "POINT_COUNT")?;
+            let mut points = Vec::with_capacity(n_points);
+            for _ in 0..n_points {
+                points.push(Point {
+                    p_row: read_optional(reader, 8, "P_ROW")?,
+                    p_column: read_optional(reader, 8, "P_COLUMN")?,
+                    p_latitude: read_optional(reader, 10, "P_LATITUDE")?,
+                    p_longitude: read_optional(reader, 11, "P_LONGITUDE")?,
+                    p_elevation: read_optional(reader, 6, "P_ELEVATION")?,
+                    p_range: read_optional(reader, 8, "P_RANGE")?,
+                });
+            }
+            self.point_sets.push(PointSet {
+                point_set_type,
+                points,
+            });
+        }
+        Ok(())
+    }
+
+    fn read_time_stamped_data(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        let n_sets: usize = read_value(reader, 2, "TIME_STAMPED_DATA_SETS")?;
+        for _ in 0..n_sets {
+            let time_stamp_type = read_string(reader, 3, "TIME_STAMP_TYPE")?;
+            let value_length = field_length(&time_stamp_type)?;
+            let n_values: usize = read_value(reader, 4, "TIME_STAMP_COUNT")?;
+            let mut values = Vec::with_capacity(n_values);
+            for _ in 0..n_values {
+                values.push(TimeStampedValue {
+                    time: read_optional(reader, 12, "TIME_STAMP_TIME")?,
+                    value: read_string(reader, value_length, "TIME_STAMP_VALUE")?,
+                });
+            }
+            self.time_stamped_data.push(TimeStampedSet {
+                time_stamp_type,
+                values,
+            });
+        }
+        Ok(())
+    }
+
+    fn read_pixel_referenced_data(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        let n_sets: usize = read_value(reader, 2, "PIXEL_REFERENCED_DATA_SETS")?;
+        for _ in 0..n_sets {
+            let pixel_reference_type = read_string(reader, 3, "PIXEL_REFERENCE_TYPE")?;
+            let value_length = field_length(&pixel_reference_type)?;
+            let n_values: usize = read_value(reader, 4, "PIXEL_REFERENCE_COUNT")?;
+            let mut values = Vec::with_capacity(n_values);
+            for _ in 0..n_values {
+                values.push(PixelReferencedValue {
+                    row: read_optional(reader, 8, "PIXEL_REFERENCE_ROW")?,
+                    column: read_optional(reader, 8, "PIXEL_REFERENCE_COLUMN")?,
+                    value: read_string(reader, value_length, "PIXEL_REFERENCE_VALUE")?,
+                });
+            }
+            self.pixel_referenced_data.push(PixelReferencedSet {
+                pixel_reference_type,
+                values,
+            });
+        }
+        Ok(())
+    }
+
+    fn read_uncertainty_data(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        let n_values: usize = read_value(reader, 3, "UNCERTAINTY_DATA")?;
+        for _ in 0..n_values {
+            self.uncertainty_data.push(Uncertainty {
+                first_type: read_string(reader, 11, "UNCERTAINTY_FIRST_TYPE")?,
+                second_type: read_string(reader, 11, "UNCERTAINTY_SECOND_TYPE")?,
+                value: read_optional(reader, 10, "UNCERTAINTY_VALUE")?,
+            });
+        }
+        Ok(())
+    }
+
+    fn read_additional_parameters(&mut self, reader: &mut impl Read) -> NitfResult<()> {
+        let n_params: usize = read_value(reader, 3, "ADDITIONAL_PARAMETER_DATA")?;
+        for _ in 0..n_params {
+            let name = read_string(reader, 25, "PARAMETER_NAME")?;
+            let size: usize = read_value(reader, 3, "PARAMETER_SIZE")?;
+            let count: usize = read_value(reader, 4, "PARAMETER_COUNT")?;
+            let values = (0..count)
+                .map(|_| read_string(reader, size, "PARAMETER_VALUE"))
+                .collect::<NitfResult<_>>()?;
+            self.additional_parameters
+                .push(AdditionalParameter { name, size, values });
+        }
+        Ok(())
+    }
+
+    fn write_loops(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_int(writer, self.point_sets.len() as i64, 2, "POINT_SET_DATA")?;
+        for set in &self.point_sets {
+            bytes_written += write_string(writer, &set.point_set_type, 25, "POINT_SET_TYPE")?;
+            bytes_written += write_int(writer, set.points.len() as i64, 3, "POINT_COUNT")?;
+            for point in &set.points {
+                bytes_written += write_optional_real(writer, point.p_row, 8, "P_ROW")?;
+                bytes_written += write_optional_real(writer, point.p_column, 8, "P_COLUMN")?;
+                bytes_written += write_optional_real(writer, point.p_latitude, 10, "P_LATITUDE")?;
+                bytes_written += write_optional_real(writer, point.p_longitude, 11, "P_LONGITUDE")?;
+                bytes_written += write_optional_real(writer, point.p_elevation, 6, "P_ELEVATION")?;
+                bytes_written += write_optional_real(writer, point.p_range, 8, "P_RANGE")?;
+            }
+        }
+
+        let n_sets = self.time_stamped_data.len() as i64;
+        bytes_written += write_int(writer, n_sets, 2, "TIME_STAMPED_DATA_SETS")?;
+        for set in &self.time_stamped_data {
+            let value_length = field_length(&set.time_stamp_type)?;
+            bytes_written += write_string(writer, &set.time_stamp_type, 3, "TIME_STAMP_TYPE")?;
+            bytes_written += write_int(writer, set.values.len() as i64, 4, "TIME_STAMP_COUNT")?;
+            for value in &set.values {
+                bytes_written += write_optional_real(writer, value.time, 12, "TIME_STAMP_TIME")?;
+                bytes_written +=
+                    write_string(writer, &value.value, value_length, "TIME_STAMP_VALUE")?;
+            }
+        }
+
+        let n_sets = self.pixel_referenced_data.len() as i64;
+        bytes_written += write_int(writer, n_sets, 2, "PIXEL_REFERENCED_DATA_SETS")?;
+        for set in &self.pixel_referenced_data {
+            let value_length = field_length(&set.pixel_reference_type)?;
+            bytes_written +=
+                write_string(writer, &set.pixel_reference_type, 3, "PIXEL_REFERENCE_TYPE")?;
+            bytes_written +=
+                write_int(writer, set.values.len() as i64, 4, "PIXEL_REFERENCE_COUNT")?;
+            for value in &set.values {
+                bytes_written += write_optional_real(writer, value.row, 8, "PIXEL_REFERENCE_ROW")?;
+                bytes_written +=
+                    write_optional_real(writer, value.column, 8, "PIXEL_REFERENCE_COLUMN")?;
+                bytes_written +=
+                    write_string(writer, &value.value, value_length, "PIXEL_REFERENCE_VALUE")?;
+            }
+        }
+
+        let n_values = self.uncertainty_data.len() as i64;
+        bytes_written += write_int(writer, n_values, 3, "UNCERTAINTY_DATA")?;
+        for uncertainty in &self.uncertainty_data {
+            bytes_written += write_string(
+                writer,
+                &uncertainty.first_type,
+                11,
+                "UNCERTAINTY_FIRST_TYPE",
+            )?;
+            bytes_written += write_string(
+                writer,
+                &uncertainty.second_type,
+                11,
+                "UNCERTAINTY_SECOND_TYPE",
+            )?;
+            bytes_written +=
+                write_optional_real(writer, uncertainty.value, 10, "UNCERTAINTY_VALUE")?;
+        }
+
+        let n_params = self.additional_parameters.len() as i64;
+        bytes_written += write_int(writer, n_params, 3, "ADDITIONAL_PARAMETER_DATA")?;
+        for param in &self.additional_parameters {
+            bytes_written += write_string(writer, &param.name, 25, "PARAMETER_NAME")?;
+            bytes_written += write_int(writer, param.size as i64, 3, "PARAMETER_SIZE")?;
+            bytes_written += write_int(writer, param.values.len() as i64, 4, "PARAMETER_COUNT")?;
+            for value in &param.values {
+                bytes_written += write_string(writer, value, param.size, "PARAMETER_VALUE")?;
+            }
+        }
+        Ok(bytes_written)
+    }
+}
+
+impl Tre for Sensrb {
+    const TAG: &'static str = "SENSRB";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.general_data = read_module(reader, "GENERAL_DATA", GeneralData::read)?;
+        self.sensor_array_data = read_module(reader, "SENSOR_ARRAY_DATA", SensorArrayData::read)?;
+        self.sensor_calibration_data = read_module(
+            reader,
+            "SENSOR_CALIBRATION_DATA",
+            SensorCalibrationData::read,
+        )?;
+        self.image_formation_data =
+            read_module(reader, "IMAGE_FORMATION_DATA", ImageFormationData::read)?;
+        self.reference_time.read(reader)?;
+        self.sensor_position.read(reader)?;
+        self.attitude_euler_angles =
+            read_module(reader, "ATTITUDE_EULER_ANGLES", AttitudeEulerAngles::read)?;
+        self.attitude_unit_vectors =
+            read_module(reader, "ATTITUDE_UNIT_VECTORS", AttitudeUnitVectors::read)?;
+        self.attitude_quaternion =
+            read_module(reader, "ATTITUDE_QUATERNION", AttitudeQuaternion::read)?;
+        self.sensor_velocity = read_module(reader, "SENSOR_VELOCITY_DATA", SensorVelocity::read)?;
+        self.point_sets.clear();
+        self.read_point_sets(reader)?;
+        self.time_stamped_data.clear();
+        self.read_time_stamped_data(reader)?;
+        self.pixel_referenced_data.clear();
+        self.read_pixel_referenced_data(reader)?;
+        self.uncertainty_data.clear();
+        self.read_uncertainty_data(reader)?;
+        self.additional_parameters.clear();
+        self.read_additional_parameters(reader)?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_module(
+            writer,
+            &self.general_data,
+            "GENERAL_DATA",
+            GeneralData::write,
+        )?;
+        bytes_written += write_module(
+            writer,
+            &self.sensor_array_data,
+            "SENSOR_ARRAY_DATA",
+            SensorArrayData::write,
+        )?;
+        bytes_written += write_module(
+            writer,
+            &self.sensor_calibration_data,
+            "SENSOR_CALIBRATION_DATA",
+            SensorCalibrationData::write,
+        )?;
+        bytes_written += write_module(
+            writer,
+            &self.image_formation_data,
+            "IMAGE_FORMATION_DATA",
+            ImageFormationData::write,
+        )?;
+        bytes_written += self.reference_time.write(writer)?;
+        bytes_written += self.sensor_position.write(writer)?;
+        bytes_written += write_module(
+            writer,
+            &self.attitude_euler_angles,
+            "ATTITUDE_EULER_ANGLES",
+            AttitudeEulerAngles::write,
+        )?;
+        bytes_written += write_module(
+            writer,
+            &self.attitude_unit_vectors,
+            "ATTITUDE_UNIT_VECTORS",
+            AttitudeUnitVectors::write,
+        )?;
+        bytes_written += write_module(
+            writer,
+            &self.attitude_quaternion,
+            "ATTITUDE_QUATERNION",
+            AttitudeQuaternion::write,
+        )?;
+        bytes_written += write_module(
+            writer,
+            &self.sensor_velocity,
+            "SENSOR_VELOCITY_DATA",
+            SensorVelocity::write,
+        )?;
+        bytes_written += self.write_loops(writer)?;
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        // One byte flag for each optional module
+        let mut length = 8;
+        length += self
+            .general_data
+            .as_ref()
+            .map_or(0, |_| GeneralData::LENGTH);
+        length += self
+            .sensor_array_data
+            .as_ref()
+            .map_or(0, |_| SensorArrayData::LENGTH);
+        length += self
+            .sensor_calibration_data
+            .as_ref()
+            .map_or(0, |_| SensorCalibrationData::LENGTH);
+        length += self.image_formation_data.as_ref().map_or(0, |m| m.length());
+        length += ReferenceTime::LENGTH + SensorPosition::LENGTH;
+        length += self
+            .attitude_euler_angles
+            .as_ref()
+            .map_or(0, |_| AttitudeEulerAngles::LENGTH);
+        length += self
+            .attitude_unit_vectors
+            .as_ref()
+            .map_or(0, |_| AttitudeUnitVectors::LENGTH);
+        length += self
+            .attitude_quaternion
+            .as_ref()
+            .map_or(0, |_| AttitudeQuaternion::LENGTH);
+        length += self
+            .sensor_velocity
+            .as_ref()
+            .map_or(0, |_| SensorVelocity::LENGTH);
+
+        // Module 11 to 15 counts
+        length += 2 + 2 + 2 + 3 + 3;
+        for set in &self.point_sets {
+            length += 28 + set.points.len() * Point::LENGTH;
+        }
+        for set in &self.time_stamped_data {
+            let value_length = field_length(&set.time_stamp_type).unwrap_or(0);
+            length += 7 + set.values.len() * (12 + value_length);
+        }
+        for set in &self.pixel_referenced_data {
+            let value_length = field_length(&set.pixel_reference_type).unwrap_or(0);
+            length += 7 + set.values.len() * (16 + value_length);
+        }
+        length += self.uncertainty_data.len() * Uncertainty::LENGTH;
+        for param in &self.additional_parameters {
+            length += 32 + param.size * param.values.len();
+        }
+        length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synthetic() -> Sensrb {
+        Sensrb {
+            general_data: Some(GeneralData {
+                sensor: "Synthetic Sensor".to_string(),
+                sensor_uri: "urn:example:sensor".to_string(),
+                platform: "Synthetic Platform".to_string(),
+                operation_domain: "Airborne".to_string(),
+                content_level: Some(4),
+                geodetic_system: "WGS84".to_string(),
+                geodetic_type: "G".to_string(),
+                elevation_datum: "HAE".to_string(),
+                length_unit: "SI".to_string(),
+                angular_unit: "DEG".to_string(),
+                start_date: "20200101".to_string(),
+                start_time: Some(43200.5),
+                end_date: "20200101".to_string(),
+                end_time: Some(43201.25),
+                generation_count: Some(0),
+                generation_date: "20200102".to_string(),
+                generation_time: "120000.000".to_string(),
+                ..Default::default()
+            }),
+            sensor_array_data: Some(SensorArrayData {
+                detection: "VIS".to_string(),
+                row_detectors: Some(4096),
+                column_detectors: Some(4096),
+                row_metric: Some(36.0),
+                column_metric: Some(24.0),
+                focal_length: Some(85.5),
+                row_fov: None,
+                column_fov: None,
+                calibrated: Some(true),
+            }),
+            sensor_calibration_data: Some(SensorCalibrationData {
+                calibration_unit: "mm".to_string(),
+                principal_point_offset_x: Some(0.125),
+                principal_point_offset_y: Some(-0.25),
+                radial_distort_1: Some(-1.5e-5),
+                radial_distort_limit: Some(20.0),
+                calibration_date: "20190601".to_string(),
+                ..Default::default()
+            }),
+            image_formation_data: Some(ImageFormationData {
+                method: "Single Frame".to_string(),
+                mode: "001".to_string(),
+                row_count: Some(4096),
+                column_count: Some(4096),
+                row_set: Some(4096),
+                column_set: Some(4096),
+                row_rate: Some(0.0),
+                column_rate: Some(0.0),
+                first_pixel_row: Some(1),
+                first_pixel_column: Some(1),
+                transform_params: vec![Some(1.0), Some(0.0), Some(-2.5)],
+            }),
+            reference_time: ReferenceTime {
+                reference_time: Some(0.5),
+                reference_row: Some(2048.5),
+                reference_column: Some(2048.5),
+            },
+            sensor_position: SensorPosition {
+                latitude_or_x: Some(38.8895),
+                longitude_or_y: Some(-77.0353),
+                altitude_or_z: Some(1500.0),
+                sensor_x_offset: Some(0.0),
+                sensor_y_offset: Some(0.0),
+                sensor_z_offset: Some(-1.0),
+            },
+            attitude_euler_angles: Some(AttitudeEulerAngles {
+                sensor_angle_model: Some(1),
+                sensor_angle_1: Some(90.0),
+                sensor_angle_2: Some(-45.0),
+                sensor_angle_3: Some(0.0),
+                platform_relative: Some(false),
+                ..Default::default()
+            }),
+            attitude_unit_vectors: None,
+            attitude_quaternion: Some(AttitudeQuaternion {
+                attitude_q1: Some(0.5),
+                attitude_q2: Some(-0.5),
+                attitude_q3: Some(0.5),
+                attitude_q4: Some(0.5),
+            }),
+            sensor_velocity: Some(SensorVelocity {
+                velocity_north_or_x: Some(50.0),
+                velocity_east_or_y: Some(-10.25),
+                velocity_down_or_z: Some(0.0),
+            }),
+            point_sets: vec![PointSet {
+                point_set_type: "Image Center".to_string(),
+                points: vec![Point {
+                    p_row: Some(2048.0),
+                    p_column: Some(2048.0),
+                    p_latitude: Some(38.9),
+                    p_longitude: Some(-77.04),
+                    p_elevation: Some(10.0),
+                    p_range: None,
+                }],
+            }],
+            time_stamped_data: vec![TimeStampedSet {
+                time_stamp_type: "06a".to_string(),
+                values: vec![
+                    TimeStampedValue {
+                        time: Some(0.0),
+                        value: "38.8895".to_string(),
+                    },
+                    TimeStampedValue {
+                        time: Some(1.0),
+                        value: "38.8899".to_string(),
+                    },
+                ],
+            }],
+            pixel_referenced_data: vec![PixelReferencedSet {
+                pixel_reference_type: "05a".to_string(),
+                values: vec![PixelReferencedValue {
+                    row: Some(1.0),
+                    column: Some(1.0),
+                    value: "0.25".to_string(),
+                }],
+            }],
+            uncertainty_data: vec![Uncertainty {
+                first_type: "06a".to_string(),
+                second_type: String::new(),
+                value: Some(0.001),
+            }],
+            additional_parameters: vec![AdditionalParameter {
+                name: "BAND".to_string(),
+                size: 3,
+                values: vec!["RED".to_string(), "GRN".to_string()],
+            }],
+        }
+    }
+
+    #[test]
+    fn sensrb_round_trip() {
+        let sensrb = synthetic();
+        let raw = sensrb.to_raw().unwrap();
+        assert_eq!(raw.cel, sensrb.length());
+        let decoded = Sensrb::from_raw(&raw).unwrap();
+        assert_eq!(decoded, sensrb);
+        assert_eq!(decoded.to_raw().unwrap().data, raw.data);
+    }
+
+    #[test]
+    fn distortion_coefficients() {
+        let calibration = SensorCalibrationData {
+            radial_distort_1: Some(1.2345678e-9),
+            radial_distort_2: Some(-2.5e-12),
+            radial_distort_3: Some(3.0e-15),
+            decent_distort_1: Some(-4.25e-7),
+            decent_distort_2: Some(5.0e-8),
+            affinity_distort_1: Some(6.0e-6),
+            affinity_distort_2: Some(-7.5e-10),
+            ..Default::default()
+        };
+        let sensrb = Sensrb {
+            sensor_calibration_data: Some(calibration.clone()),
+            ..Default::default()
+        };
+        let raw = sensrb.to_raw().unwrap();
+        let data = String::from_utf8(raw.data.clone()).unwrap();
+        assert!(data.contains("+1.234568E-9"));
+        assert!(data.contains("-2.50000E-12"));
+        let decoded = Sensrb::from_raw(&raw).unwrap();
+        let decoded = decoded.sensor_calibration_data.unwrap();
+        assert_eq!(decoded.radial_distort_1, Some(1.234568e-9));
+        assert_eq!(decoded.radial_distort_2, calibration.radial_distort_2);
+        assert_eq!(decoded.radial_distort_3, calibration.radial_distort_3);
+        assert_eq!(decoded.decent_distort_1, calibration.decent_distort_1);
+        assert_eq!(decoded.affinity_distort_2, calibration.affinity_distort_2);
+    }
+
+    #[test]
+    fn sensrb_minimal() {
+        let sensrb = Sensrb::default();
+        let raw = sensrb.to_raw().unwrap();
+        // Eight unset flags, modules 5 and 6, and the five loop counts
+        assert_eq!(raw.cel, 8 + 28 + 58 + 12);
+        assert_eq!(Sensrb::from_raw(&raw).unwrap(), sensrb);
+    }
+
+    #[test]
+    fn field_codes() {
+        assert_eq!(field_length("06a").unwrap(), 11);
+        assert_eq!(field_length("04l").unwrap(), 12);
+        assert!(field_length("11a").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! STDIDC standard identifier TRE
+use std::io::{Read, Seek, Write};
+
+use crate::tre::{read_optional, read_string, write_optional_int, write_string, Tre};
+use crate::NitfResult;
+
+/// STDIDC TRE
+///
+/// Numeric fields are `None` when blank or filled with `-`. Reserved fields
+/// are skipped when reading and written as spaces.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stdidc {
+    /// Acquisition Date, `CCYYMMDDhhmmss`
+    pub acquisition_date: String,
+    /// Mission Identification
+    pub mission: String,
+    /// Pass Number
+    pub pass: String,
+    /// Image Operation Number
+    pub op_num: Option<u16>,
+    /// Start Segment ID
+    pub start_segment: String,
+    /// Reprocess Number
+    pub repro_num: Option<u8>,
+    /// Replay
+    pub replay_regen: String,
+    /// Blank Fill
+    pub blank_fill: String,
+    /// Starting Column Block
+    pub start_column: Option<u16>,
+    /// Starting Row Block
+    pub start_row: Option<u32>,
+    /// Ending Segment
+    pub end_segment: String,
+    /// Ending Column Block
+    pub end_column: Option<u16>,
+    /// Ending Row Block
+    pub end_row: Option<u32>,
+    /// Country Code
+    pub country: String,
+    /// World Aeronautical Chart
+    pub wac: Option<u16>,
+    /// Location, `ddmmXdddmmY`
+    pub location: String,
+}
+
+impl Tre for Stdidc {
+    const TAG: &'static str = "STDIDC";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.acquisition_date = read_string(reader, 14, "ACQUISITION_DATE")?;
+        self.mission = read_string(reader, 14, "MISSION")?;
+        self.pass = read_string(reader, 2, "PASS")?;
+        self.op_num = read_optional(reader, 3, "OP_NUM")?;
+        self.start_segment = read_string(reader, 2, "START_SEGMENT")?;
+        self.repro_num = read_optional(reader, 2, "REPRO_NUM")?;
+        self.replay_regen = read_string(reader, 3, "REPLAY_REGEN")?;
+        self.blank_fill = read_string(reader, 1, "BLANK_FILL")?;
+        self.start_column = read_optional(reader, 3, "START_COLUMN")?;
+        self.start_row = read_optional(reader, 5, "START_ROW")?;
+        self.end_segment = read_string(reader, 2, "END_SEGMENT")?;
+        self.end_column = read_optional(reader, 3, "END_COLUMN")?;
+        self.end_row = read_optional(reader, 5, "END_ROW")?;
+        self.country = read_string(reader, 2, "COUNTRY")?;
+        self.wac = read_optional(reader, 4, "WAC")?;
+        self.location = read_string(reader, 11, "LOCATION")?;
+        read_string(reader, 13, "RESERVED")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_string(writer, &self.acquisition_date, 14, "ACQUISITION_DATE")?;
+        bytes_written += write_string(writer, &self.mission, 14, "MISSION")?;
+        bytes_written += write_string(writer, &self.pass, 2, "PASS")?;
+        bytes_written += write_optional_int(writer, self.op_num, 3, "OP_NUM")?;
+        bytes_written += write_string(writer, &self.start_segment, 2, "START_SEGMENT")?;
+        bytes_written += write_optional_int(writer, self.repro_num, 2, "REPRO_NUM")?;
+        bytes_written += write_string(writer, &self.replay_regen, 3, "REPLAY_REGEN")?;
+        bytes_written += write_string(writer, &self.blank_fill, 1, "BLANK_FILL")?;
+        bytes_written += write_optional_int(writer, self.start_column, 3, "START_COLUMN")?;
+        bytes_written += write_optional_int(writer, self.start_row, 5, "START_ROW")?;
+        bytes_written += write_string(writer, &self.end_segment, 2, "END_SEGMENT")?;
+        bytes_written += write_optional_int(writer, self.end_column, 3, "END_COLUMN")?;
+        bytes_written += write_optional_int(writer, self.end_row, 5, "END_ROW")?;
+        bytes_written += write_string(writer, &self.country, 2, "COUNTRY")?;
+        bytes_written += write_optional_int(writer, self.wac, 4, "WAC")?;
+        bytes_written += write_string(writer, &self.location, 11, "LOCATION")?;
+        bytes_written += write_string(writer, "", 13, "RESERVED")?;
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        89
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tre::test_tre;
+
+    #[test]
+    fn stdidc_round_trip() {
+        let (raw, written) = test_tre(
+            Stdidc::TAG,
+            &[
+                ("20200101120000", 14),
+                ("MISSION", 14),
+                ("01", 2),
+                ("---", 3),
+                ("AA", 2),
+                ("", 2),
+                ("000", 3),
+                ("", 1),
+                ("001", 3),
+                ("00012", 5),
+                ("AB", 2),
+                ("---", 3),
+                ("00034", 5),
+                ("US", 2),
+                ("", 4),
+                ("3000N11700W", 11),
+                ("", 13),
+            ],
+        );
+        let stdidc = Stdidc::from_raw(&raw).unwrap();
+        assert_eq!(raw.cel, stdidc.length());
+        assert_eq!(stdidc.mission, "MISSION");
+        assert_eq!(stdidc.op_num, None);
+        assert_eq!(stdidc.repro_num, None);
+        assert_eq!(stdidc.start_row, Some(12));
+        assert_eq!(stdidc.end_column, None);
+        assert_eq!(stdidc.end_row, Some(34));
+        assert_eq!(stdidc.wac, None);
+        assert_eq!(stdidc.to_raw().unwrap().data, written);
+    }
+}
@@ -0,0 +1,121 @@
+//! USE00A exploitation usability TRE
+use std::io::{Read, Seek, Write};
+
+use crate::tre::{
+    read_optional, read_string, write_optional_float, write_optional_int, write_string, Tre,
+};
+use crate::NitfResult;
+
+/// USE00A TRE
+///
+/// Numeric fields are `None` when blank or filled with `-`. Reserved fields
+/// are skipped when reading and written as spaces.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Use00a {
+    /// Angle to North, degrees clockwise from the first row
+    pub angle_to_north: Option<u16>,
+    /// Mean Ground Sample Distance, inches
+    pub mean_gsd: Option<f64>,
+    /// Dynamic Range of image pixels
+    pub dynamic_range: Option<u32>,
+    /// Obliquity Angle, degrees
+    pub obl_ang: Option<f64>,
+    /// Roll Angle, degrees
+    pub roll_ang: Option<f64>,
+    /// Number of Reference Lines
+    pub n_ref: Option<u8>,
+    /// Revolution Number
+    pub rev_num: Option<u32>,
+    /// Number of Segments
+    pub n_seg: Option<u16>,
+    /// Maximum Lines Per Segment
+    pub max_lp_seg: Option<u32>,
+    /// Sun Elevation, degrees
+    pub sun_el: Option<f64>,
+    /// Sun Azimuth, degrees
+    pub sun_az: Option<f64>,
+}
+
+impl Tre for Use00a {
+    const TAG: &'static str = "USE00A";
+    fn read(&mut self, reader: &mut (impl Read + Seek)) -> NitfResult<()> {
+        self.angle_to_north = read_optional(reader, 3, "ANGLE_TO_NORTH")?;
+        self.mean_gsd = read_optional(reader, 5, "MEAN_GSD")?;
+        read_string(reader, 1, "RESERVED")?;
+        self.dynamic_range = read_optional(reader, 5, "DYNAMIC_RANGE")?;
+        read_string(reader, 7, "RESERVED")?;
+        self.obl_ang = read_optional(reader, 5, "OBL_ANG")?;
+        self.roll_ang = read_optional(reader, 6, "ROLL_ANG")?;
+        read_string(reader, 37, "RESERVED")?;
+        self.n_ref = read_optional(reader, 2, "N_REF")?;
+        self.rev_num = read_optional(reader, 5, "REV_NUM")?;
+        self.n_seg = read_optional(reader, 3, "N_SEG")?;
+        self.max_lp_seg = read_optional(reader, 6, "MAX_LP_SEG")?;
+        read_string(reader, 12, "RESERVED")?;
+        self.sun_el = read_optional(reader, 5, "SUN_EL")?;
+        self.sun_az = read_optional(reader, 5, "SUN_AZ")?;
+        Ok(())
+    }
+    fn write(&self, writer: &mut (impl Write + Seek)) -> NitfResult<usize> {
+        let mut bytes_written = 0;
+        bytes_written += write_optional_int(writer, self.angle_to_north, 3, "ANGLE_TO_NORTH")?;
+        bytes_written += write_optional_float(writer, self.mean_gsd, 5, 1, false, "MEAN_GSD")?;
+        bytes_written += write_string(writer, "", 1, "RESERVED")?;
+        bytes_written += write_optional_int(writer, self.dynamic_range, 5, "DYNAMIC_RANGE")?;
+        bytes_written += write_string(writer, "", 7, "RESERVED")?;
+        bytes_written += write_optional_float(writer, self.obl_ang, 5, 2, false, "OBL_ANG")?;
+        bytes_written += write_optional_float(writer, self.roll_ang, 6, 2, true, "ROLL_ANG")?;
+        bytes_written += write_string(writer, "", 37, "RESERVED")?;
+        bytes_written += write_optional_int(writer, self.n_ref, 2, "N_REF")?;
+        bytes_written += write_optional_int(writer, self.rev_num, 5, "REV_NUM")?;
+        bytes_written += write_optional_int(writer, self.n_seg, 3, "N_SEG")?;
+        bytes_written += write_optional_int(writer, self.max_lp_seg, 6, "MAX_LP_SEG")?;
+        bytes_written += write_string(writer, "", 12, "RESERVED")?;
+        bytes_written += write_optional_float(writer, self.sun_el, 5, 1, true, "SUN_EL")?;
+        bytes_written += write_optional_float(writer, self.sun_az, 5, 1, false, "SUN_AZ")?;
+        Ok(bytes_written)
+    }
+    fn length(&self) -> usize {
+        107
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tre::test_tre;
+
+    #[test]
+    fn use00a_round_trip() {
+        let (raw, written) = test_tre(
+            Use00a::TAG,
+            &[
+                ("270", 3),
+                ("032.5", 5),
+                ("", 1),
+                ("-----", 5),
+                ("", 7),
+                ("12.34", 5),
+                ("-01.50", 6),
+                ("", 37),
+                ("", 2),
+                ("01234", 5),
+                ("001", 3),
+                ("------", 6),
+                ("", 12),
+                ("+45.2", 5),
+                ("123.4", 5),
+            ],
+        );
+        let use00a = Use00a::from_raw(&raw).unwrap();
+        assert_eq!(raw.cel, use00a.length());
+        assert_eq!(use00a.angle_to_north, Some(270));
+        assert_eq!(use00a.mean_gsd, Some(32.5));
+        assert_eq!(use00a.dynamic_range, None);
+        assert_eq!(use00a.roll_ang, Some(-1.5));
+        assert_eq!(use00a.n_ref, None);
+        assert_eq!(use00a.max_lp_seg, None);
+        assert_eq!(use00a.sun_el, Some(45.2));
+        assert_eq!(use00a.to_raw().unwrap().data, written);
+    }
+}
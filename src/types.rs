@@ -144,8 +144,19 @@ impl<T: NitfSegmentHeader> NitfSegment<T> {
                 .map(reader.deref())
         }?)
     }
-    /// Write segment data to file. Assumes cursor is in correct position
-    pub fn write_data(&self, writer: &mut (impl Write + Seek), data: &[u8]) -> NitfResult<usize> {
+    /// Write segment data to file, after the header has been written.
+    ///
+    /// Header fields which depend on the data, such as a DES CRC, are updated
+    /// and the header is written again if they changed.
+    pub fn write_data(
+        &mut self,
+        writer: &mut (impl Write + Seek),
+        data: &[u8],
+    ) -> NitfResult<usize> {
+        if self.header.update_for_data(data)? {
+            writer.seek(std::io::SeekFrom::Start(self.header_offset))?;
+            self.header.write(writer)?;
+        }
         writer.seek(std::io::SeekFrom::Start(self.data_offset))?;
         writer.write(data).map_err(NitfError::IOError)
     }
@@ -158,6 +169,71 @@ impl<T: NitfSegmentHeader + Display> Display for NitfSegment<T> {
         write!(f, "{}", self.header)
     }
 }
+
+/// Signed row and column offset, stored as `RRRRRCCCCC`. Used for image and
+/// graphic locations (ILOC, SLOC) and graphic bounds (SBND1, SBND2).
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct RowColumn {
+    pub row: i32,
+    pub col: i32,
+}
+
+impl RowColumn {
+    /// Smallest value of a five character component
+    pub const MIN: i32 = -9999;
+    /// Largest value of a five character component
+    pub const MAX: i32 = 99999;
+
+    pub fn new(row: i32, col: i32) -> Self {
+        Self { row, col }
+    }
+    /// Whether both components fit in five characters
+    pub fn is_valid(&self) -> bool {
+        (Self::MIN..=Self::MAX).contains(&self.row) && (Self::MIN..=Self::MAX).contains(&self.col)
+    }
+    /// Check that the offset can be written to the field `name`
+    pub fn check(&self, name: &str) -> NitfResult<()> {
+        match self.is_valid() {
+            true => Ok(()),
+            false => Err(NitfError::Value(format!("{name} {self:?}"))),
+        }
+    }
+}
+
+impl FromStr for RowColumn {
+    type Err = NitfError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 10 || !s.is_ascii() {
+            Err(NitfError::ParseError("RowColumn".to_string()))?
+        }
+        Ok(Self {
+            row: s[..5]
+                .parse()
+                .or(Err(NitfError::ParseError("RowColumn.row".to_string())))?,
+            col: s[5..]
+                .parse()
+                .or(Err(NitfError::ParseError("RowColumn.col".to_string())))?,
+        })
+    }
+}
+impl Display for RowColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:05}{:05}", self.row, self.col)
+    }
+}
+impl std::ops::Add for RowColumn {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.row + rhs.row, self.col + rhs.col)
+    }
+}
+impl std::ops::Sub for RowColumn {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.row - rhs.row, self.col - rhs.col)
+    }
+}
+
 /// Standard security metadata
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Security {
@@ -587,6 +663,10 @@ pub struct ExtendedSubheader {
     tre: Vec<u8>,
     /// Length of subheader
     size: usize,
+    /// TREs stored in a `TRE_OVERFLOW` data extension segment
+    overflow: Vec<u8>,
+    /// Index of the `TRE_OVERFLOW` segment holding `overflow`
+    overflow_segment: Option<usize>,
     /// Name of subheader
     pub name: String,
 }
@@ -599,10 +679,21 @@ impl ExtendedSubheader {
     pub fn size(&self) -> &usize {
         &self.size
     }
+    /// Get `overflow`
+    pub fn overflow(&self) -> &Vec<u8> {
+        &self.overflow
+    }
+    /// Index in [Nitf::data_extension_segments](crate::Nitf::data_extension_segments)
+    /// of the `TRE_OVERFLOW` segment holding the overflow, if there is one
+    pub fn overflow_segment(&self) -> Option<usize> {
+        self.overflow_segment
+    }
     pub fn init(name: &str) -> Self {
         Self {
             tre: vec![],
             size: 0,
+            overflow: vec![],
+            overflow_segment: None,
             name: name.to_string(),
         }
     }
@@ -611,6 +702,104 @@ impl ExtendedSubheader {
         self.size = new_tre.len();
         self.tre = new_tre;
     }
+    /// Set the TREs stored in `TRE_OVERFLOW` data extension segment
+    /// `i_seg`
+    pub(crate) fn set_overflow(&mut self, overflow: Vec<u8>, i_seg: usize) {
+        self.overflow = overflow;
+        self.overflow_segment = Some(i_seg);
+    }
+    /// Record that the overflow is held by segment `i_seg`
+    pub(crate) fn set_overflow_segment(&mut self, i_seg: usize) {
+        self.overflow_segment = Some(i_seg);
+    }
+    /// Rebuild the TRE byte vector from a list of entries.
+    ///
+    /// Any overflow is cleared, and recomputed when the file is written.
+    pub fn set_tres(&mut self, tres: &[RawTre]) -> NitfResult<()> {
+        let mut new_tre = Vec::with_capacity(tres.iter().map(|t| t.length()).sum());
+        for tre in tres {
+            tre.write(&mut new_tre)?;
+        }
+        self.set_tre(new_tre);
+        self.overflow.clear();
+        self.overflow_segment = None;
+        Ok(())
+    }
+    /// Keep as many leading TREs as fit in `capacity` bytes, and move the rest
+    /// to the overflow. The overflow segment is assigned by the caller.
+    pub(crate) fn spill(&mut self, capacity: usize) -> NitfResult<()> {
+        let mut tre = vec![];
+        let mut overflow = vec![];
+        for entry in self.tres()? {
+            if overflow.is_empty() && tre.len() + entry.length() <= capacity {
+                entry.write(&mut tre)?;
+            } else {
+                entry.write(&mut overflow)?;
+            }
+        }
+        self.set_tre(tre);
+        self.overflow = overflow;
+        self.overflow_segment = None;
+        Ok(())
+    }
+    /// Append a TRE to the end of the subheader.
+    pub fn add_tre(&mut self, tre: RawTre) -> NitfResult<()> {
+        let mut tres = self.tres()?;
+        tres.push(tre);
+        self.set_tres(&tres)
+    }
+    /// Replace the first TRE with a matching tag, appending it if there is none.
+    ///
+    /// Returns the entry which was replaced, if any.
+    pub fn replace_tre(&mut self, tre: RawTre) -> NitfResult<Option<RawTre>> {
+        let mut tres = self.tres()?;
+        let old = match tres.iter_mut().find(|t| t.cetag == tre.cetag) {
+            Some(existing) => Some(std::mem::replace(existing, tre)),
+            None => {
+                tres.push(tre);
+                None
+            }
+        };
+        self.set_tres(&tres)?;
+        Ok(old)
+    }
+    /// Remove all TREs with a matching tag, returning the removed entries.
+    pub fn remove_tre(&mut self, cetag: &str) -> NitfResult<Vec<RawTre>> {
+        let (removed, kept): (Vec<_>, Vec<_>) = self
+            .tres()?
+            .into_iter()
+            .partition(|t| t.cetag == cetag.trim());
+        self.set_tres(&kept)?;
+        Ok(removed)
+    }
+    /// Number of data bytes which fit in a subheader described by `length`,
+    /// which also counts the `overflow` field
+    pub(crate) fn capacity(length: &NitfField<u32>, overflow: &NitfField<u16>) -> usize {
+        10usize.pow(length.length as u32) - 1 - overflow.length
+    }
+    /// Set the length and overflow fields which describe this subheader.
+    ///
+    /// The length field counts the 3 byte overflow field along with the data,
+    /// and is zero when there is no data and no overflow.
+    pub(crate) fn update_length_fields(
+        &self,
+        length: &mut NitfField<u32>,
+        overflow: &mut NitfField<u16>,
+    ) -> NitfResult<()> {
+        if self.size == 0 && self.overflow.is_empty() {
+            length.val = 0;
+            overflow.val = 0;
+            return Ok(());
+        }
+        if self.size > Self::capacity(length, overflow) {
+            Err(NitfError::TreError(format!(
+                "{} data ({} bytes) does not fit in {}",
+                self.name, self.size, length.name
+            )))?
+        }
+        length.val = (self.size + overflow.length) as u32;
+        Ok(())
+    }
     pub fn read(&mut self, reader: &mut (impl Read + Seek), n_bytes: usize) -> NitfResult<()> {
         self.size = n_bytes;
         self.tre = vec![0; n_bytes];
@@ -625,6 +814,178 @@ impl ExtendedSubheader {
             .write(self.tre.as_slice())
             .map_err(NitfError::IOError)
     }
+    /// Iterate over the individual TREs stored in the subheader, followed by
+    /// any TREs from its overflow. Each entry records its [TreOrigin].
+    ///
+    /// Iteration stops after the first malformed entry.
+    pub fn iter_tres(&self) -> TreIter<'_> {
+        TreIter {
+            bytes: &self.tre,
+            offset: 0,
+            origin: TreOrigin::Header,
+            overflow: &self.overflow,
+            overflow_origin: self
+                .overflow_segment
+                .map_or(TreOrigin::Header, TreOrigin::Overflow),
+            name: &self.name,
+        }
+    }
+    /// Split the subheader data into its individual TREs
+    pub fn tres(&self) -> NitfResult<Vec<RawTre>> {
+        self.iter_tres().collect()
+    }
+}
+
+/// Where a TRE was read from
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum TreOrigin {
+    #[default]
+    /// The subheader itself
+    Header,
+    /// A `TRE_OVERFLOW` segment, by index in
+    /// [Nitf::data_extension_segments](crate::Nitf::data_extension_segments)
+    Overflow(usize),
+}
+
+/// Single tagged record extension, as stored in an [ExtendedSubheader]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct RawTre {
+    /// Unique Extension Type Identifier
+    pub cetag: String,
+    /// Length of User-Defined Data
+    pub cel: usize,
+    /// User-Defined Data
+    pub data: Vec<u8>,
+    /// Where the entry was read from. New entries are placed in the header
+    /// when written, and spill to an overflow segment if they do not fit.
+    pub origin: TreOrigin,
+}
+impl RawTre {
+    /// Length of the CETAG field
+    pub const CETAG_LENGTH: usize = 6;
+    /// Length of the CEL field
+    pub const CEL_LENGTH: usize = 5;
+
+    /// Create an entry from a tag and its data, setting `cel` accordingly
+    pub fn new(cetag: &str, data: Vec<u8>) -> Self {
+        Self {
+            cetag: cetag.trim().to_string(),
+            cel: data.len(),
+            data,
+            origin: TreOrigin::Header,
+        }
+    }
+    /// Total number of bytes used to store the entry, including CETAG and CEL
+    pub fn length(&self) -> usize {
+        Self::CETAG_LENGTH + Self::CEL_LENGTH + self.data.len()
+    }
+    pub fn write(&self, writer: &mut impl Write) -> NitfResult<usize> {
+        if self.cetag.len() > Self::CETAG_LENGTH || !self.cetag.is_ascii() {
+            Err(NitfError::TreError(format!(
+                "invalid CETAG {:?}",
+                self.cetag
+            )))?
+        }
+        if self.cel != self.data.len() || self.cel > 99999 {
+            Err(NitfError::TreError(format!(
+                "{}: CEL {} does not match {} data bytes",
+                self.cetag,
+                self.cel,
+                self.data.len()
+            )))?
+        }
+        let buf = format!("{:<6}{:05}", self.cetag, self.cel);
+        writer.write_all(buf.as_bytes())?;
+        writer.write_all(&self.data)?;
+        Ok(self.length())
+    }
+}
+impl Display for RawTre {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CETAG: {}, CEL: {}", self.cetag, self.cel)
+    }
+}
+
+/// Iterator over the TREs of an [ExtendedSubheader]
+pub struct TreIter<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    origin: TreOrigin,
+    /// Overflow TREs, iterated once `bytes` is exhausted
+    overflow: &'a [u8],
+    overflow_origin: TreOrigin,
+    name: &'a str,
+}
+impl TreIter<'_> {
+    fn error(&mut self, msg: String) -> Option<NitfResult<RawTre>> {
+        // Nothing after a malformed entry can be trusted
+        self.offset = self.bytes.len();
+        self.overflow = &[];
+        Some(Err(NitfError::TreError(format!("{}: {msg}", self.name))))
+    }
+}
+impl Iterator for TreIter<'_> {
+    type Item = NitfResult<RawTre>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset == self.bytes.len() && !self.overflow.is_empty() {
+            self.bytes = std::mem::take(&mut self.overflow);
+            self.offset = 0;
+            self.origin = self.overflow_origin;
+        }
+        let remaining = &self.bytes[self.offset..];
+        if remaining.is_empty() {
+            return None;
+        }
+        let header_length = RawTre::CETAG_LENGTH + RawTre::CEL_LENGTH;
+        if remaining.len() < header_length {
+            let msg = format!(
+                "{} trailing bytes at offset {} are too short for a TRE header",
+                remaining.len(),
+                self.offset
+            );
+            return self.error(msg);
+        }
+        let cetag = match std::str::from_utf8(&remaining[..RawTre::CETAG_LENGTH]) {
+            Ok(tag) if tag.is_ascii() => tag.trim().to_string(),
+            _ => {
+                let msg = format!("non-ASCII CETAG at offset {}", self.offset);
+                return self.error(msg);
+            }
+        };
+        let cel_bytes = &remaining[RawTre::CETAG_LENGTH..header_length];
+        let cel = match std::str::from_utf8(cel_bytes)
+            .ok()
+            .filter(|s| s.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|s| s.parse::<usize>().ok())
+        {
+            Some(cel) => cel,
+            None => {
+                let msg = format!(
+                    "{cetag} at offset {} has invalid CEL {:?}",
+                    self.offset,
+                    String::from_utf8_lossy(cel_bytes)
+                );
+                return self.error(msg);
+            }
+        };
+        if cel > remaining.len() - header_length {
+            let msg = format!(
+                "{cetag} at offset {} has CEL {cel} but only {} bytes remain",
+                self.offset,
+                remaining.len() - header_length
+            );
+            return self.error(msg);
+        }
+        let data = remaining[header_length..header_length + cel].to_vec();
+        trace!("Found TRE {cetag} ({cel} bytes) at offset {}", self.offset);
+        self.offset += header_length + cel;
+        Some(Ok(RawTre {
+            cetag,
+            cel,
+            data,
+            origin: self.origin,
+        }))
+    }
 }
 impl Display for ExtendedSubheader {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
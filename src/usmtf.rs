@@ -0,0 +1,129 @@
+//! US Message Text Format (USMTF) messages in `MTF` text segments
+//!
+//! A message is a sequence of sets, each ending with `//`. A set starts with
+//! its identifier and its fields follow, each introduced by `/`. A field of
+//! `-` has no data. Line breaks inside a set only wrap it, and are removed,
+//! except in free text sets, whose text is kept as a single field.
+//! ```
+//! use nitf_rs::usmtf::MtfMessage;
+//!
+//! let text = "MSGID/SITREP/JOINT STAFF/-/JUN//\nNARR/ALL UNITS\nREPORTING//";
+//! let message = MtfMessage::parse(text).unwrap();
+//! assert_eq!(message.identifier(), Some("SITREP"));
+//! let msgid = message.set("MSGID").unwrap();
+//! assert_eq!(msgid.field(1), Some("JOINT STAFF"));
+//! assert_eq!(msgid.field(2), None);
+//! assert_eq!(message.set("NARR").unwrap().field(0), Some("ALL UNITS\nREPORTING"));
+//! ```
+use log::warn;
+use std::io::{Read, Seek};
+
+use crate::headers::text_hdr::TextFormat;
+use crate::{NitfError, NitfResult, TextSegment};
+
+/// Sets holding free text rather than fields. GENTEXT has a text indicator
+/// field before its text.
+const FREE_TEXT_SETS: [&str; 4] = ["AMPN", "NARR", "RMKS", "GENTEXT"];
+
+/// Set of a USMTF message
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MtfSet {
+    /// Set identifier, such as `MSGID`
+    pub id: String,
+    /// Fields after the identifier, `None` for `-`
+    pub fields: Vec<Option<String>>,
+}
+
+impl MtfSet {
+    /// Value of field `index`, counting from 0 after the set identifier
+    pub fn field(&self, index: usize) -> Option<&str> {
+        self.fields.get(index)?.as_deref()
+    }
+
+    fn parse(text: &str) -> Self {
+        let text = text.trim();
+        let (id, rest) = text.split_once('/').unwrap_or((text, ""));
+        let id = id.trim().to_string();
+        let field = |value: &str| match value {
+            "-" => None,
+            _ => Some(value.to_string()),
+        };
+        let fields = match id.as_str() {
+            "GENTEXT" => match rest.split_once('/') {
+                Some((indicator, text)) => vec![field(indicator), field(text)],
+                None => vec![field(rest)],
+            },
+            _ if FREE_TEXT_SETS.contains(&id.as_str()) => vec![field(rest)],
+            _ if text.len() == id.len() => vec![],
+            _ => rest
+                .replace(['\r', '\n'], "")
+                .split('/')
+                .map(field)
+                .collect(),
+        };
+        Self { id, fields }
+    }
+}
+
+/// USMTF message, as a sequence of sets
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MtfMessage {
+    pub sets: Vec<MtfSet>,
+}
+
+impl MtfMessage {
+    /// Split message text into sets and fields
+    pub fn parse(text: &str) -> NitfResult<Self> {
+        let mut sets = vec![];
+        let mut rest = text;
+        while let Some(end) = rest.find("//") {
+            sets.push(MtfSet::parse(&rest[..end]));
+            rest = &rest[end + 2..];
+        }
+        if !rest.trim().is_empty() {
+            Err(NitfError::ParseError(format!(
+                "USMTF set is not terminated with //: {}",
+                rest.trim()
+            )))?
+        }
+        if sets.is_empty() {
+            Err(NitfError::ParseError(
+                "USMTF message has no sets".to_string(),
+            ))?
+        }
+        Ok(Self { sets })
+    }
+
+    /// First set with identifier `id`
+    pub fn set(&self, id: &str) -> Option<&MtfSet> {
+        self.sets.iter().find(|set| set.id == id)
+    }
+
+    /// All sets with identifier `id`, in message order
+    pub fn sets_with_id<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a MtfSet> {
+        self.sets.iter().filter(move |set| set.id == id)
+    }
+
+    /// Message text format identifier, the first field of the MSGID set
+    pub fn identifier(&self) -> Option<&str> {
+        self.set("MSGID")?.field(0)
+    }
+}
+
+impl TextSegment {
+    /// Read and parse the USMTF message of an `MTF` segment. `None` for other
+    /// text formats.
+    pub fn read_mtf(&self, reader: &mut (impl Read + Seek)) -> NitfResult<Option<MtfMessage>> {
+        if self.header.txtfmt.val != TextFormat::MTF {
+            return Ok(None);
+        }
+        let decoded = self.read_text(reader)?;
+        if !decoded.invalid.is_empty() {
+            warn!(
+                "USMTF message has {} characters outside of BCS",
+                decoded.invalid.len()
+            );
+        }
+        MtfMessage::parse(&decoded.text).map(Some)
+    }
+}